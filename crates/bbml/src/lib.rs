@@ -1,47 +1,261 @@
 //! Renders [BbML](https://blackboard.github.io/rest-apis/learn/advanced/bbml) (a subset of HTML) to styled text for [`ratatui`]
+use std::{cell::Cell, collections::HashSet};
+
 use log::debug;
 use ratatui::{
+    layout::Alignment,
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
     widgets::{Paragraph, Wrap},
 };
-use tl::{HTMLTag, Node, NodeHandle, VDom};
-
-const SCREEN_WIDTH: usize = 70;
-const TABLE_VERTICAL_BORDER: char = '─';
-const TABLE_MID_LEFT_BORDER: char = '├';
-const TABLE_MID_INTERSECT: char = '┼';
-const TABLE_MID_RIGHT_BORDER: char = '┤';
-const TABLE_TOP_LEFT_BORDER: char = '┌';
-const TABLE_TOP_INTERSECT: char = '┬';
-const TABLE_BOT_INTERSECT: char = '┴';
-const TABLE_TOP_RIGHT_BORDER: char = '┐';
-const TABLE_BOT_LEFT_BORDER: char = '└';
-const TABLE_BOT_RIGHT_BORDER: char = '┘';
-const TABLE_HORIZ_BORDER: char = '│';
+use tl::{HTMLTag, Node, NodeHandle, VDom, VDomGuard};
+use unicode_width::UnicodeWidthChar;
+
+/// Options that change how bbml is rendered
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// The width of the area the text will be displayed in.
+    /// Tables will be shrunk to fit inside this if possible.
+    pub width: usize,
+
+    /// Which characters to draw table borders with
+    pub table_borders: TableBorders,
+
+    /// The URL relative links are resolved against, ie the root of the Learn instance.
+    /// If this isn't set, links are left as they are.
+    pub link_base: Option<String>,
+
+    /// The colours to use
+    pub theme: Theme,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            width: 70,
+            table_borders: Default::default(),
+            link_base: None,
+            theme: Default::default(),
+        }
+    }
+}
+
+/// The colours used when rendering
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Links
+    pub link: Color,
+
+    /// The underline of `h4` headings
+    pub heading_underline: Color,
+
+    /// Placeholders for embedded content, ie iframes
+    pub embed: Color,
+
+    /// Placeholders for audio and video
+    pub media: Color,
+
+    /// Tags we don't know how to render
+    pub unknown: Color,
+}
+
+impl Theme {
+    /// For terminals with a dark background
+    pub const DARK: Theme = Theme {
+        link: Color::Blue,
+        heading_underline: Color::White,
+        embed: Color::Magenta,
+        media: Color::Green,
+        unknown: Color::Red,
+    };
+
+    /// For terminals with a light background
+    pub const LIGHT: Theme = Theme {
+        link: Color::Blue,
+        heading_underline: Color::Black,
+        embed: Color::Magenta,
+        media: Color::Green,
+        unknown: Color::Red,
+    };
+
+    /// Brighter colours, for readability
+    pub const HIGH_CONTRAST: Theme = Theme {
+        link: Color::LightCyan,
+        heading_underline: Color::White,
+        embed: Color::LightMagenta,
+        media: Color::LightGreen,
+        unknown: Color::LightRed,
+    };
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DARK
+    }
+}
+
+/// The character set used for drawing table borders
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TableBorders {
+    /// Unicode box-drawing characters
+    #[default]
+    Unicode,
+
+    /// Plain ASCII, for terminals which can't display unicode properly
+    Ascii,
+}
+
+impl TableBorders {
+    fn chars(&self) -> &'static BorderChars {
+        match self {
+            TableBorders::Unicode => &UNICODE_BORDERS,
+            TableBorders::Ascii => &ASCII_BORDERS,
+        }
+    }
+}
+
+/// The actual characters used to draw a table
+struct BorderChars {
+    /// Horizontal line, used at the top and bottom of rows
+    straight: char,
+    /// Vertical line, used between cells
+    side: char,
+    top_left: char,
+    top_intersect: char,
+    top_right: char,
+    mid_left: char,
+    mid_intersect: char,
+    mid_right: char,
+    bot_left: char,
+    bot_intersect: char,
+    bot_right: char,
+}
+
+const UNICODE_BORDERS: BorderChars = BorderChars {
+    straight: '─',
+    side: '│',
+    top_left: '┌',
+    top_intersect: '┬',
+    top_right: '┐',
+    mid_left: '├',
+    mid_intersect: '┼',
+    mid_right: '┤',
+    bot_left: '└',
+    bot_intersect: '┴',
+    bot_right: '┘',
+};
+
+const ASCII_BORDERS: BorderChars = BorderChars {
+    straight: '-',
+    side: '|',
+    top_left: '+',
+    top_intersect: '+',
+    top_right: '+',
+    mid_left: '+',
+    mid_intersect: '+',
+    mid_right: '+',
+    bot_left: '+',
+    bot_intersect: '+',
+    bot_right: '+',
+};
 
 /// Render the given bbml as best as possible.
 /// Returns the rendered text as a paragraph, and a list of links inside that text
 pub fn render(html: &str) -> (Paragraph<'static>, Vec<String>) {
-    let mut state = RenderState::new(html);
+    render_with_options(html, &RenderOptions::default())
+}
+
+/// Render the given bbml as best as possible, using the given options.
+/// Returns the rendered text as a paragraph, and a list of links inside that text
+pub fn render_with_options(html: &str, opts: &RenderOptions) -> (Paragraph<'static>, Vec<String>) {
+    let (text, links) = render_text_with_options(html, opts);
+
+    (Paragraph::new(text).wrap(Wrap { trim: false }), links)
+}
+
+/// Render the given bbml as best as possible, using the given options.
+/// Returns the rendered text, which should be displayed with wrapping but without trimming, and a list of links inside that text.
+pub fn render_text_with_options(html: &str, opts: &RenderOptions) -> (Text<'static>, Vec<String>) {
+    let dom = tl::parse(html, tl::ParserOptions::default()).unwrap();
+
+    render_dom(&dom, opts)
+}
+
+/// Parse the given bbml, so it can be rendered several times (ie at different widths) without parsing it again.
+pub fn parse(html: &str) -> ParsedDoc {
+    // SAFETY: the guard owns the input string, and only hands out references to the DOM that are bound to its own lifetime
+    let dom = unsafe { tl::parse_owned(html.to_string(), tl::ParserOptions::default()) }.unwrap();
+
+    ParsedDoc { dom }
+}
+
+/// bbml which has already been parsed. See [`parse`]
+#[derive(Debug)]
+pub struct ParsedDoc {
+    dom: VDomGuard,
+}
+
+impl ParsedDoc {
+    /// Render this document as best as possible, using the given options.
+    /// Returns the same as [`render_text_with_options`].
+    pub fn render(&self, opts: &RenderOptions) -> (Text<'static>, Vec<String>) {
+        render_dom(self.dom.get_ref(), opts)
+    }
+}
+
+/// Render an already-parsed DOM, and clean up the output.
+fn render_dom(dom: &VDom<'_>, opts: &RenderOptions) -> (Text<'static>, Vec<String>) {
+    let mut state = RenderState::new(dom, opts);
     let (mut text, links) = state.render();
 
     cleanup(&mut text);
 
-    (Paragraph::new(text).wrap(Wrap { trim: false }), links)
+    (text, links)
+}
+
+/// Render the given bbml as plain text, without any styling, using the given options.
+/// Returns the text, and a list of links inside that text.
+pub fn render_to_plaintext(html: &str, opts: &RenderOptions) -> (String, Vec<String>) {
+    let (text, links) = render_text_with_options(html, opts);
+    let plain = text
+        .lines
+        .iter()
+        .map(|l| {
+            l.spans
+                .iter()
+                .map(|s| s.content.as_ref())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    (plain, links)
 }
 
 /// State needed throughout the rendering process
-struct RenderState<'a> {
+struct RenderState<'a, 'h> {
     /// Handle into our DOM, since [`tl`] is 0-copy
-    dom: VDom<'a>,
+    dom: &'a VDom<'h>,
+
+    /// Options we were given
+    opts: &'a RenderOptions,
+
+    /// Whether we've rendered any form controls, which can't be used here
+    has_controls: Cell<bool>,
+
+    /// Whether we're inside a link, since nested links are invalid
+    in_link: Cell<bool>,
 }
 
-impl<'a> RenderState<'a> {
-    /// Initialise render state with the given HTML
-    fn new(html: &'a str) -> RenderState<'a> {
-        let dom = tl::parse(html, tl::ParserOptions::default()).unwrap();
-        Self { dom }
+impl<'a, 'h> RenderState<'a, 'h> {
+    /// Initialise render state with the given DOM
+    fn new(dom: &'a VDom<'h>, opts: &'a RenderOptions) -> RenderState<'a, 'h> {
+        Self {
+            dom,
+            opts,
+            has_controls: Cell::new(false),
+            in_link: Cell::new(false),
+        }
     }
 
     /// Render everything into a text object
@@ -59,25 +273,81 @@ impl<'a> RenderState<'a> {
             self.render_internal(&mut out, child, Style::default());
         }
 
+        if self.has_controls.get() {
+            out.ensure_line_empty();
+            out.newline();
+            out.append(Span::styled(
+                "This page has buttons or form fields - open it in your browser to use them.",
+                Style::new().add_modifier(Modifier::DIM | Modifier::ITALIC),
+            ));
+        }
+
         (text, links)
     }
 
+    /// Get the items of a list with the given children, as the nodes to render for each.
+    /// `<li>`s which aren't closed end up containing the next item, so those are split back out.
+    fn list_items(&self, children: &[NodeHandle]) -> Vec<Vec<NodeHandle>> {
+        let mut items = vec![];
+        for child in children.iter() {
+            match child.get(self.dom.parser()) {
+                Some(Node::Tag(t)) if t.name().as_utf8_str() == "li" => {
+                    let (nested, own): (Vec<NodeHandle>, Vec<NodeHandle>) =
+                        t.children().top().iter().partition(|c| {
+                            matches!(c.get(self.dom.parser()), Some(Node::Tag(t)) if t.name().as_utf8_str() == "li")
+                        });
+                    items.push(own);
+                    items.extend(self.list_items(&nested));
+                }
+                _ => items.push(vec![*child]),
+            }
+        }
+
+        items
+    }
+
     /// Actual internal rendering function
     fn render_internal(&self, out: &mut RenderOutput, handle: &NodeHandle, curr_style: Style) {
         let node = handle.get(self.dom.parser()).unwrap();
         match node {
             Node::Tag(t) => {
-                let tag_name = &*t.name().as_utf8_str();
+                // Self-closing tags without a space, ie `<br/>`, keep the slash in their name
+                let tag_name = t.name().as_utf8_str();
+                let tag_name = tag_name.trim_end_matches('/');
                 let c = t.children();
                 let children = c.top();
                 match tag_name {
-                    "br" => out.newline(),
+                    // Void elements
+                    "br" | "hr" | "img" => {
+                        match tag_name {
+                            "br" => out.newline(),
+                            "hr" => {
+                                out.ensure_line_empty();
+                                out.append(Span::styled(
+                                    self.opts
+                                        .table_borders
+                                        .chars()
+                                        .straight
+                                        .to_string()
+                                        .repeat(self.opts.width),
+                                    curr_style.add_modifier(Modifier::DIM),
+                                ));
+                                out.newline();
+                            }
+                            _ => (),
+                        }
+
+                        // These can't have children, but when self-closed without a space, whatever comes after ends up inside them
+                        for child in children.iter() {
+                            self.render_internal(out, child, curr_style);
+                        }
+                    }
 
                     // Block text elements, which force their own line and may change the style
                     "h4" | "h5" | "h6" | "div" | "p" => {
                         let new_style = match tag_name {
                             "h4" => curr_style
-                                .underline_color(Color::White)
+                                .underline_color(self.opts.theme.heading_underline)
                                 .add_modifier(Modifier::BOLD),
                             "h5" | "h6" => curr_style.add_modifier(Modifier::BOLD),
                             "div" | "p" => curr_style,
@@ -91,12 +361,35 @@ impl<'a> RenderState<'a> {
                         out.ensure_line_empty();
                     }
 
+                    // Figures, which are padded with blank lines like other block media
+                    "figure" => {
+                        if !out.text.lines.is_empty() {
+                            out.ensure_line_empty();
+                            out.newline();
+                        }
+                        for child in children.iter() {
+                            self.render_internal(out, child, curr_style);
+                        }
+                        out.ensure_line_empty();
+                        out.newline();
+                    }
+                    "figcaption" => {
+                        let new_style = curr_style.add_modifier(Modifier::ITALIC);
+                        out.ensure_line_empty();
+                        out.append(Span::styled("Figure: ", new_style));
+                        for child in children.iter() {
+                            self.render_internal(out, child, new_style);
+                        }
+                        out.ensure_line_empty();
+                    }
+
                     // Inline text elements, which at most change the style
                     // td is here because we deal with it at the tr level (see further down)
-                    "span" | "strong" | "em" | "li" | "td" | "th" => {
+                    "span" | "strong" | "b" | "em" | "i" | "u" | "li" | "td" | "th" => {
                         let new_style = match tag_name {
-                            "strong" => curr_style.add_modifier(Modifier::BOLD),
-                            "em" => curr_style.add_modifier(Modifier::ITALIC),
+                            "strong" | "b" => curr_style.add_modifier(Modifier::BOLD),
+                            "em" | "i" => curr_style.add_modifier(Modifier::ITALIC),
+                            "u" => curr_style.add_modifier(Modifier::UNDERLINED),
                             _ => curr_style,
                         };
 
@@ -105,49 +398,212 @@ impl<'a> RenderState<'a> {
                         }
                     }
 
+                    // Form controls, which we can only show placeholders for
+                    "form" | "label" => {
+                        for child in children.iter() {
+                            self.render_internal(out, child, curr_style);
+                        }
+                    }
+                    "button" | "textarea" => {
+                        self.has_controls.set(true);
+                        let new_style = curr_style.add_modifier(Modifier::DIM);
+                        let label = collapse_whitespace(&t.inner_text(self.dom.parser()));
+                        let placeholder = match (tag_name, label.is_empty()) {
+                            ("button", _) => format!("[ {} ]", label),
+                            (_, true) => "[____]".to_string(),
+                            (_, false) => format!("[{}]", label),
+                        };
+                        out.append(Span::styled(placeholder, new_style));
+                    }
+                    "select" => {
+                        self.has_controls.set(true);
+                        let new_style = curr_style.add_modifier(Modifier::DIM);
+                        let selected = t
+                            .query_selector(self.dom.parser(), "option[selected]")
+                            .and_then(|mut q| q.next())
+                            .or_else(|| {
+                                t.query_selector(self.dom.parser(), "option")
+                                    .and_then(|mut q| q.next())
+                            })
+                            .and_then(|h| h.get(self.dom.parser()))
+                            .map(|n| collapse_whitespace(&n.inner_text(self.dom.parser())))
+                            .unwrap_or_default();
+                        out.append(Span::styled(format!("[{} ▾]", selected), new_style));
+                    }
+                    "input" => {
+                        let attr = |name: &str| {
+                            t.attributes()
+                                .get(name)
+                                .flatten()
+                                .map(|b| b.as_utf8_str().trim().to_string())
+                        };
+                        let ty = attr("type").unwrap_or_default().to_ascii_lowercase();
+                        let value = attr("value").filter(|v| !v.is_empty());
+                        let checked = t.attributes().contains("checked");
+                        let placeholder = match ty.as_str() {
+                            "hidden" => None,
+                            "submit" | "button" | "reset" => Some(format!(
+                                "[ {} ]",
+                                value.unwrap_or_else(|| "Submit".to_string())
+                            )),
+                            "checkbox" => Some(if checked { "[x]" } else { "[ ]" }.to_string()),
+                            "radio" => Some(if checked { "(•)" } else { "( )" }.to_string()),
+                            "password" => Some(value.map_or("[____]".to_string(), |v| {
+                                format!("[{}]", "*".repeat(v.chars().count()))
+                            })),
+                            _ => Some(value.map_or("[____]".to_string(), |v| format!("[{}]", v))),
+                        };
+                        if let Some(placeholder) = placeholder {
+                            self.has_controls.set(true);
+                            out.append(Span::styled(
+                                placeholder,
+                                curr_style.add_modifier(Modifier::DIM),
+                            ));
+                        }
+                    }
+
                     // Links
                     "a" => {
-                        let new_style = curr_style.fg(Color::Blue);
+                        // Browsers close the outer link instead, but keeping it is close enough
+                        if self.in_link.get() {
+                            for child in children.iter() {
+                                self.render_internal(out, child, curr_style);
+                            }
+                            return;
+                        }
+
+                        let new_style = curr_style.fg(self.opts.theme.link);
+                        let start = out.position();
+                        self.in_link.set(true);
                         for child in children.iter() {
                             self.render_internal(out, child, new_style);
                         }
-                        if let Some(Some(b)) = t.attributes().get("href") {
-                            let href = b.as_utf8_str().to_string();
+                        self.in_link.set(false);
+                        let href = t.attributes().get("href").flatten().map(|b| {
+                            resolve_href(&b.as_utf8_str(), self.opts.link_base.as_deref())
+                        });
+
+                        // Links with no text (ie just an icon) would be invisible, so show something
+                        if out.whitespace_since(start) {
+                            let title = t
+                                .attributes()
+                                .get("title")
+                                .flatten()
+                                .map(|b| b.as_utf8_str().trim().to_string())
+                                .filter(|s| !s.is_empty());
+                            if let Some(label) = title.or_else(|| href.as_deref().map(short_href)) {
+                                out.append(Span::styled(label, new_style));
+                            }
+                        }
+
+                        if let Some(href) = href {
                             let idx = out.add_link(href);
 
                             out.append(Span::styled(format!("[{idx}]"), new_style));
                         }
                     }
 
+                    // Embedded content, which we can't show but can link to
+                    "iframe" => {
+                        let src = t
+                            .attributes()
+                            .get("src")
+                            .flatten()
+                            .map(|b| b.as_utf8_str().trim().to_string())
+                            .filter(|s| !s.is_empty() && s != "about:blank");
+                        if let Some(src) = src {
+                            let new_style = curr_style.fg(self.opts.theme.embed);
+                            out.ensure_line_empty();
+                            out.append(Span::styled(
+                                format!("[embedded content: {}]", href_host(&src)),
+                                new_style,
+                            ));
+                            let idx = out.add_link(src);
+                            out.append(Span::styled(format!("[{idx}]"), new_style));
+                            out.ensure_line_empty();
+                        }
+                    }
+
+                    // Audio and video, which can be opened externally
+                    "audio" | "video" => {
+                        let src_of = |t: &HTMLTag| {
+                            t.attributes()
+                                .get("src")
+                                .flatten()
+                                .map(|b| b.as_utf8_str().trim().to_string())
+                                .filter(|s| !s.is_empty())
+                        };
+                        let src = src_of(t).or_else(|| {
+                            t.query_selector(self.dom.parser(), "source[src]")?
+                                .filter_map(|h| h.get(self.dom.parser())?.as_tag().and_then(src_of))
+                                .next()
+                        });
+
+                        // Without a source, show the fallback text instead
+                        let Some(src) = src else {
+                            for child in children.iter() {
+                                self.render_internal(out, child, curr_style);
+                            }
+                            return;
+                        };
+
+                        let src = resolve_href(&src, self.opts.link_base.as_deref());
+                        let new_style = curr_style.fg(self.opts.theme.media);
+                        out.ensure_line_empty();
+                        out.append(Span::styled(
+                            format!("[{}: {}]", tag_name, href_host(&src)),
+                            new_style,
+                        ));
+                        let idx = out.add_link(src);
+                        out.append(Span::styled(format!("[{idx}]"), new_style));
+                        out.ensure_line_empty();
+                    }
+
                     // Lists
                     "ul" | "ol" => {
                         // Function for getting next label
                         let mut next_item: Box<dyn FnMut() -> String> = match tag_name {
                             "ul" => Box::new(|| "  - ".to_string()),
                             "ol" => {
-                                let mut i = 0;
+                                let attrs = t.attributes();
+                                let mut i = attrs
+                                    .get("start")
+                                    .flatten()
+                                    .and_then(|s| s.as_utf8_str().trim().parse::<usize>().ok())
+                                    .unwrap_or(1);
+                                let numbering = attrs
+                                    .get("type")
+                                    .flatten()
+                                    .map(|s| s.as_utf8_str().to_string())
+                                    .unwrap_or_default();
                                 Box::new(move || {
+                                    let label = format!("{}. ", list_number(i, &numbering));
                                     i += 1;
-                                    format!("{}. ", i)
+                                    label
                                 })
                             }
                             _ => unreachable!(),
                         };
 
-                        for child in children.iter() {
+                        for item in self.list_items(children.as_slice()) {
                             // Render into new text object
                             let mut subtext = Text::raw("");
                             let mut suboutp = out.with_subtext(&mut subtext);
-                            let child_node = child.get(self.dom.parser()).unwrap();
-                            self.render_internal(&mut suboutp, child, curr_style);
+                            for handle in item.iter() {
+                                self.render_internal(&mut suboutp, handle, curr_style);
+                            }
 
                             if suboutp.empty_or_whitespace() {
                                 continue;
                             }
 
+                            let child_node = match item.as_slice() {
+                                [handle] => handle.get(self.dom.parser()),
+                                _ => None,
+                            };
                             match child_node {
                                 // Sublists don't use <li>s
-                                Node::Tag(t)
+                                Some(Node::Tag(t))
                                     if t.name().as_utf8_str() == "ul"
                                         || t.name().as_utf8_str() == "ol" =>
                                 {
@@ -156,12 +612,25 @@ impl<'a> RenderState<'a> {
                                     subtext.lines.pop();
                                     subtext.lines.pop();
 
-                                    // Don't use label, just indent further
+                                    // Don't use label, just indent so its labels start a bit after ours
+                                    let indent = " ".repeat(
+                                        list_label_indent(tag_name) + 2
+                                            - list_label_indent(&t.name().as_utf8_str()),
+                                    );
                                     for i in 0..subtext.lines.len() {
-                                        subtext.lines[i].spans.insert(0, Span::raw("  "));
+                                        subtext.lines[i].spans.insert(0, Span::raw(indent.clone()));
                                     }
                                 }
                                 _ => {
+                                    // Drop padding left by anything nested inside the item
+                                    while subtext.lines.len() > 1
+                                        && subtext.lines.last().is_some_and(|l| {
+                                            l.spans.iter().all(|s| s.content.trim().is_empty())
+                                        })
+                                    {
+                                        subtext.lines.pop();
+                                    }
+
                                     // Add label at top, and indent other lines
                                     subtext.lines[0].spans.insert(0, Span::raw(next_item()));
                                     for i in 1..subtext.lines.len() {
@@ -180,17 +649,44 @@ impl<'a> RenderState<'a> {
 
                     // Tables
                     "table" => {
+                        // Captions go above the table, once we know how wide it is
+                        let mut caption = Text::default();
+                        for child in children.iter() {
+                            if let Some(Node::Tag(c)) = child.get(self.dom.parser()) {
+                                if c.name().as_utf8_str() == "caption" {
+                                    let new_style = curr_style.add_modifier(Modifier::BOLD);
+                                    let mut suboutp = out.with_subtext(&mut caption);
+                                    for caption_child in c.children().top().iter() {
+                                        self.render_internal(
+                                            &mut suboutp,
+                                            caption_child,
+                                            new_style,
+                                        );
+                                    }
+                                }
+                            }
+                        }
+                        cleanup(&mut caption);
+                        trim_blank_lines(&mut caption);
+
                         // Render each cell
                         let mut subtexts: Vec<Vec<Text<'static>>> = vec![];
-                        self.render_table_cells(out, t, &mut subtexts);
+                        let mut nested_cols = HashSet::new();
+                        self.render_table_cells(out, t, &mut subtexts, &mut nested_cols);
 
                         debug!("{:?}", subtexts);
 
-                        // Ensure table is a square
+                        // Ensure table is a square.
+                        // Rows with fewer cells are padded with empty cells at the end, since we can't tell if they were meant to be offset.
                         let max_cols = subtexts.iter().map(Vec::len).max().unwrap_or(0);
+                        if max_cols == 0 || subtexts.iter().flatten().all(|c| c.width() == 0) {
+                            // Nothing to draw borders around
+                            push_caption(out, caption, 0);
+                            return;
+                        }
                         subtexts
                             .iter_mut()
-                            .for_each(|v| v.resize(max_cols, "".into()));
+                            .for_each(|v| v.resize(max_cols, Text::default()));
 
                         // Figure out the dimensions of everything
                         let mut col_widths = (0..max_cols)
@@ -205,14 +701,17 @@ impl<'a> RenderState<'a> {
                             .collect::<Vec<_>>();
 
                         let total_width = col_widths.iter().sum::<usize>() + col_widths.len() + 1;
+                        // Nested tables would be mangled by wrapping, so never pick those columns to shrink
                         let (widest_col_idx, &max_width) = col_widths
                             .iter()
                             .enumerate()
+                            .filter(|(i, _)| !nested_cols.contains(i))
                             .max_by_key(|(_, w)| **w)
                             .unwrap_or((0, &0));
                         // Attempt to shrink largest column if we need to
-                        if total_width > SCREEN_WIDTH && max_width > (total_width - SCREEN_WIDTH) {
-                            let new_width = max_width - (total_width - SCREEN_WIDTH);
+                        let screen_width = self.opts.width;
+                        if total_width > screen_width && max_width > (total_width - screen_width) {
+                            let new_width = max_width - (total_width - screen_width);
                             col_widths[widest_col_idx] = new_width;
 
                             for row in subtexts.iter_mut() {
@@ -222,41 +721,61 @@ impl<'a> RenderState<'a> {
 
                         let row_heights = subtexts
                             .iter()
-                            .map(|row| row.iter().map(|cell| cell.height()).max().unwrap_or(0))
+                            .map(|row| {
+                                row.iter()
+                                    .map(|cell| cell.height())
+                                    .max()
+                                    .unwrap_or(0)
+                                    .max(1)
+                            })
                             .collect::<Vec<_>>();
 
                         // Now we can output our table with the right dimensions
+                        let borders = self.opts.table_borders.chars();
+                        push_caption(
+                            out,
+                            caption,
+                            col_widths.iter().sum::<usize>() + col_widths.len() + 1,
+                        );
                         out.ensure_line_empty();
 
                         out.append(table_vertical_border(
                             &col_widths,
-                            TABLE_TOP_LEFT_BORDER,
-                            TABLE_VERTICAL_BORDER,
-                            TABLE_TOP_INTERSECT,
-                            TABLE_TOP_RIGHT_BORDER,
+                            borders.top_left,
+                            borders.straight,
+                            borders.top_intersect,
+                            borders.top_right,
                         ));
                         let n_rows = subtexts.len();
                         for (row_idx, row) in subtexts.into_iter().enumerate() {
                             // append however many lines in this row to work with
                             let row_height = row_heights[row_idx];
                             let row_start_idx = out.text.lines.len();
-                            (0..row_height).for_each(|_| {
-                                out.text.lines.push(TABLE_HORIZ_BORDER.to_string().into())
-                            });
+                            (0..row_height)
+                                .for_each(|_| out.text.lines.push(borders.side.to_string().into()));
 
                             for (col_idx, cell) in row.into_iter().enumerate() {
                                 let col_width = col_widths[col_idx];
                                 let added_to_lines = cell.lines.len();
 
                                 // add to the end of the existing lines, padding if needed
+                                // the cell's alignment is stored on each of its lines
                                 for (line_idx, line) in cell.lines.into_iter().enumerate() {
-                                    let adding_width = line.width();
+                                    let padding = col_width.saturating_sub(line.width());
+                                    let (before, after) = match line.alignment {
+                                        Some(Alignment::Right) => (padding, 0),
+                                        Some(Alignment::Center) => {
+                                            (padding / 2, padding - padding / 2)
+                                        }
+                                        _ => (0, padding),
+                                    };
                                     let add_to_line = &mut out.text.lines[row_start_idx + line_idx];
+                                    if before > 0 {
+                                        add_to_line.spans.push(" ".repeat(before).into());
+                                    }
                                     add_to_line.spans.extend(line.spans);
-                                    if adding_width < col_width {
-                                        add_to_line
-                                            .spans
-                                            .push(" ".repeat(col_width - adding_width).into());
+                                    if after > 0 {
+                                        add_to_line.spans.push(" ".repeat(after).into());
                                     }
                                 }
 
@@ -271,7 +790,7 @@ impl<'a> RenderState<'a> {
                                 (0..row_height).for_each(|i| {
                                     out.text.lines[row_start_idx + i]
                                         .spans
-                                        .push(TABLE_HORIZ_BORDER.to_string().into())
+                                        .push(borders.side.to_string().into())
                                 });
                             }
 
@@ -279,10 +798,10 @@ impl<'a> RenderState<'a> {
                                 out.ensure_line_empty();
                                 out.append(table_vertical_border(
                                     &col_widths,
-                                    TABLE_MID_LEFT_BORDER,
-                                    TABLE_VERTICAL_BORDER,
-                                    TABLE_MID_INTERSECT,
-                                    TABLE_MID_RIGHT_BORDER,
+                                    borders.mid_left,
+                                    borders.straight,
+                                    borders.mid_intersect,
+                                    borders.mid_right,
                                 ));
                             }
                         }
@@ -290,10 +809,10 @@ impl<'a> RenderState<'a> {
                         out.ensure_line_empty();
                         out.append(table_vertical_border(
                             &col_widths,
-                            TABLE_BOT_LEFT_BORDER,
-                            TABLE_VERTICAL_BORDER,
-                            TABLE_BOT_INTERSECT,
-                            TABLE_BOT_RIGHT_BORDER,
+                            borders.bot_left,
+                            borders.straight,
+                            borders.bot_intersect,
+                            borders.bot_right,
                         ));
                     }
 
@@ -304,7 +823,9 @@ impl<'a> RenderState<'a> {
                             self.render_internal(
                                 out,
                                 child,
-                                curr_style.fg(Color::Red).underline_color(Color::Red),
+                                curr_style
+                                    .fg(self.opts.theme.unknown)
+                                    .underline_color(self.opts.theme.unknown),
                             )
                         })
                     }
@@ -335,29 +856,57 @@ impl<'a> RenderState<'a> {
         out: &mut RenderOutput<'_>,
         table: &HTMLTag<'_>,
         cells: &mut Vec<Vec<Text<'static>>>,
+        nested_cols: &mut HashSet<usize>,
     ) {
         for row_handle in table.children().top().iter() {
             if let Node::Tag(row) = row_handle.get(self.dom.parser()).unwrap() {
                 match &*row.name().as_utf8_str() {
-                    "thead" | "tbody" => {
-                        self.render_table_cells(out, row, cells);
+                    "thead" | "tbody" | "tfoot" => {
+                        self.render_table_cells(out, row, cells, nested_cols);
                     }
+                    "caption" => (),
                     _ => {
+                        // Every row is kept, even if it's empty, so the table doesn't lose any
                         let mut cols = vec![];
                         for cell in row.children().top().iter() {
+                            // Whitespace between cells isn't a cell itself
+                            if !matches!(cell.get(self.dom.parser()), Some(Node::Tag(_))) {
+                                continue;
+                            }
+
                             let mut subtext = Text::default();
                             let mut suboutp = out.with_subtext(&mut subtext);
                             self.render_internal(&mut suboutp, cell, Style::new());
 
-                            if subtext.width() == 0 || subtext.height() == 0 {
+                            // Empty cells still take up a column, so the ones after them line up
+                            if subtext.width() == 0 {
+                                cols.push(Text::default());
                                 continue;
                             }
                             cleanup(&mut subtext);
+                            trim_blank_lines(&mut subtext);
+
+                            if let Some(Node::Tag(cell_tag)) = cell.get(self.dom.parser()) {
+                                if let Some(alignment) = cell_alignment(cell_tag) {
+                                    subtext
+                                        .lines
+                                        .iter_mut()
+                                        .for_each(|l| l.alignment = Some(alignment));
+                                }
+                            }
+
+                            // Nested tables are rendered into the cell as a self-contained block
+                            if let Some(Node::Tag(cell_tag)) = cell.get(self.dom.parser()) {
+                                if cell_tag
+                                    .query_selector(self.dom.parser(), "table")
+                                    .is_some_and(|mut q| q.next().is_some())
+                                {
+                                    nested_cols.insert(cols.len());
+                                }
+                            }
                             cols.push(subtext);
                         }
-                        if !cols.is_empty() {
-                            cells.push(cols);
-                        }
+                        cells.push(cols);
                     }
                 }
             }
@@ -365,11 +914,36 @@ impl<'a> RenderState<'a> {
     }
 }
 
+/// Get the alignment a table cell asks for, from its `align` attribute or `text-align` style.
+fn cell_alignment(cell: &HTMLTag<'_>) -> Option<Alignment> {
+    let attrs = cell.attributes();
+    let attr = |name: &str| {
+        attrs
+            .get(name)
+            .flatten()
+            .map(|b| b.as_utf8_str().to_ascii_lowercase())
+    };
+
+    let align = attr("align").or_else(|| {
+        let style = attr("style")?;
+        let (_, rest) = style.split_once("text-align")?;
+        let (_, value) = rest.split_once(':')?;
+        Some(value.split(';').next()?.trim().to_string())
+    })?;
+    match align.trim() {
+        "left" => Some(Alignment::Left),
+        "right" => Some(Alignment::Right),
+        "center" | "centre" => Some(Alignment::Center),
+        _ => None,
+    }
+}
+
 fn wrap_text_to_width(text: &mut Text<'_>, new_width: usize) {
     let mut i = 0;
     while i < text.lines.len() {
         if text.lines[i].width() > new_width {
-            let new_line = chop_after(&mut text.lines[i], new_width);
+            let mut new_line = chop_after(&mut text.lines[i], new_width);
+            new_line.alignment = text.lines[i].alignment;
             text.lines.insert(i + 1, new_line);
         } else {
             i += 1;
@@ -381,13 +955,29 @@ fn chop_after<'a>(line: &mut Line<'a>, width: usize) -> Line<'a> {
     let mut cum_width = 0;
     for i in 0..line.spans.len() {
         if cum_width + line.spans[i].width() > width {
-            // split current span
+            // split current span at the last character boundary that fits.
+            // this has to go by display width, not chars, or wide characters (ie CJK) overflow.
             let keep = width - cum_width;
             let content = line.spans[i].content.clone();
-            line.spans[i].content = content.chars().take(keep).collect::<String>().into();
+            let mut split_at = content.len();
+            let mut kept_width = 0;
+            for (byte_idx, c) in content.char_indices() {
+                let c_width = c.width().unwrap_or(0);
+                if kept_width + c_width > keep {
+                    split_at = byte_idx;
+                    break;
+                }
+                kept_width += c_width;
+            }
+
+            // always make progress, even if a single character is wider than we have space for
+            if split_at == 0 && cum_width == 0 {
+                split_at = content.chars().next().map(|c| c.len_utf8()).unwrap_or(0);
+            }
 
+            line.spans[i].content = content[..split_at].to_string().into();
             let mut new_line = vec![Span::styled(
-                content.chars().skip(keep).collect::<String>(),
+                content[split_at..].to_string(),
                 line.spans[i].style,
             )];
             line.spans.drain(i + 1..).for_each(|s| new_line.push(s));
@@ -457,6 +1047,27 @@ impl<'a> RenderOutput<'a> {
         self.text.lines.is_empty() || self.text.lines[self.text.lines.len() - 1].spans.is_empty()
     }
 
+    /// Get the current end of the output, for use with [`Self::whitespace_since`]
+    fn position(&self) -> (usize, usize) {
+        (
+            self.text.lines.len(),
+            self.text.lines.last().map(|l| l.spans.len()).unwrap_or(0),
+        )
+    }
+
+    /// Check if everything output since the given position is empty or only whitespace
+    fn whitespace_since(&self, (line, span): (usize, usize)) -> bool {
+        let first_line = line.saturating_sub(1);
+        self.text.lines[first_line..]
+            .iter()
+            .enumerate()
+            .flat_map(|(i, l)| {
+                let skip = if i == 0 && line > 0 { span } else { 0 };
+                l.spans.iter().skip(skip)
+            })
+            .all(|s| s.content.trim().is_empty())
+    }
+
     /// Check if the given text is empty or only whitespace
     fn empty_or_whitespace(&mut self) -> bool {
         self.text
@@ -465,8 +1076,13 @@ impl<'a> RenderOutput<'a> {
             .all(|l| l.spans.iter().all(|s| s.content.is_empty()))
     }
 
-    /// Add a link to the encountered list, returning its index
+    /// Add a link to the encountered list, returning its index.
+    /// If the link has already been seen, its existing index is reused.
     fn add_link(&mut self, href: String) -> usize {
+        if let Some(idx) = self.links.iter().position(|l| *l == href) {
+            return idx;
+        }
+
         self.links.push(href);
         self.links.len() - 1
     }
@@ -482,6 +1098,142 @@ impl<'a> RenderOutput<'a> {
     }
 }
 
+/// How far the labels of the given list type are indented, ie `  - ` for `ul`s
+fn list_label_indent(tag_name: &str) -> usize {
+    match tag_name {
+        "ul" => 2,
+        _ => 0,
+    }
+}
+
+/// Format a number for an ordered list, following the `type` attribute of `<ol>`
+fn list_number(n: usize, numbering: &str) -> String {
+    match numbering {
+        "a" => alpha_number(n),
+        "A" => alpha_number(n).to_uppercase(),
+        "i" => roman_number(n),
+        "I" => roman_number(n).to_uppercase(),
+        _ => n.to_string(),
+    }
+}
+
+/// Format a number as a, b, ..., z, aa, ab, ...
+fn alpha_number(mut n: usize) -> String {
+    if n == 0 {
+        return "0".to_string();
+    }
+
+    let mut out = vec![];
+    while n > 0 {
+        n -= 1;
+        out.push((b'a' + (n % 26) as u8) as char);
+        n /= 26;
+    }
+    out.into_iter().rev().collect()
+}
+
+/// Format a number as lowercase roman numerals
+fn roman_number(mut n: usize) -> String {
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+    if n == 0 {
+        return "0".to_string();
+    }
+
+    let mut out = String::new();
+    for (value, numeral) in NUMERALS {
+        while n >= value {
+            out.push_str(numeral);
+            n -= value;
+        }
+    }
+    out
+}
+
+/// Shorten a link for display, removing the scheme and truncating long paths
+fn short_href(href: &str) -> String {
+    const MAX_LEN: usize = 30;
+    let href = href
+        .strip_prefix("https://")
+        .or_else(|| href.strip_prefix("http://"))
+        .unwrap_or(href);
+    if href.chars().count() > MAX_LEN {
+        format!("{}…", href.chars().take(MAX_LEN - 1).collect::<String>())
+    } else {
+        href.to_string()
+    }
+}
+
+/// Resolve a link against the given base URL, if it's relative.
+/// Links with a scheme (ie `https:` or `mailto:`) and links to anchors are left as they are.
+fn resolve_href(href: &str, base: Option<&str>) -> String {
+    let href = href.trim();
+    let has_scheme = href.split_once(':').is_some_and(|(scheme, _)| {
+        !scheme.is_empty()
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    });
+    let (Some(base), false) = (base, has_scheme || href.starts_with('#')) else {
+        return href.to_string();
+    };
+
+    if let Some(rest) = href.strip_prefix("//") {
+        // protocol-relative, so only take the scheme
+        let scheme = base.split_once("//").map_or("https:", |(s, _)| s);
+        format!("{}//{}", scheme, rest)
+    } else if let Some(path) = href.strip_prefix('/') {
+        // relative to the root of the site
+        let host_end = base
+            .split_once("//")
+            .and_then(|(s, rest)| rest.find('/').map(|i| s.len() + 2 + i))
+            .unwrap_or(base.len());
+        format!("{}/{}", &base[..host_end], path)
+    } else {
+        // relative to the base itself
+        format!("{}/{}", base.trim_end_matches('/'), href)
+    }
+}
+
+/// Get the host part of a link, or the whole thing if it doesn't look like a URL
+fn href_host(href: &str) -> &str {
+    let Some((_, rest)) = href.split_once("//") else {
+        return href;
+    };
+    rest.split(['/', '?', '#']).next().unwrap_or(rest)
+}
+
+/// Output a table's caption on its own lines, centred over a table of the given width.
+/// Captions wider than the table are left-aligned.
+fn push_caption(out: &mut RenderOutput<'_>, caption: Text<'static>, table_width: usize) {
+    if caption.lines.is_empty() {
+        return;
+    }
+
+    for line in caption.lines {
+        out.ensure_line_empty();
+        let padding = table_width.saturating_sub(line.width()) / 2;
+        if padding > 0 {
+            out.append(" ".repeat(padding).into());
+        }
+        line.spans.into_iter().for_each(|s| out.append(s));
+    }
+    out.ensure_line_empty();
+}
+
 /// Collapse all whitespace in a string
 fn collapse_whitespace(s: &str) -> String {
     let s = s.trim();
@@ -513,3 +1265,14 @@ fn cleanup(text: &mut Text<'static>) {
         text.lines.remove(text.lines.len() - 1);
     }
 }
+
+/// Remove all blank lines from the start and end of some text.
+/// Block elements like lists can leave several lines of padding, which would make table rows too tall.
+fn trim_blank_lines(text: &mut Text<'static>) {
+    let leading = text.lines.iter().take_while(|l| l.spans.is_empty()).count();
+    text.lines.drain(..leading);
+
+    while text.lines.last().is_some_and(|l| l.spans.is_empty()) {
+        text.lines.pop();
+    }
+}
@@ -1,4 +1,4 @@
-use bbml::render;
+use bbml::{render, render_with_options, RenderOptions};
 use ratatui::{
     style::{Color, Style},
     text::Span,
@@ -20,3 +20,159 @@ fn test_a_link() {
 
     assert_eq!(links, vec!["google.com".to_string()]);
 }
+
+#[test]
+fn test_a_link_relative() {
+    let opts = RenderOptions {
+        link_base: Some("https://www.learn.ed.ac.uk/".to_string()),
+        ..Default::default()
+    };
+    let (_, links) = render_with_options(
+        "<a href=\"/webapps/blackboard/content/listContent.jsp?course_id=_1_1\">root</a>\
+         <a href=\"bbcswebdav/pid-1/file.pdf\">relative</a>\
+         <a href=\"https://example.com/page\">absolute</a>\
+         <a href=\"mailto:someone@ed.ac.uk\">email</a>",
+        &opts,
+    );
+
+    assert_eq!(
+        links,
+        vec![
+            "https://www.learn.ed.ac.uk/webapps/blackboard/content/listContent.jsp?course_id=_1_1",
+            "https://www.learn.ed.ac.uk/bbcswebdav/pid-1/file.pdf",
+            "https://example.com/page",
+            "mailto:someone@ed.ac.uk",
+        ]
+    );
+}
+
+#[test]
+fn test_a_link_repeated() {
+    let (text, links) = render(
+        "<a href=\"google.com\">a link</a><a href=\"bing.com\">other</a><a href=\"google.com\">again</a>",
+    );
+    assert_eq!(
+        text,
+        Paragraph::new(vec![vec![
+            Span::styled("a link", Style::new().fg(Color::Blue)),
+            Span::styled("[0]", Style::new().fg(Color::Blue)),
+            Span::styled("other", Style::new().fg(Color::Blue)),
+            Span::styled("[1]", Style::new().fg(Color::Blue)),
+            Span::styled("again", Style::new().fg(Color::Blue)),
+            Span::styled("[0]", Style::new().fg(Color::Blue))
+        ]
+        .into(),])
+        .wrap(Wrap { trim: false })
+    );
+
+    assert_eq!(
+        links,
+        vec!["google.com".to_string(), "bing.com".to_string()]
+    );
+}
+
+#[test]
+fn test_a_link_empty_uses_title() {
+    let (text, links) = render("<a href=\"slides.pdf\" title=\"Lecture slides\"></a>");
+    assert_eq!(
+        text,
+        Paragraph::new(vec![vec![
+            Span::styled("Lecture slides", Style::new().fg(Color::Blue)),
+            Span::styled("[0]", Style::new().fg(Color::Blue))
+        ]
+        .into(),])
+        .wrap(Wrap { trim: false })
+    );
+
+    assert_eq!(links, vec!["slides.pdf".to_string()]);
+}
+
+#[test]
+fn test_a_link_empty_uses_href() {
+    let (text, _) = render("<a href=\"https://example.com/a\"> </a>");
+    assert_eq!(
+        text,
+        Paragraph::new(vec![vec![
+            Span::styled("example.com/a", Style::new().fg(Color::Blue)),
+            Span::styled("[0]", Style::new().fg(Color::Blue))
+        ]
+        .into(),])
+        .wrap(Wrap { trim: false })
+    );
+}
+
+#[test]
+fn test_iframe_link() {
+    let (text, links) = render(
+        "<p>before</p><iframe src=\"https://media.ed.ac.uk/embed/secure/iframe/entryId/1_abc\"></iframe><p>after</p>",
+    );
+    assert_eq!(
+        text,
+        Paragraph::new(vec![
+            vec![Span::styled("before", Style::new())].into(),
+            vec![
+                Span::styled(
+                    "[embedded content: media.ed.ac.uk]",
+                    Style::new().fg(Color::Magenta)
+                ),
+                Span::styled("[0]", Style::new().fg(Color::Magenta))
+            ]
+            .into(),
+            vec![Span::styled("after", Style::new())].into(),
+        ])
+        .wrap(Wrap { trim: false })
+    );
+
+    assert_eq!(
+        links,
+        vec!["https://media.ed.ac.uk/embed/secure/iframe/entryId/1_abc".to_string()]
+    );
+}
+
+#[test]
+fn test_iframe_blank_skipped() {
+    let (text, links) = render("<iframe src=\"about:blank\"></iframe><iframe></iframe>");
+    assert_eq!(
+        text,
+        Paragraph::new(Vec::<ratatui::text::Line>::new()).wrap(Wrap { trim: false })
+    );
+    assert!(links.is_empty());
+}
+
+#[test]
+fn test_video_source_link() {
+    let (text, links) = render(
+        "<p>Lecture 1</p><video controls><source src=\"https://media.ed.ac.uk/lecture1.mp4\" type=\"video/mp4\">Your browser can't play this</video>",
+    );
+    assert_eq!(
+        text,
+        Paragraph::new(vec![
+            vec![Span::styled("Lecture 1", Style::new())].into(),
+            vec![
+                Span::styled("[video: media.ed.ac.uk]", Style::new().fg(Color::Green)),
+                Span::styled("[0]", Style::new().fg(Color::Green))
+            ]
+            .into(),
+        ])
+        .wrap(Wrap { trim: false })
+    );
+    assert_eq!(
+        links,
+        vec!["https://media.ed.ac.uk/lecture1.mp4".to_string()]
+    );
+}
+
+#[test]
+fn test_audio_src() {
+    let (_, links) = render("<audio src=\"https://example.com/podcast.mp3\"></audio>");
+    assert_eq!(links, vec!["https://example.com/podcast.mp3".to_string()]);
+
+    // no source, so just the fallback
+    let (text, links) = render("<audio>No audio</audio>");
+    assert_eq!(
+        text,
+        Paragraph::new(vec![vec![Span::styled("No audio", Style::new())].into()])
+            .wrap(Wrap { trim: false })
+    );
+    assert!(links.is_empty());
+}
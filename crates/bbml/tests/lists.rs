@@ -1,4 +1,4 @@
-use bbml::render;
+use bbml::{render, render_to_plaintext, RenderOptions};
 use pretty_assertions::assert_eq;
 use ratatui::{
     prelude::*,
@@ -172,3 +172,120 @@ fn test_ol_nested() {
         .wrap(Wrap { trim: false })
     );
 }
+
+#[test]
+fn test_ol_start() {
+    assert_eq!(
+        render("<ol start=\"3\"><li>a</li><li>b</li></ol>").0,
+        Paragraph::new(vec![
+            vec![
+                Span::styled("3. ", Style::new()),
+                Span::styled("a", Style::new()),
+            ]
+            .into(),
+            vec![
+                Span::styled("4. ", Style::new()),
+                Span::styled("b", Style::new()),
+            ]
+            .into(),
+            vec![].into()
+        ])
+        .wrap(Wrap { trim: false })
+    );
+}
+
+#[test]
+fn test_ol_alpha() {
+    assert_eq!(
+        render("<ol type=\"a\"><li>a</li><li>b</li></ol>").0,
+        Paragraph::new(vec![
+            vec![
+                Span::styled("a. ", Style::new()),
+                Span::styled("a", Style::new()),
+            ]
+            .into(),
+            vec![
+                Span::styled("b. ", Style::new()),
+                Span::styled("b", Style::new()),
+            ]
+            .into(),
+            vec![].into()
+        ])
+        .wrap(Wrap { trim: false })
+    );
+}
+
+#[test]
+fn test_ol_roman_start() {
+    assert_eq!(
+        render("<ol type=\"I\" start=\"3\"><li>a</li><li>b</li></ol>").0,
+        Paragraph::new(vec![
+            vec![
+                Span::styled("III. ", Style::new()),
+                Span::styled("a", Style::new()),
+            ]
+            .into(),
+            vec![
+                Span::styled("IV. ", Style::new()),
+                Span::styled("b", Style::new()),
+            ]
+            .into(),
+            vec![].into()
+        ])
+        .wrap(Wrap { trim: false })
+    );
+}
+
+#[test]
+fn test_ol_in_ul() {
+    assert_eq!(
+        render("<ul><li>a</li><ol><li>b</li></ol></ul>").0,
+        Paragraph::new(vec![
+            vec![
+                Span::styled("  - ", Style::new()),
+                Span::styled("a", Style::new()),
+            ]
+            .into(),
+            vec![
+                Span::styled("    ", Style::new()),
+                Span::styled("1. ", Style::new()),
+                Span::styled("b", Style::new()),
+            ]
+            .into(),
+            vec![].into()
+        ])
+        .wrap(Wrap { trim: false })
+    );
+}
+
+#[test]
+fn test_ul_in_ol() {
+    assert_eq!(
+        render_to_plaintext(
+            "<ol><li>a</li><ul><li>b</li></ul><li>c</li></ol>",
+            &RenderOptions::default()
+        )
+        .0,
+        "1. a\n  - b\n2. c\n"
+    );
+}
+
+#[test]
+fn test_unclosed_items() {
+    assert_eq!(
+        render_to_plaintext("<ul><li>a<li>b</ul>", &RenderOptions::default()).0,
+        "  - a\n  - b\n"
+    );
+}
+
+#[test]
+fn test_list_inside_item() {
+    assert_eq!(
+        render_to_plaintext(
+            "<ul><li>a<ol><li>b</li><li>c</li></ol></li><li>d</li></ul>",
+            &RenderOptions::default()
+        )
+        .0,
+        "  - a\n    1. b\n    2. c\n  - d\n"
+    );
+}
@@ -1,4 +1,4 @@
-use bbml::render;
+use bbml::{render, render_to_plaintext, RenderOptions};
 use pretty_assertions::assert_eq;
 use ratatui::{
     prelude::*,
@@ -17,6 +17,41 @@ fn test_br() {
     );
 }
 #[test]
+fn test_plaintext() {
+    assert_eq!(
+        render_to_plaintext("a string<br>on two lines", &RenderOptions::default()).0,
+        "a string\non two lines"
+    );
+}
+#[test]
+fn test_nested_emphasis() {
+    assert_eq!(
+        render("<strong><em><u>all three</u></em></strong>").0,
+        Paragraph::new(vec![vec![Span::styled(
+            "all three",
+            Style::new().add_modifier(Modifier::BOLD | Modifier::ITALIC | Modifier::UNDERLINED)
+        )]
+        .into()])
+        .wrap(Wrap { trim: false })
+    );
+    assert_eq!(
+        render("<u>a<b>b<i>c</i></b></u>").0,
+        Paragraph::new(vec![vec![
+            Span::styled("a", Style::new().add_modifier(Modifier::UNDERLINED)),
+            Span::styled(
+                "b",
+                Style::new().add_modifier(Modifier::UNDERLINED | Modifier::BOLD)
+            ),
+            Span::styled(
+                "c",
+                Style::new().add_modifier(Modifier::UNDERLINED | Modifier::BOLD | Modifier::ITALIC)
+            ),
+        ]
+        .into()])
+        .wrap(Wrap { trim: false })
+    );
+}
+#[test]
 fn test_br_multiple() {
     assert_eq!(
         render("a<br><br>string").0,
@@ -40,3 +75,119 @@ fn test_linebreaks() {
         .wrap(Wrap { trim: false })
     );
 }
+
+#[test]
+fn test_figure_caption() {
+    assert_eq!(
+        render("before<figure><p>content</p><figcaption>A caption</figcaption></figure>after").0,
+        Paragraph::new(vec![
+            vec![Span::styled("before", Style::new()),].into(),
+            vec![].into(),
+            vec![Span::styled("content", Style::new()),].into(),
+            vec![
+                Span::styled("Figure: ", Style::new().add_modifier(Modifier::ITALIC)),
+                Span::styled("A caption", Style::new().add_modifier(Modifier::ITALIC)),
+            ]
+            .into(),
+            vec![].into(),
+            vec![Span::styled("after", Style::new()),].into(),
+        ])
+        .wrap(Wrap { trim: false })
+    );
+}
+
+#[test]
+fn test_button_placeholder() {
+    let dim = Style::new().add_modifier(Modifier::DIM);
+    assert_eq!(
+        dbg!(render("<p>Done?</p><button>Submit</button>").0),
+        Paragraph::new(vec![
+            vec![Span::styled("Done?", Style::new())].into(),
+            vec![Span::styled("[ Submit ]", dim)].into(),
+            vec![].into(),
+            vec![Span::styled(
+                "This page has buttons or form fields - open it in your browser to use them.",
+                dim.add_modifier(Modifier::ITALIC)
+            )]
+            .into(),
+        ])
+        .wrap(Wrap { trim: false })
+    );
+}
+
+#[test]
+fn test_input_placeholders() {
+    let dim = Style::new().add_modifier(Modifier::DIM);
+    let text = render(
+        "<form><input type=\"hidden\" value=\"secret\"><input type=\"text\" value=\"hello\"><input type=\"text\"><input type=\"checkbox\" checked><select><option>A</option><option selected>B</option></select></form>",
+    )
+    .0;
+    assert_eq!(
+        dbg!(text),
+        Paragraph::new(vec![
+            vec![
+                Span::styled("[hello]", dim),
+                Span::styled("[____]", dim),
+                Span::styled("[x]", dim),
+                Span::styled("[B ▾]", dim),
+            ]
+            .into(),
+            vec![].into(),
+            vec![Span::styled(
+                "This page has buttons or form fields - open it in your browser to use them.",
+                dim.add_modifier(Modifier::ITALIC)
+            )]
+            .into(),
+        ])
+        .wrap(Wrap { trim: false })
+    );
+}
+
+#[test]
+fn test_self_closing_void_tags() {
+    let opts = RenderOptions {
+        width: 5,
+        ..Default::default()
+    };
+    assert_eq!(render_to_plaintext("a<br/>b", &opts).0, "a\nb");
+    assert_eq!(render_to_plaintext("a<hr/>b", &opts).0, "a\n─────\nb");
+    assert_eq!(
+        render_to_plaintext("<img src=\"x.png\"/>after", &opts).0,
+        "after"
+    );
+}
+
+#[test]
+fn test_unclosed_p() {
+    assert_eq!(
+        render_to_plaintext("<p>one<p>two", &RenderOptions::default()).0,
+        "one\ntwo"
+    );
+}
+
+#[test]
+fn test_nested_links() {
+    let (text, links) = render_to_plaintext(
+        "<a href=\"https://a.com\">outer<a href=\"https://b.com\">inner</a></a>",
+        &RenderOptions::default(),
+    );
+    assert_eq!(text, "outerinner[0]");
+    assert_eq!(links, vec!["https://a.com"]);
+}
+
+#[test]
+fn test_malformed_doesnt_panic() {
+    for html in [
+        "</p>stray close",
+        "<div><span>x</div>",
+        "<b>bold <i>both</b> italic</i>",
+        "<td>orphan cell</td>",
+        "<tr><td>orphan row</td></tr>",
+        "<table><tr><td>a<td>b</table>",
+        "<ul><li>a<ol><li>b</ul>",
+        "<p",
+        "<<>>",
+    ] {
+        render(html);
+    }
+}
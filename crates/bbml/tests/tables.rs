@@ -1,4 +1,6 @@
-use bbml::render;
+use bbml::{
+    parse, render, render_text_with_options, render_with_options, RenderOptions, TableBorders,
+};
 use pretty_assertions::assert_eq;
 use ratatui::{
     prelude::*,
@@ -128,3 +130,435 @@ fn test_table_width_wraps_properly() {
         .wrap(Wrap { trim: false })
     );
 }
+
+#[test]
+fn test_table_ascii_borders() {
+    let opts = RenderOptions {
+        table_borders: TableBorders::Ascii,
+        ..Default::default()
+    };
+    assert_eq!(
+        dbg!(
+            render_with_options(
+                "<table><tr><td>1</td><td>2</td></tr><tr><td>3</td><td>4</td></tr></table>",
+                &opts
+            )
+            .0
+        ),
+        Paragraph::new(vec![
+            vec![Span::raw("+-+-+")].into(),
+            vec![
+                Span::raw("|"),
+                Span::raw("1"),
+                Span::raw("|"),
+                Span::raw("2"),
+                Span::raw("|")
+            ]
+            .into(),
+            vec![Span::raw("+-+-+")].into(),
+            vec![
+                Span::raw("|"),
+                Span::raw("3"),
+                Span::raw("|"),
+                Span::raw("4"),
+                Span::raw("|")
+            ]
+            .into(),
+            vec![Span::raw("+-+-+")].into(),
+        ])
+        .wrap(Wrap { trim: false })
+    );
+}
+
+#[test]
+fn test_table_width_from_options() {
+    let opts = RenderOptions {
+        width: 10,
+        ..Default::default()
+    };
+    assert_eq!(
+        dbg!(render_with_options("<table><tr><td>1111111111</td></tr></table>", &opts).0),
+        Paragraph::new(vec![
+            vec![Span::raw("┌────────┐")].into(),
+            vec![Span::raw("│"), Span::raw("11111111"), Span::raw("│")].into(),
+            vec![
+                Span::raw("│"),
+                Span::raw("11"),
+                Span::raw("      "),
+                Span::raw("│")
+            ]
+            .into(),
+            vec![Span::raw("└────────┘")].into(),
+        ])
+        .wrap(Wrap { trim: false })
+    );
+}
+
+#[test]
+fn test_parsed_rerender() {
+    let html = "<p>before</p><table><tr><td>1111111111</td><td>22</td></tr></table>";
+    let doc = parse(html);
+    for width in [10, 30] {
+        let opts = RenderOptions {
+            width,
+            ..Default::default()
+        };
+        assert_eq!(doc.render(&opts), render_text_with_options(html, &opts));
+    }
+}
+
+#[test]
+fn test_table_wide_chars_wrap() {
+    let opts = RenderOptions {
+        width: 7,
+        ..Default::default()
+    };
+    assert_eq!(
+        dbg!(render_with_options("<table><tr><td>日本語です</td></tr></table>", &opts).0),
+        Paragraph::new(vec![
+            vec![Span::raw("┌─────┐")].into(),
+            vec![
+                Span::raw("│"),
+                Span::raw("日本"),
+                Span::raw(" "),
+                Span::raw("│")
+            ]
+            .into(),
+            vec![
+                Span::raw("│"),
+                Span::raw("語で"),
+                Span::raw(" "),
+                Span::raw("│")
+            ]
+            .into(),
+            vec![
+                Span::raw("│"),
+                Span::raw("す"),
+                Span::raw("   "),
+                Span::raw("│")
+            ]
+            .into(),
+            vec![Span::raw("└─────┘")].into(),
+        ])
+        .wrap(Wrap { trim: false })
+    );
+}
+
+#[test]
+fn test_table_mixed_width_chars_wrap() {
+    let opts = RenderOptions {
+        width: 6,
+        ..Default::default()
+    };
+    assert_eq!(
+        dbg!(render_with_options("<table><tr><td>a日b本c</td></tr></table>", &opts).0),
+        Paragraph::new(vec![
+            vec![Span::raw("┌────┐")].into(),
+            vec![Span::raw("│"), Span::raw("a日b"), Span::raw("│")].into(),
+            vec![
+                Span::raw("│"),
+                Span::raw("本c"),
+                Span::raw(" "),
+                Span::raw("│")
+            ]
+            .into(),
+            vec![Span::raw("└────┘")].into(),
+        ])
+        .wrap(Wrap { trim: false })
+    );
+}
+
+#[test]
+fn test_table_nested() {
+    assert_eq!(
+        dbg!(
+            render("<table><tr><td><table><tr><td>1</td><td>2</td></tr></table></td></tr></table>")
+                .0
+        ),
+        Paragraph::new(vec![
+            vec![Span::raw("┌─────┐")].into(),
+            vec![Span::raw("│"), Span::raw("┌─┬─┐"), Span::raw("│")].into(),
+            vec![
+                Span::raw("│"),
+                Span::raw("│"),
+                Span::raw("1"),
+                Span::raw("│"),
+                Span::raw("2"),
+                Span::raw("│"),
+                Span::raw("│")
+            ]
+            .into(),
+            vec![Span::raw("│"), Span::raw("└─┴─┘"), Span::raw("│")].into(),
+            vec![Span::raw("└─────┘")].into(),
+        ])
+        .wrap(Wrap { trim: false })
+    );
+}
+
+#[test]
+fn test_table_nested_not_wrapped() {
+    let opts = RenderOptions {
+        width: 12,
+        ..Default::default()
+    };
+    assert_eq!(
+        dbg!(
+            render_with_options(
+                "<table><tr><td>aaaaa</td><td><table><tr><td>1</td><td>2</td></tr></table></td></tr></table>",
+                &opts
+            )
+            .0
+        ),
+        Paragraph::new(vec![
+            vec![Span::raw("┌────┬─────┐")].into(),
+            vec![
+                Span::raw("│"),
+                Span::raw("aaaa"),
+                Span::raw("│"),
+                Span::raw("┌─┬─┐"),
+                Span::raw("│")
+            ]
+            .into(),
+            vec![
+                Span::raw("│"),
+                Span::raw("a"),
+                Span::raw("   "),
+                Span::raw("│"),
+                Span::raw("│"),
+                Span::raw("1"),
+                Span::raw("│"),
+                Span::raw("2"),
+                Span::raw("│"),
+                Span::raw("│")
+            ]
+            .into(),
+            vec![
+                Span::raw("│"),
+                Span::raw("    "),
+                Span::raw("│"),
+                Span::raw("└─┴─┘"),
+                Span::raw("│")
+            ]
+            .into(),
+            vec![Span::raw("└────┴─────┘")].into(),
+        ])
+        .wrap(Wrap { trim: false })
+    );
+}
+
+#[test]
+fn test_table_empty() {
+    assert_eq!(
+        dbg!(render("<table></table>").0),
+        Paragraph::new(Text::default()).wrap(Wrap { trim: false })
+    );
+    assert_eq!(
+        dbg!(render("<table><tr></tr><tr><td></td></tr></table>").0),
+        Paragraph::new(Text::default()).wrap(Wrap { trim: false })
+    );
+}
+
+#[test]
+fn test_table_ragged_rows() {
+    let (text, _) = render_text_with_options(
+        "<table>
+<tr><td>a</td><td>bb</td><td>c</td></tr>
+<tr><td>d</td></tr>
+<tr></tr>
+<tr><td></td><td>e</td></tr>
+</table>",
+        &RenderOptions::default(),
+    );
+    let lines = text
+        .lines
+        .iter()
+        .map(|l| {
+            l.spans
+                .iter()
+                .map(|s| s.content.as_ref())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>();
+
+    // short rows are padded at the end, and empty cells and rows are kept
+    assert_eq!(
+        lines,
+        vec![
+            "┌─┬──┬─┐",
+            "│a│bb│c│",
+            "├─┼──┼─┤",
+            "│d│  │ │",
+            "├─┼──┼─┤",
+            "│ │  │ │",
+            "├─┼──┼─┤",
+            "│ │e │ │",
+            "└─┴──┴─┘",
+        ]
+    );
+}
+
+#[test]
+fn test_table_caption_only() {
+    assert_eq!(
+        dbg!(render("<table><caption>Results</caption></table>").0),
+        Paragraph::new(vec![vec![Span::styled(
+            "Results",
+            Style::new().add_modifier(Modifier::BOLD)
+        )]
+        .into(),])
+        .wrap(Wrap { trim: false })
+    );
+}
+
+#[test]
+fn test_table_caption() {
+    assert_eq!(
+        dbg!(render("<table><caption>Results</caption><tr><td>1</td></tr></table>").0),
+        Paragraph::new(vec![
+            vec![Span::styled(
+                "Results",
+                Style::new().add_modifier(Modifier::BOLD)
+            )]
+            .into(),
+            vec![Span::raw("┌─┐")].into(),
+            vec![Span::raw("│"), Span::raw("1"), Span::raw("│")].into(),
+            vec![Span::raw("└─┘")].into(),
+        ])
+        .wrap(Wrap { trim: false })
+    );
+}
+
+#[test]
+fn test_table_caption_centred() {
+    assert_eq!(
+        dbg!(
+            render("<table><caption>Results</caption><tr><td>name</td><td>score</td></tr></table>")
+                .0
+        ),
+        Paragraph::new(vec![
+            vec![
+                Span::raw("  "),
+                Span::styled("Results", Style::new().add_modifier(Modifier::BOLD))
+            ]
+            .into(),
+            vec![Span::raw("┌────┬─────┐")].into(),
+            vec![
+                Span::raw("│"),
+                Span::raw("name"),
+                Span::raw("│"),
+                Span::raw("score"),
+                Span::raw("│")
+            ]
+            .into(),
+            vec![Span::raw("└────┴─────┘")].into(),
+        ])
+        .wrap(Wrap { trim: false })
+    );
+}
+
+#[test]
+fn test_table_cell_with_list() {
+    assert_eq!(
+        dbg!(render("<table><tr><td><ul><li>a</li><li>b</li></ul></td><td>c</td></tr></table>").0),
+        Paragraph::new(vec![
+            vec![Span::raw("┌─────┬─┐")].into(),
+            vec![
+                Span::raw("│"),
+                Span::raw("  - "),
+                Span::raw("a"),
+                Span::raw("│"),
+                Span::raw("c"),
+                Span::raw("│")
+            ]
+            .into(),
+            vec![
+                Span::raw("│"),
+                Span::raw("  - "),
+                Span::raw("b"),
+                Span::raw("│"),
+                Span::raw(" "),
+                Span::raw("│")
+            ]
+            .into(),
+            vec![Span::raw("└─────┴─┘")].into(),
+        ])
+        .wrap(Wrap { trim: false })
+    );
+}
+
+#[test]
+fn test_table_cell_with_paragraphs() {
+    assert_eq!(
+        dbg!(render("<table><tr><td><p>1</p><p>2</p></td></tr></table>").0),
+        Paragraph::new(vec![
+            vec![Span::raw("┌─┐")].into(),
+            vec![Span::raw("│"), Span::raw("1"), Span::raw("│")].into(),
+            vec![Span::raw("│"), Span::raw("2"), Span::raw("│")].into(),
+            vec![Span::raw("└─┘")].into(),
+        ])
+        .wrap(Wrap { trim: false })
+    );
+}
+
+#[test]
+fn test_table_cell_align_right() {
+    assert_eq!(
+        dbg!(
+            render(
+                "<table><tr><td>Mark</td><td>Total</td></tr><tr><td>A</td><td align=\"right\">7</td></tr></table>"
+            )
+            .0
+        ),
+        Paragraph::new(vec![
+            vec![Span::raw("┌────┬─────┐")].into(),
+            vec![
+                Span::raw("│"),
+                Span::raw("Mark"),
+                Span::raw("│"),
+                Span::raw("Total"),
+                Span::raw("│")
+            ]
+            .into(),
+            vec![Span::raw("├────┼─────┤")].into(),
+            vec![
+                Span::raw("│"),
+                Span::raw("A"),
+                Span::raw("   "),
+                Span::raw("│"),
+                Span::raw("    "),
+                Span::raw("7"),
+                Span::raw("│")
+            ]
+            .into(),
+            vec![Span::raw("└────┴─────┘")].into(),
+        ])
+        .wrap(Wrap { trim: false })
+    );
+}
+
+#[test]
+fn test_table_cell_align_center_style() {
+    assert_eq!(
+        dbg!(
+            render(
+                "<table><tr><td>Total</td></tr><tr><td style=\"text-align: center\">7</td></tr></table>"
+            )
+            .0
+        ),
+        Paragraph::new(vec![
+            vec![Span::raw("┌─────┐")].into(),
+            vec![Span::raw("│"), Span::raw("Total"), Span::raw("│")].into(),
+            vec![Span::raw("├─────┤")].into(),
+            vec![
+                Span::raw("│"),
+                Span::raw("  "),
+                Span::raw("7"),
+                Span::raw("  "),
+                Span::raw("│")
+            ]
+            .into(),
+            vec![Span::raw("└─────┘")].into(),
+        ])
+        .wrap(Wrap { trim: false })
+    );
+}
@@ -0,0 +1,50 @@
+use std::cmp::Reverse;
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::{Client, Result};
+
+/// An announcement, which may be institution-wide or for a single course
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Announcement {
+    pub id: String,
+    pub title: String,
+
+    /// HTML body of the announcement
+    #[serde(default)]
+    pub body: Option<String>,
+
+    #[serde(default)]
+    pub created: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub modified: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+struct AnnouncementsResp {
+    #[serde(default)]
+    results: Vec<Announcement>,
+}
+
+impl Client {
+    /// Get institution-wide announcements, as shown on the dashboard.
+    pub fn system_announcements(&self) -> Result<Vec<Announcement>> {
+        self.get::<AnnouncementsResp>("learn/api/public/v1/announcements")
+            .map(|r| r.results)
+    }
+
+    /// Get the announcements posted in a course, most recent first
+    pub fn course_announcements(&self, course_id: &str) -> Result<Vec<Announcement>> {
+        let mut announcements = self
+            .get::<AnnouncementsResp>(&format!(
+                "learn/api/public/v1/courses/{}/announcements",
+                course_id
+            ))?
+            .results;
+        announcements.sort_by_key(|a| Reverse(a.created));
+
+        Ok(announcements)
+    }
+}
@@ -7,6 +7,7 @@ use regex::Regex;
 use reqwest::blocking::Response;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use zeroize::Zeroizing;
 
 use crate::Client;
 
@@ -36,11 +37,56 @@ pub enum Error {
 
     #[error("misc I/O error: {}", .0)]
     IOError(#[from] std::io::Error),
+
+    /// A service took longer than [`Client::with_auth_timeout`] to respond
+    #[error("timed out contacting {}", .0)]
+    Timeout(&'static str),
+
+    /// The credentials can't possibly be right, so we didn't try to login.
+    /// Contains a message to show the user.
+    #[error("{}", .0)]
+    InvalidCredentialsFormat(&'static str),
+
+    /// Logging in has failed too many times in a row, so we've stopped trying until the credentials change.
+    #[error("gave up logging in after repeated failures")]
+    RepeatedFailures,
+}
+
+/// Check the given username and password could be valid, without making any requests.
+pub fn validate_credentials(username: &str, password: &str) -> Result<(), Error> {
+    if username.trim().is_empty() {
+        Err(Error::InvalidCredentialsFormat("Username is empty!"))
+    } else if username.chars().any(char::is_whitespace) {
+        Err(Error::InvalidCredentialsFormat(
+            "Username can't contain spaces!",
+        ))
+    } else if password.trim().is_empty() {
+        Err(Error::InvalidCredentialsFormat("Password is empty!"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Wrap an error from a request to the given service, picking out timeouts
+fn req_error(
+    service: &'static str,
+    wrap: fn(reqwest::Error) -> Error,
+) -> impl Fn(reqwest::Error) -> Error {
+    move |e| {
+        if e.is_timeout() {
+            Error::Timeout(service)
+        } else {
+            wrap(e)
+        }
+    }
 }
 
 impl Client {
     /// Attempt to authenticate with the set credentials
     pub fn authenticate(&self) -> Result<(), Error> {
+        let (username, password) = self.creds();
+        validate_credentials(&username, password.as_ref())?;
+
         self.ease_login()?;
         self.learn_login()?;
 
@@ -49,25 +95,30 @@ impl Client {
 
     /// Logs into Ease / Cosign.
     fn ease_login(&self) -> Result<(), Error> {
+        self.report_login_progress("EASE");
+
         // Get once to set the cookies
         self.http
-            .get("https://www.ease.ed.ac.uk/")
+            .get(&self.endpoints.ease)
+            .timeout(self.auth_timeout)
             .send()
             .and_then(Response::error_for_status)
-            .map_err(Error::EaseReqError)?;
+            .map_err(req_error("EASE", Error::EaseReqError))?;
 
         // Login to CoSign
+        let (username, password) = self.creds();
         let text = self
             .http
-            .post("https://www.ease.ed.ac.uk/cosign.cgi")
+            .post(format!("{}cosign.cgi", self.endpoints.ease))
             .form(&[
-                ("login", self.creds.0.as_str()),
-                ("password", self.creds.1.as_ref()),
+                ("login", username.as_str()),
+                ("password", password.as_ref()),
             ])
+            .timeout(self.auth_timeout)
             .send()
             .and_then(Response::error_for_status)
             .and_then(|r| r.text())
-            .map_err(Error::EaseReqError)?;
+            .map_err(req_error("EASE", Error::EaseReqError))?;
 
         if !text.contains("/logout/logout.cgi") {
             return Err(Error::LoginFailed);
@@ -79,17 +130,21 @@ impl Client {
     // Logs into learn by performing the SAML request to the IDP
     fn learn_login(&self) -> Result<(), Error> {
         // Initiates the login process
-        const LEARN_LOGIN_URL: &str = "https://www.learn.ed.ac.uk/auth-saml/saml/login?apId=_175_1&redirectUrl=https%3A%2F%2Fwww.learn.ed.ac.uk%2Fultra";
-        const SSO_SAML_URL: &str = "https://idp.ed.ac.uk/idp/profile/SAML2/POST/SSO";
-        const LEARN_CALLBACK_URL: &str =
-            "https://www.learn.ed.ac.uk/auth-saml/saml/SSO/alias/_175_1";
+        let learn_login_url = format!(
+            "{}auth-saml/saml/login?apId=_175_1&redirectUrl=https%3A%2F%2Fwww.learn.ed.ac.uk%2Fultra",
+            self.endpoints.learn
+        );
+        let sso_saml_url = format!("{}idp/profile/SAML2/POST/SSO", self.endpoints.idp);
+        let learn_callback_url = format!("{}auth-saml/saml/SSO/alias/_175_1", self.endpoints.learn);
+        self.report_login_progress("Learn");
         let text = self
             .http
-            .get(LEARN_LOGIN_URL)
+            .get(learn_login_url)
+            .timeout(self.auth_timeout)
             .send()
             .and_then(Response::error_for_status)
             .and_then(|r| r.text())
-            .map_err(Error::LearnReqError)?;
+            .map_err(req_error("Learn", Error::LearnReqError))?;
 
         let samlreq_re = Regex::new(r#"name="SAMLRequest" value="([^"]*)""#).unwrap();
         let Some(caps) = samlreq_re.captures(&text) else {
@@ -98,26 +153,30 @@ impl Client {
         let samlreq = &caps[1];
 
         // Authn Request
+        self.report_login_progress("the IDP");
         let text = self
             .http
-            .post(SSO_SAML_URL)
+            .post(sso_saml_url)
             .form(&[("SAMLRequest", samlreq)])
+            .timeout(self.auth_timeout)
             .send()
             .and_then(Response::error_for_status)
             .and_then(|t| t.text())
-            .map_err(Error::IDPReqError)?;
+            .map_err(req_error("the IDP", Error::IDPReqError))?;
         let samlresp_re = Regex::new(r#"name="SAMLResponse" value="([^"]*)""#).unwrap();
         let Some(caps) = samlresp_re.captures(&text) else {
             return Err(Error::NoSAMLResponse(text));
         };
         let samlresp = &caps[1];
 
+        self.report_login_progress("Learn");
         self.http
-            .post(LEARN_CALLBACK_URL)
+            .post(learn_callback_url)
             .form(&[("SAMLResponse", samlresp)])
+            .timeout(self.auth_timeout)
             .send()
             .and_then(Response::error_for_status)
-            .map_err(Error::LearnReqError)?;
+            .map_err(req_error("Learn", Error::LearnReqError))?;
 
         Ok(())
     }
@@ -144,9 +203,10 @@ impl std::fmt::Debug for AuthState {
     }
 }
 
-/// A password, wrapped so we don't print it by accident
+/// A password, wrapped so we don't print it by accident.
+/// Its memory is zeroed when dropped, so it doesn't linger after we're done with it.
 #[derive(Clone, Serialize, Deserialize)]
-pub struct Password(String);
+pub struct Password(Zeroizing<String>);
 impl std::fmt::Debug for Password {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "Password (******)")
@@ -155,13 +215,14 @@ impl std::fmt::Debug for Password {
 
 impl From<String> for Password {
     fn from(value: String) -> Self {
-        Password(value)
+        Password(Zeroizing::new(value))
     }
 }
 
 impl From<Password> for String {
-    fn from(val: Password) -> Self {
-        val.0
+    fn from(mut val: Password) -> Self {
+        // move the buffer out, so it's not copied
+        std::mem::take(&mut *val.0)
     }
 }
 
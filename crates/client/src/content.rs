@@ -1,9 +1,10 @@
 use chrono::{DateTime, Local};
+use reqwest::StatusCode;
 use serde::{
     de::{self, MapAccess, Visitor},
     Deserialize, Deserializer,
 };
-use std::fmt;
+use std::{collections::HashSet, fmt};
 
 use crate::{Client, Error, Result, LEARN_BASE};
 
@@ -14,36 +15,77 @@ impl Client {
     }
 
     /// Get the children of a given content item.
+    /// If there are too many for one response, this follows the pages until it has them all.
     pub fn content_children(&self, course_id: &str, content_id: &str) -> Result<Vec<Content>> {
         Ok(self
-            .get::<ContentChildrenResp>(&format!(
+            .get_paged::<RawContent>(&format!(
                 "learn/api/v1/courses/{}/contents/{}/children",
                 course_id, content_id
             ))?
-            .results
             .into_iter()
             .map(|raw| Content::new(raw, course_id))
             .collect())
     }
 
-    /// Get the text of a page
-    pub fn page_text(&self, course_id: &str, content_id: &str) -> Result<String> {
-        let mut results = self
-            .get::<ContentChildrenResp>(&format!(
-                "learn/api/v1/courses/{}/contents/{}/children",
-                course_id, content_id
-            ))?
-            .results;
-        if results.len() != 1 {
-            return Err(Error::BadContentLeaf);
-        }
+    /// Get a course's content, and the content of folders inside it up to `max_depth` levels down.
+    /// A depth of 0 only gets the top-level content, the same as [`Self::course_children`].
+    ///
+    /// Learn has no way to get several levels at once, so this still makes one request per folder,
+    /// but it saves a round trip through the caller for each one.
+    pub fn course_tree(&self, course_id: &str, max_depth: usize) -> Result<Vec<ContentNode>> {
+        let mut seen = HashSet::new();
+        self.content_subtree(course_id, "ROOT", max_depth, &mut seen)
+    }
 
-        let result = results.pop().unwrap();
-        let Some(RawContentBody { raw_text, .. }) = result.body else {
-            return Err(Error::BadContentLeaf);
-        };
+    fn content_subtree(
+        &self,
+        course_id: &str,
+        content_id: &str,
+        depth: usize,
+        seen: &mut HashSet<String>,
+    ) -> Result<Vec<ContentNode>> {
+        self.content_children(course_id, content_id)?
+            .into_iter()
+            .map(|content| {
+                // folders can be linked from more than one place, so don't get them twice
+                let children =
+                    if depth > 0 && content.is_container() && seen.insert(content.id.clone()) {
+                        Some(self.content_subtree(course_id, &content.id, depth - 1, seen)?)
+                    } else {
+                        None
+                    };
+
+                Ok(ContentNode { content, children })
+            })
+            .collect()
+    }
+
+    /// Get a single content item by its ID, or `None` if it doesn't exist.
+    /// For pages, use [`Self::page_text`] to get the body.
+    pub fn content(&self, course_id: &str, content_id: &str) -> Result<Option<Content>> {
+        Ok(self
+            .get_unless::<RawContent>(
+                &format!("learn/api/v1/courses/{}/contents/{}", course_id, content_id),
+                StatusCode::NOT_FOUND,
+            )?
+            .map(|raw| Content::new(raw, course_id)))
+    }
 
-        Ok(raw_text)
+    /// Get a course's overview page, if it has one. See [`Content::is_overview`].
+    pub fn course_overview(&self, course_id: &str) -> Result<Option<Content>> {
+        Ok(self
+            .course_children(course_id)?
+            .into_iter()
+            .find(Content::is_overview))
+    }
+
+    /// Get the text of a page
+    pub fn page_text(&self, course_id: &str, content_id: &str) -> Result<String> {
+        self.get::<ContentChildrenResp>(&format!(
+            "learn/api/v1/courses/{}/contents/{}/children",
+            course_id, content_id
+        ))?
+        .into_page_text()
     }
 }
 
@@ -54,11 +96,17 @@ pub struct Content {
     pub id: String,
     pub course_id: String,
 
+    /// The folder this is in, which is the course's root folder for top-level items
+    pub parent_id: String,
+
     pub title: String,
     pub description: Option<String>,
 
     pub payload: ContentPayload,
 
+    /// When this item was last changed, or created if it hasn't been changed.
+    pub last_modified: Option<DateTime<Local>>,
+
     link: String,
 }
 
@@ -107,6 +155,11 @@ impl Content {
             Some(ContentDetail::Assessment { test }) => ContentPayload::Assessment {
                 name: test.grading_column.effective_column_name,
                 due_date: test.grading_column.due_date,
+                column_id: test.grading_column.id,
+                url: format!(
+                    "{}ultra/courses/{}/outline/assessment/test/{}?courseId={}",
+                    LEARN_BASE, course_id, raw.id, course_id
+                ),
             },
             Some(ContentDetail::Unknown {}) | None => ContentPayload::Other,
         };
@@ -117,9 +170,11 @@ impl Content {
                 LEARN_BASE, course_id, raw.id, raw.parent_id
             ),
             id: raw.id,
+            parent_id: raw.parent_id,
             course_id: course_id.to_string(),
             title: raw.title,
             description: raw.description,
+            last_modified: raw.modified.or(raw.created),
             payload,
         }
     }
@@ -128,16 +183,84 @@ impl Content {
         matches!(self.payload, ContentPayload::Folder)
     }
 
+    /// Parse a content item from the JSON the API gives for it, ie from a saved response
+    pub fn from_json(json: &str, course_id: &str) -> Result<Self> {
+        Ok(Self::new(serde_json::from_str(json)?, course_id))
+    }
+
+    /// Check if this looks like a course's overview or syllabus page.
+    /// The API doesn't mark these specially, so this goes by the title of top-level pages.
+    pub fn is_overview(&self) -> bool {
+        const TITLES: &[&str] = &[
+            "course overview",
+            "course information",
+            "course info",
+            "course outline",
+            "overview",
+            "syllabus",
+            "start here",
+        ];
+
+        matches!(self.payload, ContentPayload::Page)
+            && TITLES.contains(&self.title.trim().to_lowercase().as_str())
+    }
+
     pub fn browser_link(&self) -> &str {
         match &self.payload {
             ContentPayload::Link(link) => link,
             ContentPayload::File { permanent_url, .. } => permanent_url,
             ContentPayload::Placement { url, .. } => url,
+            ContentPayload::Assessment { url, .. } => url,
             _ => &self.link,
         }
     }
 }
 
+/// Get the course and content IDs out of a link to some content on Learn, if it is one.
+///
+/// This understands the redirect links in [`Content::link`], old-style links with `course_id` and `content_id` parameters,
+/// and Ultra links like `ultra/courses/{course_id}/outline/.../{content_id}`.
+pub fn parse_content_link(href: &str) -> Option<(String, String)> {
+    let url = reqwest::Url::parse(href).ok()?;
+    let base = reqwest::Url::parse(LEARN_BASE).unwrap();
+    if url.host_str() != base.host_str() {
+        return None;
+    }
+
+    let param = |names: [&str; 2]| {
+        url.query_pairs()
+            .find(|(k, _)| names.contains(&k.as_ref()))
+            .map(|(_, v)| v.into_owned())
+    };
+    if let (Some(course_id), Some(content_id)) = (
+        param(["courseId", "course_id"]),
+        param(["contentId", "content_id"]),
+    ) {
+        return Some((course_id, content_id));
+    }
+
+    let mut segments = url.path_segments()?;
+    if segments.next()? != "ultra" || segments.next()? != "courses" {
+        return None;
+    }
+    let course_id = segments.next()?.to_string();
+    if segments.next()? != "outline" {
+        return None;
+    }
+    let content_id = segments.next_back().filter(|s| s.starts_with('_'))?;
+
+    Some((course_id, content_id.to_string()))
+}
+
+/// A content item and what's inside it, from [`Client::course_tree`]
+#[derive(Debug)]
+pub struct ContentNode {
+    pub content: Content,
+
+    /// The folder's children, or `None` if this isn't a folder or it's deeper than we fetched
+    pub children: Option<Vec<ContentNode>>,
+}
+
 /// What the content is, and the actual content if it carries it.
 #[derive(Debug)]
 pub enum ContentPayload {
@@ -164,9 +287,13 @@ pub enum ContentPayload {
     /// URL will authenticate and then redirect the user.
     Placement { name: &'static str, url: String },
 
+    /// An assessment, which may be submitted to.
+    /// URL goes to the page to submit it, and `column_id` can be used with [`Client::attempts`] to check if it has been.
     Assessment {
         name: String,
         due_date: DateTime<Local>,
+        column_id: Option<String>,
+        url: String,
     },
 }
 
@@ -175,6 +302,24 @@ pub struct ContentChildrenResp {
     results: Vec<RawContent>,
 }
 
+impl ContentChildrenResp {
+    /// Get the text of a page from its children.
+    /// Usually there's only one, but if there are several their bodies are joined with a blank line.
+    fn into_page_text(self) -> Result<String> {
+        let bodies = self
+            .results
+            .into_iter()
+            .filter_map(|r| r.body)
+            .map(|b| b.raw_text)
+            .collect::<Vec<_>>();
+        if bodies.is_empty() {
+            return Err(Error::BadContentLeaf);
+        }
+
+        Ok(bodies.join("\n\n"))
+    }
+}
+
 // so firstly, everything on the blackboard learn api docs site is a lie.
 // content items actually seem to follow this pattern:
 //   - for folders, we get ContentDetail::Folder, with is_page set to false
@@ -198,12 +343,17 @@ struct RawContent {
     #[serde(deserialize_with = "raw_body_str_or_struct", default = "none")]
     body: Option<RawContentBody>,
     content_detail: Option<ContentDetail>,
+
+    #[serde(default)]
+    created: Option<DateTime<Local>>,
+    #[serde(default)]
+    modified: Option<DateTime<Local>>,
 }
 
 #[derive(Debug, Deserialize)]
-struct RawContentBody {
+pub(crate) struct RawContentBody {
     #[serde(rename = "rawText")]
-    raw_text: String,
+    pub(crate) raw_text: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -267,11 +417,15 @@ struct RawTest {
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct RawGradingColumn {
+    #[serde(default)]
+    id: Option<String>,
     effective_column_name: String,
     due_date: DateTime<Local>,
 }
 
-fn raw_body_str_or_struct<'de, D>(deserializer: D) -> Result<Option<RawContentBody>, D::Error>
+pub(crate) fn raw_body_str_or_struct<'de, D>(
+    deserializer: D,
+) -> Result<Option<RawContentBody>, D::Error>
 where
     D: Deserializer<'de>,
 {
@@ -306,10 +460,245 @@ where
         Err(_) => Ok(None),
     }
 }
-fn none<T>() -> Option<T> {
+pub(crate) fn none<T>() -> Option<T> {
     None
 }
 
 fn val_false() -> bool {
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_content_link() {
+        let ids = |course: &str, content: &str| Some((course.to_string(), content.to_string()));
+        assert_eq!(
+            parse_content_link("https://www.learn.ed.ac.uk/ultra/redirect?redirectType=nautilus&courseId=_1_1&contentId=_2_1&parentId=_3_1"),
+            ids("_1_1", "_2_1")
+        );
+        assert_eq!(
+            parse_content_link("https://www.learn.ed.ac.uk/webapps/blackboard/content/listContent.jsp?course_id=_1_1&content_id=_2_1"),
+            ids("_1_1", "_2_1")
+        );
+        assert_eq!(
+            parse_content_link("https://www.learn.ed.ac.uk/ultra/courses/_1_1/outline/assessment/test/_2_1?courseId=_1_1"),
+            ids("_1_1", "_2_1")
+        );
+
+        assert_eq!(
+            parse_content_link("https://www.learn.ed.ac.uk/ultra/courses/_1_1/outline"),
+            None
+        );
+        assert_eq!(
+            parse_content_link("https://example.com/?courseId=_1_1&contentId=_2_1"),
+            None
+        );
+        assert_eq!(parse_content_link("not a url"), None);
+    }
+
+    #[test]
+    fn test_page_text_single() {
+        let resp: ContentChildrenResp = serde_json::from_str(
+            r#"{"results": [{"id": "_1_1", "parentId": "_2_1", "title": "a", "body": {"rawText": "<p>hello</p>"}}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(resp.into_page_text().unwrap(), "<p>hello</p>");
+    }
+
+    #[test]
+    fn test_page_text_multiple_joined() {
+        let resp: ContentChildrenResp = serde_json::from_str(
+            r#"{"results": [
+                {"id": "_1_1", "parentId": "_2_1", "title": "a", "body": {"rawText": "<p>one</p>"}},
+                {"id": "_1_2", "parentId": "_2_1", "title": "b", "body": "<p>two</p>"}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(resp.into_page_text().unwrap(), "<p>one</p>\n\n<p>two</p>");
+    }
+
+    #[test]
+    fn test_page_text_no_bodies() {
+        let resp: ContentChildrenResp = serde_json::from_str(
+            r#"{"results": [{"id": "_1_1", "parentId": "_2_1", "title": "a"}]}"#,
+        )
+        .unwrap();
+
+        assert!(matches!(resp.into_page_text(), Err(Error::BadContentLeaf)));
+    }
+
+    #[test]
+    fn test_is_overview() {
+        let content = |title: &str, detail: &str| {
+            let raw: RawContent = serde_json::from_str(&format!(
+                r#"{{"id": "_1_1", "parentId": "_2_1", "title": "{}", "contentDetail": {}}}"#,
+                title, detail
+            ))
+            .unwrap();
+            Content::new(raw, "_3_1")
+        };
+        let page = r#"{"resource/x-bb-folder": {"isBbPage": true}}"#;
+        let folder = r#"{"resource/x-bb-folder": {"isBbPage": false}}"#;
+
+        assert!(content("Course Overview", page).is_overview());
+        assert!(content(" Syllabus ", page).is_overview());
+        assert!(!content("Course Overview", folder).is_overview());
+        assert!(!content("Week 1", page).is_overview());
+    }
+
+    #[test]
+    fn test_placement_link_from_ultra() {
+        let raw: RawContent = serde_json::from_str(
+            r#"{"id": "_1_1", "parentId": "_2_1", "title": "Zoom", "contentDetail": {
+                "resource/x-bb-bltiplacement-zoom": {"launchLink": "webapps/blackboard/launch?id=1"}
+            }}"#,
+        )
+        .unwrap();
+        let content = Content::new(raw, "_3_1");
+
+        assert!(matches!(
+            content.payload,
+            ContentPayload::Placement { name: "Zoom", .. }
+        ));
+        assert_eq!(
+            content.browser_link(),
+            format!(
+                "{}webapps/blackboard/launch?id=1&from_ultra=true",
+                LEARN_BASE
+            )
+        );
+    }
+
+    #[test]
+    fn test_browser_link() {
+        let content = |detail: &str| {
+            let raw: RawContent = serde_json::from_str(&format!(
+                r#"{{"id": "_1_1", "parentId": "_2_1", "title": "a", "contentDetail": {}}}"#,
+                detail
+            ))
+            .unwrap();
+            Content::new(raw, "_3_1")
+        };
+        let redirect = format!(
+            "{}ultra/redirect?redirectType=nautilus&courseId=_3_1&contentId=_1_1&parentId=_2_1",
+            LEARN_BASE
+        );
+
+        let link = content(r#"{"resource/x-bb-externallink": {"url": "https://example.com/"}}"#);
+        assert!(matches!(link.payload, ContentPayload::Link(_)));
+        assert_eq!(link.browser_link(), "https://example.com/");
+
+        let file = content(
+            r#"{"resource/x-bb-file": {"file": {
+                "mimeType": "application/pdf", "fileName": "a.pdf", "permanentUrl": "/bbcswebdav/xid-1_1"
+            }}}"#,
+        );
+        assert!(matches!(file.payload, ContentPayload::File { .. }));
+        assert_eq!(
+            file.browser_link(),
+            format!("{}bbcswebdav/xid-1_1", LEARN_BASE)
+        );
+
+        for placement in [
+            "49f1179af0494f078ce3ff737dd75de4",
+            "mhrlti",
+            "zoom",
+            "gradescope",
+        ] {
+            let content = content(&format!(
+                r#"{{"resource/x-bb-bltiplacement-{}": {{"launchLink": "webapps/launch?id=1"}}}}"#,
+                placement
+            ));
+            assert!(matches!(content.payload, ContentPayload::Placement { .. }));
+            assert_eq!(
+                content.browser_link(),
+                format!("{}webapps/launch?id=1&from_ultra=true", LEARN_BASE)
+            );
+        }
+
+        let assessment = content(
+            r#"{"resource/x-bb-asmt-test-link": {"test": {"gradingColumn": {
+                "effectiveColumnName": "Quiz", "dueDate": "2024-01-01T10:00:00.000Z"
+            }}}}"#,
+        );
+        assert!(matches!(
+            assessment.payload,
+            ContentPayload::Assessment { .. }
+        ));
+        assert_eq!(
+            assessment.browser_link(),
+            format!(
+                "{}ultra/courses/_3_1/outline/assessment/test/_1_1?courseId=_3_1",
+                LEARN_BASE
+            )
+        );
+
+        // everything else goes through Learn's redirect
+        let folder = content(r#"{"resource/x-bb-folder": {"isBbPage": false}}"#);
+        assert!(matches!(folder.payload, ContentPayload::Folder));
+        assert_eq!(folder.browser_link(), redirect);
+
+        let page = content(r#"{"resource/x-bb-folder": {"isBbPage": true}}"#);
+        assert!(matches!(page.payload, ContentPayload::Page));
+        assert_eq!(page.browser_link(), redirect);
+
+        let other = content(r#"{"resource/x-bb-something-new": {}}"#);
+        assert!(matches!(other.payload, ContentPayload::Other));
+        assert_eq!(other.browser_link(), redirect);
+
+        let none = content("null");
+        assert!(matches!(none.payload, ContentPayload::Other));
+        assert_eq!(none.browser_link(), redirect);
+    }
+
+    #[test]
+    fn test_last_modified_falls_back_to_created() {
+        let raw: RawContent = serde_json::from_str(
+            r#"{"id": "_1_1", "parentId": "_2_1", "title": "a", "created": "2024-01-01T10:00:00.000Z"}"#,
+        )
+        .unwrap();
+        let content = Content::new(raw, "_3_1");
+        assert_eq!(
+            content.last_modified,
+            Some("2024-01-01T10:00:00Z".parse().unwrap())
+        );
+
+        let raw: RawContent = serde_json::from_str(
+            r#"{"id": "_1_1", "parentId": "_2_1", "title": "a", "created": "2024-01-01T10:00:00.000Z", "modified": "2024-02-01T10:00:00.000Z"}"#,
+        )
+        .unwrap();
+        let content = Content::new(raw, "_3_1");
+        assert_eq!(
+            content.last_modified,
+            Some("2024-02-01T10:00:00Z".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_assessment_links_to_submission() {
+        let raw: RawContent = serde_json::from_str(
+            r#"{"id": "_1_1", "parentId": "_2_1", "title": "Coursework", "contentDetail": {
+                "resource/x-bb-asmt-test-link": {"test": {"gradingColumn": {"id": "_4_1", "effectiveColumnName": "Coursework 1", "dueDate": "2024-03-01T12:00:00.000Z"}}}
+            }}"#,
+        )
+        .unwrap();
+        let content = Content::new(raw, "_3_1");
+
+        assert!(matches!(
+            &content.payload,
+            ContentPayload::Assessment { column_id: Some(c), .. } if c == "_4_1"
+        ));
+        assert_eq!(
+            content.browser_link(),
+            format!(
+                "{}ultra/courses/_3_1/outline/assessment/test/_1_1?courseId=_3_1",
+                LEARN_BASE
+            )
+        );
+    }
+}
@@ -1,6 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::Deserialize;
 
+use crate::{terms::Term, Client, Result};
+
 /// A course
 #[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -18,4 +20,86 @@ pub struct Course {
     pub description: Option<String>,
     pub term_id: Option<String>,
     pub created: Option<DateTime<Utc>>,
+
+    /// When the course starts, if it has fixed dates
+    #[serde(default)]
+    pub start_date: Option<DateTime<Utc>>,
+
+    /// When the course ends, if it has fixed dates
+    #[serde(default)]
+    pub end_date: Option<DateTime<Utc>>,
+
+    /// The colour Ultra shows the course in, as a hex string like `#1c8845`
+    #[serde(default, alias = "courseColor")]
+    pub color: Option<String>,
+}
+
+impl Course {
+    /// Get the name of the term this course is in, using the given list of terms
+    pub fn term_name<'a>(&self, terms: &'a [Term]) -> Option<&'a str> {
+        let term_id = self.term_id.as_ref()?;
+        terms
+            .iter()
+            .find(|t| t.id == *term_id)
+            .map(|t| t.name.as_str())
+    }
+
+    /// Get the course's colour as RGB, if it has a valid one
+    pub fn rgb(&self) -> Option<(u8, u8, u8)> {
+        let hex = self.color.as_deref()?.trim().strip_prefix('#')?;
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+
+        Some((channel(0)?, channel(2)?, channel(4)?))
+    }
+
+    /// Check if the course has an end date, and it has passed
+    pub fn has_ended(&self) -> bool {
+        self.end_date.map(|d| d < Utc::now()).unwrap_or(false)
+    }
+}
+
+impl Client {
+    /// Get a single course by its internal ID.
+    /// Results are cached, so repeated lookups won't make more requests. Use [`Self::refresh_course`] to bypass this.
+    pub fn course(&self, id: &str) -> Result<Course> {
+        if let Some(c) = self.course_cache.read().unwrap().get(id) {
+            return Ok(c.clone());
+        }
+
+        self.refresh_course(id)
+    }
+
+    /// Fetch a single course by its internal ID, updating the cache.
+    pub fn refresh_course(&self, id: &str) -> Result<Course> {
+        let course: Course = self.get(&format!("learn/api/v1/courses/{}", id))?;
+        self.course_cache
+            .write()
+            .unwrap()
+            .insert(id.to_string(), course.clone());
+
+        Ok(course)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_course_color() {
+        let course: Course = serde_json::from_str(
+            r##"{"id": "_1_1", "uuid": "a", "courseId": "C1", "name": "Course", "description": null, "termId": null, "created": null, "courseColor": "#1C8845"}"##,
+        )
+        .unwrap();
+        assert_eq!(course.rgb(), Some((0x1c, 0x88, 0x45)));
+
+        let course = Course {
+            color: Some("blue".to_string()),
+            ..course
+        };
+        assert_eq!(course.rgb(), None);
+    }
 }
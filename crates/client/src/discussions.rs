@@ -0,0 +1,136 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::{
+    content::{none, raw_body_str_or_struct, RawContentBody},
+    Client, Result,
+};
+
+impl Client {
+    /// Get the discussion forums in a course
+    pub fn course_forums(&self, course_id: &str) -> Result<Vec<Forum>> {
+        Ok(self
+            .get::<ForumsResp>(&format!(
+                "learn/api/public/v1/courses/{}/discussions",
+                course_id
+            ))?
+            .results
+            .into_iter()
+            .map(|raw| Forum::new(raw, course_id))
+            .collect())
+    }
+
+    /// Get the threads in a discussion forum.
+    /// Only top-level posts are returned, not replies.
+    pub fn forum_threads(&self, course_id: &str, forum_id: &str) -> Result<Vec<Thread>> {
+        Ok(self
+            .get::<ThreadsResp>(&format!(
+                "learn/api/public/v1/courses/{}/discussions/{}/messages",
+                course_id, forum_id
+            ))?
+            .results
+            .into_iter()
+            .filter(|raw| raw.parent_id.is_none())
+            .map(|raw| {
+                let mut thread = Thread::new(raw, forum_id);
+                // Not being able to see the author shouldn't stop us seeing the thread
+                thread.author_name = thread
+                    .author_id
+                    .as_ref()
+                    .and_then(|id| self.user(id).ok())
+                    .map(|u| u.display_name());
+                thread
+            })
+            .collect())
+    }
+}
+
+/// A discussion forum in a course
+#[derive(Debug, Clone)]
+pub struct Forum {
+    pub id: String,
+    pub course_id: String,
+
+    pub title: String,
+
+    /// Description of the forum, as BbML
+    pub description: Option<String>,
+}
+
+impl Forum {
+    fn new(raw: RawForum, course_id: &str) -> Self {
+        Self {
+            id: raw.id,
+            course_id: course_id.to_string(),
+            title: raw.title,
+            description: raw.description.map(|b| b.raw_text),
+        }
+    }
+}
+
+/// A thread in a discussion forum
+#[derive(Debug, Clone)]
+pub struct Thread {
+    pub id: String,
+    pub forum_id: String,
+
+    pub title: String,
+
+    /// The ID of the user who started the thread, if visible to us
+    pub author_id: Option<String>,
+
+    /// The name of the user who started the thread, if visible to us
+    pub author_name: Option<String>,
+
+    /// The text of the first post, as BbML
+    pub body: String,
+
+    pub created: Option<DateTime<Utc>>,
+}
+
+impl Thread {
+    fn new(raw: RawMessage, forum_id: &str) -> Self {
+        Self {
+            id: raw.id,
+            forum_id: forum_id.to_string(),
+            title: raw.title.unwrap_or_default(),
+            author_id: raw.user_id,
+            author_name: None,
+            body: raw.body.map(|b| b.raw_text).unwrap_or_default(),
+            created: raw.created,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ForumsResp {
+    results: Vec<RawForum>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawForum {
+    id: String,
+    title: String,
+
+    #[serde(deserialize_with = "raw_body_str_or_struct", default = "none")]
+    description: Option<RawContentBody>,
+}
+
+#[derive(Deserialize)]
+struct ThreadsResp {
+    results: Vec<RawMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawMessage {
+    id: String,
+    parent_id: Option<String>,
+    title: Option<String>,
+    user_id: Option<String>,
+
+    #[serde(deserialize_with = "raw_body_str_or_struct", default = "none")]
+    body: Option<RawContentBody>,
+    created: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,207 @@
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+
+use crate::{Client, Result};
+
+/// An attempt at an assessment, ie a submission
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attempt {
+    pub id: String,
+    pub status: AttemptStatus,
+    #[serde(default)]
+    pub created: Option<DateTime<Local>>,
+}
+
+/// The state of an [`Attempt`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum AttemptStatus {
+    NotAttempted,
+    Abandoned,
+    InProgress,
+    Suspended,
+    Canceled,
+    NeedsGrading,
+    Completed,
+    InProgressReattempt,
+    #[serde(other)]
+    Unknown,
+}
+
+impl Attempt {
+    /// Check if this attempt was actually handed in
+    pub fn is_submitted(&self) -> bool {
+        matches!(
+            self.status,
+            AttemptStatus::NeedsGrading | AttemptStatus::Completed
+        )
+    }
+}
+
+#[derive(Deserialize)]
+struct AttemptsResp {
+    results: Vec<Attempt>,
+}
+
+/// A grade for one column of a course's gradebook
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grade {
+    pub column_id: String,
+    pub column_name: String,
+
+    /// The score given, if this has been marked
+    pub score: Option<f64>,
+
+    /// The maximum possible score
+    pub possible: Option<f64>,
+
+    /// The grade as it's displayed on Learn, ie a letter grade
+    pub text: Option<String>,
+}
+
+impl Grade {
+    /// Get the grade as it should be displayed to the user, if it's been marked
+    pub fn display(&self) -> Option<String> {
+        if let Some(text) = &self.text {
+            return Some(text.clone());
+        }
+
+        match (self.score, self.possible) {
+            (Some(score), Some(possible)) => Some(format!("{} / {}", score, possible)),
+            (Some(score), None) => Some(score.to_string()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Column {
+    id: String,
+    name: String,
+    #[serde(default)]
+    display_name: Option<String>,
+    #[serde(default)]
+    score: Option<ColumnScore>,
+}
+
+#[derive(Deserialize)]
+struct ColumnScore {
+    possible: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct ColumnsResp {
+    #[serde(default)]
+    results: Vec<Column>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UserGrade {
+    column_id: String,
+    #[serde(default)]
+    score: Option<f64>,
+    #[serde(default)]
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct UserGradesResp {
+    #[serde(default)]
+    results: Vec<UserGrade>,
+}
+
+/// More about an assessment, from its gradebook column
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssessmentDetails {
+    /// What the assessment asks for, which may be BbML
+    pub description: Option<String>,
+
+    /// The maximum possible score
+    pub possible: Option<f64>,
+
+    /// How many attempts are allowed, or `None` if there's no limit
+    pub attempts_allowed: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DetailedColumn {
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    score: Option<ColumnScore>,
+    #[serde(default)]
+    grading: Option<ColumnGrading>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ColumnGrading {
+    /// 0 means unlimited
+    #[serde(default)]
+    attempts_allowed: u32,
+}
+
+impl Client {
+    /// Get the current user's attempts at the assessment with the given grade column
+    pub fn attempts(&self, course_id: &str, column_id: &str) -> Result<Vec<Attempt>> {
+        self.get::<AttemptsResp>(&format!(
+            "learn/api/public/v2/courses/{}/gradebook/columns/{}/attempts",
+            course_id, column_id
+        ))
+        .map(|r| r.results)
+    }
+
+    /// Get the details of the assessment with the given grade column
+    pub fn assessment_details(
+        &self,
+        course_id: &str,
+        column_id: &str,
+    ) -> Result<AssessmentDetails> {
+        let column = self.get::<DetailedColumn>(&format!(
+            "learn/api/public/v2/courses/{}/gradebook/columns/{}",
+            course_id, column_id
+        ))?;
+
+        Ok(AssessmentDetails {
+            description: column.description.filter(|d| !d.trim().is_empty()),
+            possible: column.score.and_then(|s| s.possible),
+            attempts_allowed: column
+                .grading
+                .map(|g| g.attempts_allowed)
+                .filter(|n| *n > 0),
+        })
+    }
+
+    /// Get the given user's grades in a course, with one entry for each column they can see.
+    pub fn grades(&self, course_id: &str, user_id: &str) -> Result<Vec<Grade>> {
+        let columns = self
+            .get::<ColumnsResp>(&format!(
+                "learn/api/public/v2/courses/{}/gradebook/columns",
+                course_id
+            ))?
+            .results;
+        let user_grades = self
+            .get::<UserGradesResp>(&format!(
+                "learn/api/public/v2/courses/{}/gradebook/users/{}",
+                course_id, user_id
+            ))?
+            .results;
+
+        Ok(columns
+            .into_iter()
+            .map(|c| {
+                let grade = user_grades.iter().find(|g| g.column_id == c.id);
+                Grade {
+                    column_name: c.display_name.unwrap_or(c.name),
+                    column_id: c.id,
+                    score: grade.and_then(|g| g.score),
+                    possible: c.score.and_then(|s| s.possible),
+                    text: grade.and_then(|g| g.text.clone()),
+                }
+            })
+            .collect())
+    }
+}
@@ -1,20 +1,39 @@
 //! A wrapper around the Blackboard Learn API, specialised for Edinburgh University's instance.
 
+pub mod announcements;
 mod auth;
 pub mod content;
 pub mod course;
+pub mod discussions;
+pub mod gradebook;
 pub mod membership;
 pub mod terms;
 pub mod users;
 
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    ops::RangeInclusive,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
 
-pub use auth::{AuthState, Credentials, Error as AuthError, Password};
-use log::debug;
-use reqwest::blocking::{Client as HTTPClient, ClientBuilder as HTTPClientBuilder, Response};
+pub use auth::{validate_credentials, AuthState, Credentials, Error as AuthError, Password};
+use course::Course;
+use log::{debug, warn};
+use reqwest::{
+    blocking::{Client as HTTPClient, ClientBuilder as HTTPClientBuilder, Response},
+    header::RETRY_AFTER,
+    StatusCode,
+};
 use reqwest_cookie_store::{CookieStore, CookieStoreRwLock};
 use serde::Deserialize;
+use terms::Term;
 use thiserror::Error;
+use users::User;
 
 /// Result type used throughout
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -22,11 +41,100 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 /// The base of Edinburgh Uni's learn instance
 pub const LEARN_BASE: &str = "https://www.learn.ed.ac.uk/";
 
+/// Where to find each service we talk to.
+/// By default, these are Edinburgh Uni's, but they can be changed for testing.
+#[derive(Debug, Clone)]
+pub struct Endpoints {
+    /// The Learn instance itself, ie [`LEARN_BASE`]
+    pub learn: String,
+
+    /// EASE / CoSign, which we login to first
+    pub ease: String,
+
+    /// The SAML identity provider, which logs us into Learn
+    pub idp: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            learn: LEARN_BASE.to_string(),
+            ease: "https://www.ease.ed.ac.uk/".to_string(),
+            idp: "https://idp.ed.ac.uk/".to_string(),
+        }
+    }
+}
+
+/// How long we reuse information about the current user and terms for
+const PROFILE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// How long each request made while logging in can take, by default
+const DEFAULT_AUTH_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// How many times we back off and retry a rate-limited request before giving up
+const RATE_LIMIT_RETRIES: usize = 3;
+
+/// How long we back off for when rate-limited, if the server doesn't say, and the longest we'll wait if it does
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(2);
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// How many times in a row logging in can fail before we give up, and how long we wait after each failure
+const AUTH_RETRIES: usize = 3;
+const AUTH_RETRY_DELAY: Duration = Duration::from_millis(500);
+
 /// A client, for using the blackboard learn API
 pub struct Client {
-    pub creds: Credentials,
+    /// Shared between clones, so they all pick up new credentials from [`Self::set_credentials`]
+    creds: Arc<RwLock<Credentials>>,
     http: HTTPClient,
     cookies: Arc<CookieStoreRwLock>,
+    endpoints: Arc<Endpoints>,
+
+    /// How long each request made while logging in can take
+    auth_timeout: Duration,
+
+    /// Told the name of each service we contact while logging in, from [`Self::with_login_progress`]
+    login_progress: Option<Arc<dyn Fn(&'static str) + Send + Sync>>,
+
+    /// How many times in a row logging in has failed, shared between clones so they don't each keep trying
+    auth_failures: Arc<AtomicUsize>,
+
+    /// Courses we've already fetched, by ID
+    course_cache: Arc<RwLock<HashMap<String, Course>>>,
+
+    /// Users we've already fetched, by ID
+    user_cache: Arc<RwLock<HashMap<String, User>>>,
+
+    /// The current user and terms, which rarely change
+    me_cache: Arc<Expiring<User>>,
+    terms_cache: Arc<Expiring<Vec<Term>>>,
+}
+
+/// A cached value, which is only reused for [`PROFILE_TTL`]
+pub(crate) struct Expiring<T>(RwLock<Option<(Instant, T)>>);
+
+impl<T> Default for Expiring<T> {
+    fn default() -> Self {
+        Self(RwLock::new(None))
+    }
+}
+
+impl<T: Clone> Expiring<T> {
+    /// Get the value, if it's still fresh
+    pub(crate) fn get(&self) -> Option<T> {
+        match &*self.0.read().unwrap() {
+            Some((fetched, v)) if fetched.elapsed() < PROFILE_TTL => Some(v.clone()),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn set(&self, value: T) {
+        *self.0.write().unwrap() = Some((Instant::now(), value));
+    }
+
+    pub(crate) fn clear(&self) {
+        *self.0.write().unwrap() = None;
+    }
 }
 
 /// An error when using the learn API
@@ -51,6 +159,11 @@ pub enum Error {
     /// Might indicate the API has changed.
     #[error("content leaf was malformed")]
     BadContentLeaf,
+
+    /// Learn is rate-limiting us, and still was after backing off.
+    /// Contains how long the server asked us to wait, if it said.
+    #[error("Learn is limiting how many requests we can make - try again in a bit")]
+    RateLimited(Option<Duration>),
 }
 
 impl Client {
@@ -63,39 +176,101 @@ impl Client {
             .unwrap();
 
         Client {
-            creds,
+            creds: Arc::new(RwLock::new(creds)),
             http,
             cookies,
+            endpoints: Default::default(),
+            auth_timeout: DEFAULT_AUTH_TIMEOUT,
+            login_progress: None,
+            auth_failures: Default::default(),
+            course_cache: Default::default(),
+            user_cache: Default::default(),
+            me_cache: Default::default(),
+            terms_cache: Default::default(),
         }
     }
 
-    /// Create a ne wclient using the given credentials and authentication state
-    pub fn with_auth_state(
-        creds: Credentials,
-        state: AuthState,
-    ) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
-        let cookies = Arc::new(CookieStoreRwLock::new(CookieStore::load_json(
-            state.0.as_slice(),
-        )?));
+    /// Create a new client using the given credentials and authentication state.
+    /// If the state is corrupt, it's discarded and we'll login again when needed.
+    pub fn with_auth_state(creds: Credentials, state: AuthState) -> Self {
+        let store = CookieStore::load_json(state.0.as_slice()).unwrap_or_else(|e| {
+            warn!("discarding corrupt auth state: {}", e);
+            CookieStore::new(None)
+        });
+        let cookies = Arc::new(CookieStoreRwLock::new(store));
         let http = HTTPClientBuilder::new()
             .cookie_provider(cookies.clone())
             .build()
             .unwrap();
 
-        Ok(Self {
-            creds,
+        Self {
+            creds: Arc::new(RwLock::new(creds)),
             http,
             cookies,
-        })
+            endpoints: Default::default(),
+            auth_timeout: DEFAULT_AUTH_TIMEOUT,
+            login_progress: None,
+            auth_failures: Default::default(),
+            course_cache: Default::default(),
+            user_cache: Default::default(),
+            me_cache: Default::default(),
+            terms_cache: Default::default(),
+        }
     }
 
     /// Clone the current client, returning a new one.
-    /// The two clients will share the same authentication state, synchronised with a [`std::sync::RwLock`]
+    /// The two clients will share the same credentials, authentication state and caches, synchronised with a [`std::sync::RwLock`]
     pub fn clone_sharing_state(&self) -> Self {
         Self {
             creds: self.creds.clone(),
             http: self.http.clone(),
             cookies: self.cookies.clone(),
+            endpoints: self.endpoints.clone(),
+            auth_timeout: self.auth_timeout,
+            login_progress: self.login_progress.clone(),
+            auth_failures: self.auth_failures.clone(),
+            course_cache: self.course_cache.clone(),
+            user_cache: self.user_cache.clone(),
+            me_cache: self.me_cache.clone(),
+            terms_cache: self.terms_cache.clone(),
+        }
+    }
+
+    /// Get the credentials we login with
+    pub fn creds(&self) -> Credentials {
+        self.creds.read().unwrap().clone()
+    }
+
+    /// Login with the given credentials from now on, keeping the existing authentication state.
+    /// This also applies to any clients sharing our state, so re-authenticating doesn't need a new client.
+    pub fn set_credentials(&mut self, creds: Credentials) {
+        *self.creds.write().unwrap() = creds;
+        self.auth_failures.store(0, Ordering::Relaxed);
+    }
+
+    /// Use the given endpoints instead of Edinburgh Uni's
+    pub fn with_endpoints(mut self, endpoints: Endpoints) -> Self {
+        self.endpoints = Arc::new(endpoints);
+        self
+    }
+
+    /// Give up on each request made while logging in after the given time, instead of 20 seconds
+    pub fn with_auth_timeout(mut self, timeout: Duration) -> Self {
+        self.auth_timeout = timeout;
+        self
+    }
+
+    /// Call `f` with the name of each service we contact while logging in, ie `"the IDP"`, so progress can be shown.
+    /// This is called from whichever thread is logging in.
+    pub fn with_login_progress(mut self, f: impl Fn(&'static str) + Send + Sync + 'static) -> Self {
+        self.login_progress = Some(Arc::new(f));
+        self
+    }
+
+    /// Report that we're about to contact the given service while logging in
+    pub(crate) fn report_login_progress(&self, service: &'static str) {
+        if let Some(f) = &self.login_progress {
+            f(service);
         }
     }
 
@@ -110,53 +285,175 @@ impl Client {
     where
         F: FnMut() -> Result<T, Error>,
     {
-        match f() {
-            Err(Error::HTTPError(e)) => {
-                debug!("http error: {e}");
-                if e.status().filter(|c| c.as_u16() / 100 == 4).is_some() {
-                    self.authenticate()?;
-                    f()
-                } else {
-                    Err(Error::HTTPError(e))
+        let mut reauthenticated = false;
+        let mut rate_limited = 0;
+        loop {
+            match f() {
+                // back off instead of re-authenticating, since that would only make more requests
+                Err(Error::RateLimited(retry_after)) if rate_limited < RATE_LIMIT_RETRIES => {
+                    let wait = retry_after
+                        .unwrap_or(DEFAULT_RETRY_AFTER)
+                        .min(MAX_RETRY_AFTER);
+                    debug!("rate limited, waiting {wait:?}");
+                    thread::sleep(wait);
+                    rate_limited += 1;
+                }
+                Err(Error::HTTPError(e)) if !reauthenticated => {
+                    debug!("http error: {e}");
+                    if e.status().filter(|c| c.as_u16() / 100 == 4).is_some() {
+                        self.reauthenticate()?;
+                        reauthenticated = true;
+                    } else {
+                        return Err(Error::HTTPError(e));
+                    }
+                }
+                x => return x,
+            }
+        }
+    }
+
+    /// Login again after a request was rejected, waiting and retrying if it fails.
+    /// Wrong credentials and timeouts aren't retried, and once logging in has failed [`AUTH_RETRIES`] times in a row,
+    /// we return [`AuthError::RepeatedFailures`] without trying again.
+    fn reauthenticate(&self) -> Result<(), Error> {
+        loop {
+            let failures = self.auth_failures.load(Ordering::Relaxed);
+            if failures >= AUTH_RETRIES {
+                return Err(AuthError::RepeatedFailures.into());
+            }
+            if failures > 0 {
+                thread::sleep(AUTH_RETRY_DELAY * failures as u32);
+            }
+
+            match self.authenticate() {
+                Ok(()) => {
+                    self.auth_failures.store(0, Ordering::Relaxed);
+                    return Ok(());
+                }
+                // trying again would just fail again, and might get the account locked
+                Err(e @ (AuthError::LoginFailed | AuthError::InvalidCredentialsFormat(_))) => {
+                    self.auth_failures.store(AUTH_RETRIES, Ordering::Relaxed);
+                    return Err(e.into());
+                }
+                // the user should hear about this straight away, and it says nothing about their credentials
+                Err(e @ AuthError::Timeout(_)) => return Err(e.into()),
+                Err(e) => {
+                    warn!("error logging in, attempt {}: {}", failures + 1, e);
+                    self.auth_failures.fetch_add(1, Ordering::Relaxed);
                 }
             }
-            x => x,
         }
     }
 
     /// Send a get request, and deserialise.
     /// Also logs the response body if in debug mode.
     pub(crate) fn get<T: for<'a> Deserialize<'a>>(&self, url: &str) -> Result<T, Error> {
+        self.with_reattempt_auth(|| parse_response(check_status(self.send_get(url)?)?))
+    }
+
+    /// Like [`Self::get`], but returns `None` if the server responds with the given status.
+    /// This doesn't count as an authentication failure, so we don't login again.
+    pub(crate) fn get_unless<T: for<'a> Deserialize<'a>>(
+        &self,
+        url: &str,
+        status: StatusCode,
+    ) -> Result<Option<T>, Error> {
         self.with_reattempt_auth(|| {
-            let resp = self
-                .http
-                .get(format!("{}{}", LEARN_BASE, url))
-                .send()
-                .and_then(Response::error_for_status)?
-                .error_for_status()?;
-            if log::log_enabled!(log::Level::Debug) {
-                let s = resp.text()?;
-                debug!("response: {}", s);
-                Ok(serde_json::from_str(&s)?)
-            } else {
-                Ok(resp.json()?)
+            let resp = self.send_get(url)?;
+            if resp.status() == status {
+                return Ok(None);
             }
+
+            parse_response(check_status(resp)?).map(Some)
         })
     }
 
+    /// Like [`Self::get`], but for lists that may be split over several responses.
+    /// This follows `paging.nextPage` until it has every page.
+    pub(crate) fn get_paged<T: for<'a> Deserialize<'a>>(&self, url: &str) -> Result<Vec<T>, Error> {
+        let mut results = vec![];
+        let mut seen = HashSet::new();
+        let mut url = url.to_string();
+        loop {
+            let resp = self.get::<PagedResp<T>>(&url)?;
+            results.extend(resp.results);
+            seen.insert(url);
+
+            // next pages are relative to the root of the server, not the API
+            let Some(next) = resp.paging.and_then(|p| p.next_page) else {
+                return Ok(results);
+            };
+            let next = next.trim_start_matches('/').to_string();
+            if seen.contains(&next) {
+                warn!("server sent us back to a page we already have: {}", next);
+                return Ok(results);
+            }
+            url = next;
+        }
+    }
+
+    fn send_get(&self, url: &str) -> Result<Response, Error> {
+        Ok(self
+            .http
+            .get(format!("{}{}", self.endpoints.learn, url))
+            .send()?)
+    }
+
     /// Call server health endpoint
     pub fn health(&self) -> Result<HealthResp, Error> {
         self.with_reattempt_auth(|| {
             Ok(self
                 .http
-                .get(format!("{}institution/api/health", LEARN_BASE))
+                .get(format!("{}institution/api/health", self.endpoints.learn))
                 .send()
-                .and_then(Response::error_for_status)?
+                .map_err(Error::from)
+                .and_then(check_status)?
                 .json()?)
         })
     }
 }
 
+/// Deserialise a response, logging its body if in debug mode
+fn parse_response<T: for<'a> Deserialize<'a>>(resp: Response) -> Result<T, Error> {
+    if log::log_enabled!(log::Level::Debug) {
+        let s = resp.text()?;
+        debug!("response: {}", s);
+        Ok(serde_json::from_str(&s)?)
+    } else {
+        Ok(resp.json()?)
+    }
+}
+
+/// Turn error statuses into errors, picking out rate-limiting so we can back off
+fn check_status(resp: Response) -> Result<Response, Error> {
+    if resp.status() == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = resp
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse().ok())
+            .map(Duration::from_secs);
+        return Err(Error::RateLimited(retry_after));
+    }
+
+    Ok(resp.error_for_status()?)
+}
+
+/// One page of a list, from [`Client::get_paged`]
+#[derive(Deserialize)]
+struct PagedResp<T> {
+    results: Vec<T>,
+    #[serde(default)]
+    paging: Option<Paging>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Paging {
+    #[serde(default)]
+    next_page: Option<String>,
+}
+
 /// Response given by the health endpoint API
 #[derive(Debug, Deserialize, Clone)]
 pub struct HealthResp {
@@ -164,3 +461,73 @@ pub struct HealthResp {
     pub status: String,
     pub migration: String,
 }
+
+/// The major versions of Learn this client has been tested against.
+/// Others probably work, but the API may have changed.
+pub const TESTED_VERSIONS: RangeInclusive<u32> = 3800..=3900;
+
+impl HealthResp {
+    /// Get the major version of Learn, ie `3900` for `3900.82.0-rel.36+7d0a2a9`
+    pub fn major_version(&self) -> Option<u32> {
+        self.version.split(['.', '-']).next()?.parse().ok()
+    }
+
+    /// Check if the server's version is one we've tested against.
+    /// Versions we can't parse are assumed to be fine, since we don't know any better.
+    pub fn is_tested_version(&self) -> bool {
+        self.major_version()
+            .is_none_or(|v| TESTED_VERSIONS.contains(&v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_credentials_shared() {
+        let mut client = Client::new(("old".to_string(), "old".to_string().into()));
+        let worker = client.clone_sharing_state();
+
+        client.set_credentials(("new".to_string(), "new".to_string().into()));
+        assert_eq!(worker.creds().0, "new");
+    }
+
+    #[test]
+    fn test_password_serde() {
+        let password: Password = "hunter2".to_string().into();
+        let json = serde_json::to_string(&password).unwrap();
+        assert_eq!(json, "\"hunter2\"");
+
+        let password: Password = serde_json::from_str(&json).unwrap();
+        assert_eq!(String::from(password), "hunter2");
+    }
+
+    #[test]
+    fn test_tested_version() {
+        let health = |version: &str| HealthResp {
+            version: version.to_string(),
+            status: "OK".to_string(),
+            migration: "none".to_string(),
+        };
+
+        assert_eq!(
+            health("3900.82.0-rel.36+7d0a2a9").major_version(),
+            Some(3900)
+        );
+        assert!(health("3900.82.0-rel.36+7d0a2a9").is_tested_version());
+        assert!(!health("4100.1.0-rel.2").is_tested_version());
+        assert!(health("unknown").is_tested_version());
+    }
+
+    #[test]
+    fn test_garbage_auth_state_discarded() {
+        let client = Client::with_auth_state(
+            ("user".to_string(), "pass".to_string().into()),
+            AuthState(b"\x00not json{{".to_vec()),
+        );
+
+        // we should have a fresh, empty cookie store
+        assert_eq!(client.cookies.read().unwrap().iter_any().count(), 0);
+    }
+}
@@ -1,6 +1,7 @@
+use reqwest::StatusCode;
 use serde::Deserialize;
 
-use crate::{course::Course, Client, Result};
+use crate::{course::Course, users::User, Client, Result};
 
 /// Ties a user to a course
 #[derive(Debug, Deserialize)]
@@ -17,6 +18,37 @@ struct UserMembershipResp {
     results: Vec<UserMembership>,
 }
 
+/// Ties a course to a user enrolled in it
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CourseMembership {
+    pub id: String,
+    pub user_id: String,
+    pub course_id: String,
+
+    /// The user's role, ie `Instructor`, `TeachingAssistant` or `Student`.
+    /// Institutions can define their own roles too.
+    pub course_role_id: String,
+
+    /// The user, with some fields possibly hidden for privacy reasons
+    pub user: User,
+}
+
+impl CourseMembership {
+    /// Whether this user teaches or helps to run the course
+    pub fn is_staff(&self) -> bool {
+        matches!(
+            self.course_role_id.as_str(),
+            "Instructor" | "TeachingAssistant" | "CourseBuilder" | "Grader"
+        )
+    }
+}
+
+#[derive(Deserialize)]
+struct CourseMembershipResp {
+    results: Vec<CourseMembership>,
+}
+
 impl Client {
     pub fn user_memberships(&self, user_id: &str) -> Result<Vec<UserMembership>> {
         self.get::<UserMembershipResp>(&format!(
@@ -25,4 +57,19 @@ impl Client {
         ))
         .map(|r| r.results)
     }
+
+    /// Get the users enrolled in a course, and their roles.
+    /// Students often aren't allowed to see this, in which case the list is empty.
+    pub fn course_memberships(&self, course_id: &str) -> Result<Vec<CourseMembership>> {
+        Ok(self
+            .get_unless::<CourseMembershipResp>(
+                &format!(
+                    "learn/api/public/v1/courses/{}/users?expand=user",
+                    course_id
+                ),
+                StatusCode::FORBIDDEN,
+            )?
+            .map(|r| r.results)
+            .unwrap_or_default())
+    }
 }
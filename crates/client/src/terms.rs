@@ -1,12 +1,26 @@
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
 
 use crate::{Client, Result};
 
 /// A term / semester
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(from = "RawTerm")]
 pub struct Term {
     pub name: String,
     pub id: String,
+
+    /// When the term starts and ends, if set
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl Term {
+    /// Check if the given time falls within this term.
+    /// Missing start or end dates are treated as unbounded.
+    pub fn contains(&self, time: DateTime<Utc>) -> bool {
+        self.start.map(|s| s <= time).unwrap_or(true) && self.end.map(|e| time <= e).unwrap_or(true)
+    }
 }
 
 #[derive(Deserialize)]
@@ -14,9 +28,84 @@ struct RawResp {
     results: Vec<Term>,
 }
 
+#[derive(Deserialize)]
+struct RawTerm {
+    name: String,
+    id: String,
+    #[serde(default)]
+    availability: Option<RawAvailability>,
+}
+
+#[derive(Deserialize)]
+struct RawAvailability {
+    #[serde(default)]
+    duration: Option<RawDuration>,
+}
+
+#[derive(Deserialize)]
+struct RawDuration {
+    #[serde(default)]
+    start: Option<DateTime<Utc>>,
+    #[serde(default)]
+    end: Option<DateTime<Utc>>,
+}
+
+impl From<RawTerm> for Term {
+    fn from(raw: RawTerm) -> Self {
+        let duration = raw.availability.and_then(|a| a.duration);
+        Self {
+            name: raw.name,
+            id: raw.id,
+            start: duration.as_ref().and_then(|d| d.start),
+            end: duration.and_then(|d| d.end),
+        }
+    }
+}
+
 impl Client {
-    /// Get registered terms / semesters
+    /// Get registered terms / semesters.
+    /// This is cached for a few minutes - use [`Self::clear_profile_cache`] to force a refresh.
     pub fn terms(&self) -> Result<Vec<Term>> {
-        Ok(self.get::<RawResp>("learn/api/v1/terms")?.results)
+        if let Some(terms) = self.terms_cache.get() {
+            return Ok(terms);
+        }
+
+        let terms = self.get::<RawResp>("learn/api/v1/terms")?.results;
+        self.terms_cache.set(terms.clone());
+
+        Ok(terms)
+    }
+
+    /// Get only the terms / semesters happening right now
+    pub fn current_terms(&self) -> Result<Vec<Term>> {
+        let now = Utc::now();
+        Ok(self
+            .terms()?
+            .into_iter()
+            .filter(|t| t.contains(now))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_term_dates() {
+        let term: Term = serde_json::from_str(
+            r#"{"id": "_1_1", "name": "SEM1", "availability": {"duration": {"type": "DateRange", "start": "2023-09-01T00:00:00.000Z", "end": "2023-12-31T00:00:00.000Z"}}}"#,
+        )
+        .unwrap();
+
+        assert!(term.contains("2023-10-01T00:00:00Z".parse().unwrap()));
+        assert!(!term.contains("2024-01-15T00:00:00Z".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_term_no_dates() {
+        let term: Term = serde_json::from_str(r#"{"id": "_1_1", "name": "SEM1"}"#).unwrap();
+
+        assert!(term.contains(Utc::now()));
     }
 }
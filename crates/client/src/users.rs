@@ -4,32 +4,80 @@ use serde::{Deserialize, Serialize};
 
 use crate::{Client, Result};
 
-/// Information about a user
-#[derive(Debug, Serialize, Deserialize)]
+/// Information about a user.
+/// When looking up other users, some fields may be hidden for privacy reasons.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct User {
     /// Internal bblearn ID
     pub id: String,
 
     /// *Another* internal bblearn ID
-    pub uuid: String,
+    #[serde(default)]
+    pub uuid: Option<String>,
 
     /// An external student ID
-    pub student_id: String,
+    #[serde(default)]
+    pub student_id: Option<String>,
 
     pub user_name: String,
 
     /// First name
     pub given_name: String,
 
+    /// Last name
+    #[serde(default)]
+    pub family_name: Option<String>,
+
     /// Registered email address
-    pub email_address: String,
+    #[serde(default)]
+    pub email_address: Option<String>,
+}
+
+impl User {
+    /// Get the user's full name, as best we know it
+    pub fn display_name(&self) -> String {
+        match &self.family_name {
+            Some(f) => format!("{} {}", self.given_name, f),
+            None => self.given_name.clone(),
+        }
+    }
 }
 
 impl Client {
-    /// Get information about the currently logged in user
+    /// Get information about the currently logged in user.
+    /// This is cached for a few minutes - use [`Self::clear_profile_cache`] to force a refresh.
     pub fn me(&self) -> Result<User> {
-        self.get("learn/api/v1/users/me")
+        if let Some(me) = self.me_cache.get() {
+            return Ok(me);
+        }
+
+        let me: User = self.get("learn/api/v1/users/me")?;
+        self.me_cache.set(me.clone());
+
+        Ok(me)
+    }
+
+    /// Forget the cached user and terms, so they're fetched again next time
+    pub fn clear_profile_cache(&self) {
+        self.me_cache.clear();
+        self.terms_cache.clear();
+    }
+
+    /// Get information about another user.
+    /// Results are cached, since the same users tend to come up repeatedly (ie as authors).
+    pub fn user(&self, user_id: &str) -> Result<User> {
+        if let Some(u) = self.user_cache.read().unwrap().get(user_id) {
+            return Ok(u.clone());
+        }
+
+        let user: User = self.get(&format!("learn/api/v1/users/{}", user_id))?;
+        self.user_cache
+            .write()
+            .unwrap()
+            .insert(user_id.to_string(), user.clone());
+
+        Ok(user)
     }
 
     /// Get the current user's favourite courses.
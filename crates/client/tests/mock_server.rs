@@ -0,0 +1,610 @@
+//! Tests against a mock Learn server, serving canned responses.
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use edlearn_client::{content::ContentPayload, AuthError, Client, Endpoints, Error};
+use tiny_http::{Header, Response, Server};
+
+/// The requests a mock server has received, as `METHOD /path`
+type RequestLog = Arc<Mutex<Vec<String>>>;
+
+/// Start a mock server on a random port, which responds to each request with `handler(method, path)`.
+/// Returns a client pointed at it, and a log of the requests it receives.
+fn mock_server<F>(mut handler: F) -> (Client, RequestLog)
+where
+    F: FnMut(&str, &str) -> (u16, String) + Send + 'static,
+{
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let base = format!("http://{}/", server.server_addr().to_ip().unwrap());
+    let log = RequestLog::default();
+
+    let thread_log = log.clone();
+    thread::spawn(move || {
+        for req in server.incoming_requests() {
+            let method = req.method().to_string();
+            let path = req.url().split('?').next().unwrap().to_string();
+            thread_log
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", method, path));
+
+            let (status, body) = handler(&method, &path);
+            let mut resp = Response::from_string(body)
+                .with_status_code(status)
+                .with_header(Header::from_bytes("Content-Type", "application/json").unwrap());
+            // so rate-limiting tests don't have to wait
+            if status == 429 {
+                resp = resp.with_header(Header::from_bytes("Retry-After", "0").unwrap());
+            }
+            req.respond(resp).unwrap();
+        }
+    });
+
+    let client =
+        Client::new(("user".to_string(), "pass".to_string().into())).with_endpoints(Endpoints {
+            learn: format!("{}learn/", base),
+            ease: format!("{}ease/", base),
+            idp: format!("{}idp/", base),
+        });
+
+    (client, log)
+}
+
+/// Respond to the login flow successfully
+fn login_flow(method: &str, path: &str) -> Option<(u16, String)> {
+    match (method, path) {
+        ("GET", "/ease/") => Some((200, String::new())),
+        ("POST", "/ease/cosign.cgi") => Some((200, r#"<a href="/logout/logout.cgi">"#.into())),
+        ("GET", "/learn/auth-saml/saml/login") => {
+            Some((200, r#"<input name="SAMLRequest" value="req">"#.into()))
+        }
+        ("POST", "/idp/idp/profile/SAML2/POST/SSO") => {
+            Some((200, r#"<input name="SAMLResponse" value="resp">"#.into()))
+        }
+        ("POST", "/learn/auth-saml/saml/SSO/alias/_175_1") => Some((200, String::new())),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_content_children() {
+    let (client, log) = mock_server(|_, path| {
+        match path {
+        "/learn/learn/api/v1/courses/_1_1/contents/_2_1/children" => (
+            200,
+            r#"{"results": [
+                {"id": "_3_1", "parentId": "_2_1", "title": "Folder", "contentDetail": {"resource/x-bb-folder": {"isBbPage": false}}},
+                {"id": "_4_1", "parentId": "_2_1", "title": "Link", "contentDetail": {"resource/x-bb-externallink": {"url": "https://example.com"}}}
+            ]}"#
+            .into(),
+        ),
+        _ => (404, String::new()),
+    }
+    });
+
+    let children = client.content_children("_1_1", "_2_1").unwrap();
+    assert_eq!(children.len(), 2);
+    assert_eq!(children[0].title, "Folder");
+    assert!(matches!(children[0].payload, ContentPayload::Folder));
+    assert!(matches!(&children[1].payload, ContentPayload::Link(l) if l == "https://example.com"));
+
+    assert_eq!(log.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn test_page_text() {
+    let (client, _) = mock_server(|_, path| {
+        match path {
+        "/learn/learn/api/v1/courses/_1_1/contents/_2_1/children" => (
+            200,
+            r#"{"results": [{"id": "_3_1", "parentId": "_2_1", "title": "a", "body": {"rawText": "<p>hello</p>"}}]}"#
+                .into(),
+        ),
+        _ => (404, String::new()),
+    }
+    });
+
+    assert_eq!(client.page_text("_1_1", "_2_1").unwrap(), "<p>hello</p>");
+}
+
+#[test]
+fn test_reauthenticates_on_401() {
+    let mut authenticated = false;
+    let (client, log) = mock_server(move |method, path| {
+        if let Some(resp) = login_flow(method, path) {
+            authenticated |= path == "/learn/auth-saml/saml/SSO/alias/_175_1";
+            return resp;
+        }
+
+        match path {
+            "/learn/learn/api/v1/courses/_1_1/contents/ROOT/children" if authenticated => {
+                (200, r#"{"results": []}"#.into())
+            }
+            "/learn/learn/api/v1/courses/_1_1/contents/ROOT/children" => (401, String::new()),
+            _ => (404, String::new()),
+        }
+    });
+
+    let progress = Arc::new(Mutex::new(vec![]));
+    let client = client.with_login_progress({
+        let progress = progress.clone();
+        move |service| progress.lock().unwrap().push(service)
+    });
+
+    assert!(client.course_children("_1_1").unwrap().is_empty());
+    assert_eq!(
+        *progress.lock().unwrap(),
+        vec!["EASE", "Learn", "the IDP", "Learn"]
+    );
+
+    let log = log.lock().unwrap();
+    assert_eq!(
+        *log,
+        vec![
+            "GET /learn/learn/api/v1/courses/_1_1/contents/ROOT/children",
+            "GET /ease/",
+            "POST /ease/cosign.cgi",
+            "GET /learn/auth-saml/saml/login",
+            "POST /idp/idp/profile/SAML2/POST/SSO",
+            "POST /learn/auth-saml/saml/SSO/alias/_175_1",
+            "GET /learn/learn/api/v1/courses/_1_1/contents/ROOT/children",
+        ]
+    );
+}
+
+#[test]
+fn test_login_failed() {
+    let (client, log) = mock_server(|method, path| match (method, path) {
+        ("POST", "/ease/cosign.cgi") => (200, "wrong password".into()),
+        ("GET", "/ease/") => (200, String::new()),
+        _ => (401, String::new()),
+    });
+
+    assert!(matches!(
+        client.course_children("_1_1"),
+        Err(Error::AuthError(AuthError::LoginFailed))
+    ));
+
+    // wrong credentials aren't tried again
+    assert!(matches!(
+        client.course_children("_1_1"),
+        Err(Error::AuthError(AuthError::RepeatedFailures))
+    ));
+    let log = log.lock().unwrap();
+    assert_eq!(log.iter().filter(|r| r.contains("cosign")).count(), 1);
+}
+
+#[test]
+fn test_login_retried() {
+    let mut ease_requests = 0;
+    let mut authenticated = false;
+    let (client, _) = mock_server(move |method, path| {
+        if path == "/ease/" {
+            ease_requests += 1;
+            if ease_requests == 1 {
+                return (500, String::new());
+            }
+        }
+        if let Some(resp) = login_flow(method, path) {
+            authenticated |= path == "/learn/auth-saml/saml/SSO/alias/_175_1";
+            return resp;
+        }
+
+        match path {
+            "/learn/learn/api/v1/courses/_1_1/contents/ROOT/children" if authenticated => {
+                (200, r#"{"results": []}"#.into())
+            }
+            _ => (401, String::new()),
+        }
+    });
+
+    assert!(client.course_children("_1_1").unwrap().is_empty());
+}
+
+#[test]
+fn test_login_gives_up() {
+    let (client, log) = mock_server(|_, path| match path {
+        "/ease/" => (500, String::new()),
+        _ => (401, String::new()),
+    });
+
+    assert!(matches!(
+        client.course_children("_1_1"),
+        Err(Error::AuthError(AuthError::RepeatedFailures))
+    ));
+    let attempts = log.lock().unwrap().len();
+
+    // later requests don't keep trying to login
+    assert!(matches!(
+        client.course_children("_1_1"),
+        Err(Error::AuthError(AuthError::RepeatedFailures))
+    ));
+    let log = log.lock().unwrap();
+    assert_eq!(log.len(), attempts + 1);
+    assert_eq!(log.iter().filter(|r| *r == "GET /ease/").count(), 3);
+}
+
+#[test]
+fn test_invalid_credentials_rejected_early() {
+    let (mut client, log) = mock_server(|_, _| (401, String::new()));
+    client.set_credentials(("s123 4567".to_string(), "pass".to_string().into()));
+
+    assert!(matches!(
+        client.course_children("_1_1"),
+        Err(Error::AuthError(AuthError::InvalidCredentialsFormat(_)))
+    ));
+    assert!(log.lock().unwrap().iter().all(|r| !r.contains("/ease/")));
+}
+
+#[test]
+fn test_login_timeout() {
+    let (client, log) = mock_server(|method, path| match (method, path) {
+        ("GET", "/ease/") => {
+            thread::sleep(Duration::from_secs(1));
+            (200, String::new())
+        }
+        _ => (401, String::new()),
+    });
+    let client = client.with_auth_timeout(Duration::from_millis(200));
+
+    assert!(matches!(
+        client.course_children("_1_1"),
+        Err(Error::AuthError(AuthError::Timeout("EASE")))
+    ));
+
+    // timeouts aren't retried
+    let log = log.lock().unwrap();
+    assert_eq!(log.iter().filter(|r| *r == "GET /ease/").count(), 1);
+}
+
+#[test]
+fn test_rate_limited_backs_off() {
+    let mut requests = 0;
+    let (client, log) = mock_server(move |_, path| {
+        requests += 1;
+        match path {
+            _ if requests <= 2 => (429, String::new()),
+            "/learn/learn/api/v1/courses/_1_1/contents/ROOT/children" => {
+                (200, r#"{"results": []}"#.into())
+            }
+            _ => (404, String::new()),
+        }
+    });
+
+    assert!(client.course_children("_1_1").unwrap().is_empty());
+
+    // retried without trying to login again
+    assert_eq!(log.lock().unwrap().len(), 3);
+}
+
+#[test]
+fn test_rate_limited_gives_up() {
+    let (client, log) = mock_server(|_, _| (429, String::new()));
+
+    assert!(matches!(
+        client.course_children("_1_1"),
+        Err(Error::RateLimited(Some(d))) if d.is_zero()
+    ));
+    assert!(log.lock().unwrap().iter().all(|r| !r.contains("/ease/")));
+}
+
+#[test]
+fn test_schema_error() {
+    let (client, _) = mock_server(|_, _| (200, r#"{"results": 5}"#.into()));
+
+    assert!(matches!(
+        client.course_children("_1_1"),
+        Err(Error::HTTPError(_) | Error::SerdeError(_))
+    ));
+}
+
+#[test]
+fn test_single_content() {
+    let (client, _) = mock_server(|_, path| {
+        match path {
+        "/learn/learn/api/v1/courses/_1_1/contents/_3_1" => (
+            200,
+            r#"{"id": "_3_1", "parentId": "_2_1", "title": "Page", "contentDetail": {"resource/x-bb-folder": {"isBbPage": true}}}"#
+                .into(),
+        ),
+        _ => (404, String::new()),
+    }
+    });
+
+    let content = client.content("_1_1", "_3_1").unwrap().unwrap();
+    assert_eq!(content.title, "Page");
+    assert_eq!(content.parent_id, "_2_1");
+    assert_eq!(content.course_id, "_1_1");
+    assert!(matches!(content.payload, ContentPayload::Page));
+}
+
+#[test]
+fn test_single_content_missing() {
+    let (client, log) = mock_server(|method, path| {
+        login_flow(method, path).unwrap_or_else(|| (404, String::new()))
+    });
+
+    assert!(client.content("_1_1", "_3_1").unwrap().is_none());
+    assert_eq!(log.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn test_system_announcements() {
+    let (client, _) = mock_server(|_, path| {
+        match path {
+        "/learn/learn/api/public/v1/announcements" => (
+            200,
+            r#"{"results": [{"id": "_1_1", "title": "Maintenance", "body": "<p>Learn will be down</p>", "created": "2024-01-01T10:00:00.000Z"}]}"#
+                .into(),
+        ),
+        _ => (404, String::new()),
+    }
+    });
+
+    let announcements = client.system_announcements().unwrap();
+    assert_eq!(announcements.len(), 1);
+    assert_eq!(announcements[0].title, "Maintenance");
+    assert_eq!(
+        announcements[0].body.as_deref(),
+        Some("<p>Learn will be down</p>")
+    );
+}
+
+#[test]
+fn test_system_announcements_empty() {
+    let (client, _) = mock_server(|_, _| (200, "{}".into()));
+
+    assert!(client.system_announcements().unwrap().is_empty());
+}
+
+#[test]
+fn test_course_announcements() {
+    let (client, _) = mock_server(|_, path| match path {
+        "/learn/learn/api/public/v1/courses/_1_1/announcements" => (
+            200,
+            r#"{"results": [
+                {"id": "_1_1", "title": "Welcome", "created": "2024-01-01T10:00:00.000Z"},
+                {"id": "_2_1", "title": "Exam details", "created": "2024-03-01T10:00:00.000Z"}
+            ]}"#
+            .into(),
+        ),
+        _ => (404, String::new()),
+    });
+
+    let titles = client
+        .course_announcements("_1_1")
+        .unwrap()
+        .into_iter()
+        .map(|a| a.title)
+        .collect::<Vec<_>>();
+    assert_eq!(titles, vec!["Exam details", "Welcome"]);
+}
+
+#[test]
+fn test_course_memberships() {
+    let (client, _) = mock_server(|_, path| {
+        match path {
+        "/learn/learn/api/public/v1/courses/_1_1/users" => (
+            200,
+            r#"{"results": [
+                {"id": "_5_1", "userId": "_6_1", "courseId": "_1_1", "courseRoleId": "Instructor",
+                 "user": {"id": "_6_1", "userName": "lecturer", "givenName": "Ada", "familyName": "Lovelace", "emailAddress": "ada@example.com"}},
+                {"id": "_5_2", "userId": "_6_2", "courseId": "_1_1", "courseRoleId": "Student",
+                 "user": {"id": "_6_2", "userName": "s123", "givenName": "Sam"}}
+            ]}"#
+            .into(),
+        ),
+        _ => (404, String::new()),
+    }
+    });
+
+    let members = client.course_memberships("_1_1").unwrap();
+    assert_eq!(members.len(), 2);
+    assert!(members[0].is_staff());
+    assert_eq!(members[0].user.display_name(), "Ada Lovelace");
+    assert_eq!(
+        members[0].user.email_address.as_deref(),
+        Some("ada@example.com")
+    );
+    assert!(!members[1].is_staff());
+}
+
+#[test]
+fn test_course_memberships_forbidden() {
+    let (client, log) = mock_server(|method, path| {
+        login_flow(method, path).unwrap_or_else(|| (403, String::new()))
+    });
+
+    assert!(client.course_memberships("_1_1").unwrap().is_empty());
+
+    // not being allowed to see it doesn't mean we need to login again
+    assert_eq!(log.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn test_grades() {
+    let (client, _) = mock_server(|_, path| {
+        match path {
+        "/learn/learn/api/public/v2/courses/_1_1/gradebook/columns" => (
+            200,
+            r#"{"results": [
+                {"id": "_1_1", "name": "Coursework 1", "score": {"possible": 20}},
+                {"id": "_2_1", "name": "cw2", "displayName": "Coursework 2", "score": {"possible": 20}},
+                {"id": "_3_1", "name": "Exam", "score": {"possible": 100}}
+            ]}"#
+            .into(),
+        ),
+        "/learn/learn/api/public/v2/courses/_1_1/gradebook/users/_5_1" => (
+            200,
+            r#"{"results": [
+                {"userId": "_5_1", "columnId": "_1_1", "score": 15},
+                {"userId": "_5_1", "columnId": "_2_1", "score": 18, "text": "A"}
+            ]}"#
+            .into(),
+        ),
+        _ => (404, String::new()),
+    }
+    });
+
+    let grades = client.grades("_1_1", "_5_1").unwrap();
+    assert_eq!(grades.len(), 3);
+    assert_eq!(grades[0].display().as_deref(), Some("15 / 20"));
+    assert_eq!(grades[1].column_name, "Coursework 2");
+    assert_eq!(grades[1].display().as_deref(), Some("A"));
+    assert_eq!(grades[2].display(), None);
+}
+
+#[test]
+fn test_assessment_details() {
+    let (client, _) = mock_server(|_, path| {
+        match path {
+        "/learn/learn/api/public/v2/courses/_1_1/gradebook/columns/_2_1" => (
+            200,
+            r#"{"id": "_2_1", "name": "Quiz", "description": "<p>Answer every question</p>",
+                "score": {"possible": 10}, "grading": {"type": "Attempts", "attemptsAllowed": 2}}"#
+                .into(),
+        ),
+        "/learn/learn/api/public/v2/courses/_1_1/gradebook/columns/_3_1" => (
+            200,
+            r#"{"id": "_3_1", "name": "Essay", "description": " ", "grading": {"attemptsAllowed": 0}}"#
+                .into(),
+        ),
+        _ => (404, String::new()),
+    }
+    });
+
+    let quiz = client.assessment_details("_1_1", "_2_1").unwrap();
+    assert_eq!(
+        quiz.description.as_deref(),
+        Some("<p>Answer every question</p>")
+    );
+    assert_eq!(quiz.possible, Some(10.0));
+    assert_eq!(quiz.attempts_allowed, Some(2));
+
+    let essay = client.assessment_details("_1_1", "_3_1").unwrap();
+    assert_eq!(essay.description, None);
+    assert_eq!(essay.possible, None);
+    assert_eq!(essay.attempts_allowed, None);
+}
+
+#[test]
+fn test_content_children_paged() {
+    let mut page = 0;
+    let (client, log) = mock_server(move |_, path| match path {
+        "/learn/learn/api/v1/courses/_1_1/contents/_2_1/children" => {
+            page += 1;
+            if page == 1 {
+                (
+                    200,
+                    r#"{"results": [{"id": "_3_1", "parentId": "_2_1", "title": "One"}],
+                        "paging": {"nextPage": "/learn/api/v1/courses/_1_1/contents/_2_1/children?offset=1"}}"#
+                        .into(),
+                )
+            } else {
+                (
+                    200,
+                    r#"{"results": [{"id": "_4_1", "parentId": "_2_1", "title": "Two"}]}"#.into(),
+                )
+            }
+        }
+        _ => (404, String::new()),
+    });
+
+    let children = client.content_children("_1_1", "_2_1").unwrap();
+    assert_eq!(
+        children
+            .iter()
+            .map(|c| c.title.as_str())
+            .collect::<Vec<_>>(),
+        vec!["One", "Two"]
+    );
+    assert_eq!(log.lock().unwrap().len(), 2);
+}
+
+#[test]
+fn test_content_children_repeated_page() {
+    // a server that keeps sending us back to the first page
+    let (client, log) = mock_server(|_, path| match path {
+        "/learn/learn/api/v1/courses/_1_1/contents/ROOT/children" => (
+            200,
+            r#"{"results": [{"id": "_2_1", "parentId": "ROOT", "title": "One"}],
+                "paging": {"nextPage": "/learn/api/v1/courses/_1_1/contents/ROOT/children"}}"#
+                .into(),
+        ),
+        _ => (404, String::new()),
+    });
+
+    let children = client.course_children("_1_1").unwrap();
+    assert_eq!(children.len(), 1);
+    assert_eq!(log.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn test_course_tree() {
+    const FOLDER: &str = r#"{"resource/x-bb-folder": {"isBbPage": false}}"#;
+    let (client, log) = mock_server(|_, path| {
+        let results = match path {
+            "/learn/learn/api/v1/courses/_1_1/contents/ROOT/children" => format!(
+                r#"{{"id": "_2_1", "parentId": "ROOT", "title": "Week 1", "contentDetail": {FOLDER}}},
+                   {{"id": "_3_1", "parentId": "ROOT", "title": "Week 2", "contentDetail": {FOLDER}}},
+                   {{"id": "_4_1", "parentId": "ROOT", "title": "Page", "contentDetail": {{"resource/x-bb-folder": {{"isBbPage": true}}}}}}"#
+            ),
+            // the same folder of labs is linked from both weeks
+            "/learn/learn/api/v1/courses/_1_1/contents/_2_1/children"
+            | "/learn/learn/api/v1/courses/_1_1/contents/_3_1/children" => format!(
+                r#"{{"id": "_5_1", "parentId": "_2_1", "title": "Labs", "contentDetail": {FOLDER}}}"#
+            ),
+            "/learn/learn/api/v1/courses/_1_1/contents/_5_1/children" => {
+                r#"{"id": "_6_1", "parentId": "_5_1", "title": "Lab 1"}"#.to_string()
+            }
+            _ => return (404, String::new()),
+        };
+        (200, format!(r#"{{"results": [{}]}}"#, results))
+    });
+
+    // one request for the top level, then one for each folder down to the given depth
+    let tree = client.course_tree("_1_1", 1).unwrap();
+    assert_eq!(tree.len(), 3);
+    let week1 = tree[0].children.as_ref().unwrap();
+    assert_eq!(week1[0].content.title, "Labs");
+    assert!(week1[0].children.is_none());
+    assert!(tree[2].children.is_none());
+    assert_eq!(log.lock().unwrap().len(), 3);
+
+    log.lock().unwrap().clear();
+    assert!(client.course_tree("_1_1", 0).unwrap()[0].children.is_none());
+    assert_eq!(log.lock().unwrap().len(), 1);
+
+    // compared to opening every folder one at a time, the folder linked twice is only fetched once
+    log.lock().unwrap().clear();
+    let mut folders = client
+        .course_children("_1_1")
+        .unwrap()
+        .into_iter()
+        .filter(|c| c.is_container())
+        .collect::<Vec<_>>();
+    while let Some(folder) = folders.pop() {
+        folders.extend(
+            client
+                .content_children("_1_1", &folder.id)
+                .unwrap()
+                .into_iter()
+                .filter(|c| c.is_container()),
+        );
+    }
+    let lazy_requests = log.lock().unwrap().len();
+    assert_eq!(lazy_requests, 5);
+
+    log.lock().unwrap().clear();
+    let tree = client.course_tree("_1_1", 5).unwrap();
+    let labs = tree[0].children.as_ref().unwrap()[0]
+        .children
+        .as_ref()
+        .unwrap();
+    assert_eq!(labs[0].content.title, "Lab 1");
+    assert!(tree[1].children.as_ref().unwrap()[0].children.is_none());
+    assert_eq!(log.lock().unwrap().len(), 4);
+}
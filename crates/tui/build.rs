@@ -0,0 +1,19 @@
+use std::process::Command;
+
+/// Record the git commit we're built from, if we can find it.
+fn main() {
+    println!("cargo:rerun-if-changed=../../.git/HEAD");
+
+    let Ok(output) = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+    else {
+        return;
+    };
+    if !output.status.success() {
+        return;
+    }
+
+    let hash = String::from_utf8_lossy(&output.stdout);
+    println!("cargo:rustc-env=LEARN_TUI_GIT_HASH={}", hash.trim());
+}
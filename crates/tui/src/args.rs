@@ -0,0 +1,162 @@
+use anyhow::{anyhow, Result};
+use pico_args::Arguments;
+
+use crate::config::ThemeName;
+
+/// Command-line arguments
+#[derive(Debug, Clone, Default)]
+pub struct Args {
+    /// Course to jump to once logged in
+    pub course: Option<String>,
+
+    /// Content item within `course` to open once logged in
+    pub content: Option<String>,
+
+    /// Don't authenticate or make any requests
+    pub offline: bool,
+
+    /// Print the version and exit
+    pub version: bool,
+
+    /// Leave the mouse to the terminal, regardless of the config
+    pub no_mouse: bool,
+
+    /// Don't read or save the login or any other state, regardless of what's chosen at the login prompt
+    pub no_remember: bool,
+
+    /// Colours to use, instead of the configured ones
+    pub theme: Option<ThemeName>,
+
+    /// Print something to stdout instead of starting the TUI
+    pub dump: Option<DumpTarget>,
+}
+
+/// Something which can be printed by the `dump` subcommand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpTarget {
+    /// A JSON list of the user's courses
+    Courses,
+
+    /// The text of the page given by `--course` and `--content`
+    Page,
+}
+
+/// Something to open straight away on launch
+#[derive(Debug, Clone)]
+pub struct DeepLink {
+    pub course_id: String,
+    pub content_id: Option<String>,
+}
+
+impl Args {
+    /// Parse arguments from the environment
+    pub fn parse() -> Result<Self> {
+        Self::parse_from(Arguments::from_env())
+    }
+
+    fn parse_from(mut args: Arguments) -> Result<Self> {
+        let dump = match args.subcommand()?.as_deref() {
+            None => None,
+            Some("dump") => match args.opt_free_from_str::<String>()?.as_deref() {
+                Some("courses") => Some(DumpTarget::Courses),
+                Some("page") => Some(DumpTarget::Page),
+                _ => {
+                    return Err(anyhow!(
+                        "usage: dump (courses | page --course ID --content ID)"
+                    ))
+                }
+            },
+            Some(other) => return Err(anyhow!("unrecognised subcommand: {}", other)),
+        };
+        let parsed = Self {
+            course: args.opt_value_from_str("--course")?,
+            content: args.opt_value_from_str("--content")?,
+            offline: args.contains("--offline"),
+            version: args.contains(["-V", "--version"]),
+            no_mouse: args.contains("--no-mouse"),
+            no_remember: args.contains("--no-remember"),
+            theme: args.opt_value_from_str("--theme")?,
+            dump,
+        };
+
+        let rest = args.finish();
+        if !rest.is_empty() {
+            return Err(anyhow!("unrecognised arguments: {:?}", rest));
+        }
+        if parsed.content.is_some() && parsed.course.is_none() {
+            return Err(anyhow!("--content requires --course"));
+        }
+        if parsed.dump == Some(DumpTarget::Page) && parsed.content.is_none() {
+            return Err(anyhow!("dump page requires --course and --content"));
+        }
+        if parsed.dump.is_some() && parsed.no_remember {
+            return Err(anyhow!(
+                "dump uses the saved login, so can't be used with --no-remember"
+            ));
+        }
+
+        Ok(parsed)
+    }
+
+    /// Get what we were asked to open on launch, if anything
+    pub fn deep_link(&self) -> Option<DeepLink> {
+        Some(DeepLink {
+            course_id: self.course.clone()?,
+            content_id: self.content.clone(),
+        })
+    }
+}
+
+impl DeepLink {
+    /// Get a command which opens this again, ie the arguments it came from
+    pub fn command(&self) -> String {
+        let mut command = format!("{} --course {}", env!("CARGO_PKG_NAME"), self.course_id);
+        if let Some(content_id) = &self.content_id {
+            command.push_str(" --content ");
+            command.push_str(content_id);
+        }
+
+        command
+    }
+}
+
+/// Get the version we're running, including the git commit if it was known at build time
+pub fn version() -> String {
+    match option_env!("LEARN_TUI_GIT_HASH") {
+        Some(hash) => format!("learn-tui {} ({})", env!("CARGO_PKG_VERSION"), hash),
+        None => format!("learn-tui {}", env!("CARGO_PKG_VERSION")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deep_link_command_round_trip() {
+        let link = DeepLink {
+            course_id: "_12345_1".to_string(),
+            content_id: Some("_67890_1".to_string()),
+        };
+        let command = link.command();
+        assert_eq!(command, "edlearn_tui --course _12345_1 --content _67890_1");
+
+        let args = command.split(' ').skip(1).map(Into::into).collect();
+        let parsed = Args::parse_from(Arguments::from_vec(args))
+            .unwrap()
+            .deep_link()
+            .unwrap();
+        assert_eq!(parsed.course_id, link.course_id);
+        assert_eq!(parsed.content_id, link.content_id);
+    }
+
+    #[test]
+    fn test_no_remember() {
+        let parse = |args: &[&str]| {
+            Args::parse_from(Arguments::from_vec(args.iter().map(Into::into).collect()))
+        };
+        assert!(parse(&["--no-remember"]).unwrap().no_remember);
+        assert!(!parse(&[]).unwrap().no_remember);
+        assert!(parse(&["dump", "courses", "--no-remember"]).is_err());
+    }
+}
@@ -1,13 +1,19 @@
-use std::{env, fs::{remove_file, File, create_dir_all}};
+use std::{
+    env,
+    fs::{create_dir_all, remove_file, File},
+};
 
 use anyhow::{anyhow, Context, Result};
 use camino::Utf8PathBuf;
 use edlearn_client::{AuthState, Client, Credentials};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::args::Args;
 
 /// Caches credentials and authentication state
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthCache {
+    /// Needed to login again once the session expires, which the cookies in `auth_state` can't do alone
     pub creds: Credentials,
     auth_state: AuthState,
 }
@@ -19,18 +25,19 @@ impl AuthCache {
     pub fn from_client(client: &Client) -> Self {
         Self {
             auth_state: client.auth_state(),
-            creds: client.creds.clone(),
+            creds: client.creds(),
         }
     }
 
-    /// Get a client using this state
-    pub fn into_client(self) -> Result<Client> {
-        Ok(Client::with_auth_state(self.creds, self.auth_state).unwrap())
+    /// Get a client using this state.
+    /// If the saved cookies are corrupt, they're discarded and the client will login again.
+    pub fn into_client(self) -> Client {
+        Client::with_auth_state(self.creds, self.auth_state)
     }
 
     /// Clear the authentication cache, if it exists
     pub fn clear() -> Result<()> {
-        let Ok(path) = state_file_location() else {
+        let Ok(path) = state_file_location(FILE_NAME) else {
             return Ok(()); // already cleared
         };
 
@@ -39,28 +46,34 @@ impl AuthCache {
         Ok(())
     }
 
-    pub fn load() -> Result<Self> {
-        let path = state_file_location()?;
-        let file = File::open(path).context("error opening auth cache")?;
-        let config = serde_json::from_reader(&file).context("error deserialising auth cache")?;
+    /// Load the cache, unless `--no-remember` was passed, in which case it's left untouched
+    pub fn load_unless_forgetting(args: &Args) -> Result<Self> {
+        if args.no_remember {
+            return Err(anyhow!("not reading auth cache because of --no-remember"));
+        }
 
-        Ok(config)
+        Self::load()
     }
 
-    pub fn save(&self) -> Result<()> {
-        let path = state_file_location()?;
-        create_dir_all(path.parent().unwrap())?;
-        let mut file = File::create(path).context("error opening auth cache")?;
-
-        serde_json::to_writer(&mut file, &self).context("error deserialising auth cache")?;
+    pub fn load() -> Result<Self> {
+        load_state(FILE_NAME)
+    }
 
-        Ok(())
+    pub fn save(&self) -> Result<()> {
+        save_state(FILE_NAME, self)
     }
 }
 
+/// Environment variable which overrides where all of our state is kept
+const DATA_DIR_VAR: &str = "LEARN_TUI_DATA_DIR";
+
+/// Get the location of a file with the given name in our state directory.
+/// This is `$LEARN_TUI_DATA_DIR` if set, then `$XDG_STATE_DIR`, then `~/.local/.state`.
 #[cfg(not(target_os = "windows"))]
-fn state_file_location() -> Result<Utf8PathBuf> {
-    let mut out = if let Ok(loc) = env::var("XDG_STATE_DIR") {
+pub(crate) fn state_file_location(file_name: &str) -> Result<Utf8PathBuf> {
+    let mut out = if let Ok(loc) = env::var(DATA_DIR_VAR) {
+        Utf8PathBuf::from(loc)
+    } else if let Ok(loc) = env::var("XDG_STATE_DIR") {
         Utf8PathBuf::from(loc)
     } else {
         // Ok here, since this isn't compiled on windows.
@@ -70,15 +83,19 @@ fn state_file_location() -> Result<Utf8PathBuf> {
         home.push(".state");
         home.try_into().expect("non utf8 path")
     };
-    
-    out.push(FILE_NAME);
+
+    out.push(file_name);
 
     Ok(out)
 }
 
+/// Get the location of a file with the given name in our state directory.
+/// This is `%LEARN_TUI_DATA_DIR%` if set, then `%LOCALAPPDATA%`, then `~\AppData\Local`.
 #[cfg(target_os = "windows")]
-fn state_file_location() -> Result<Utf8PathBuf> {
-    let mut out = if let Ok(loc) = env::var("LOCALAPPDATA") {
+pub(crate) fn state_file_location(file_name: &str) -> Result<Utf8PathBuf> {
+    let mut out = if let Ok(loc) = env::var(DATA_DIR_VAR) {
+        Utf8PathBuf::from(loc)
+    } else if let Ok(loc) = env::var("LOCALAPPDATA") {
         Utf8PathBuf::from(loc)
     } else {
         // This method is deprecated because if you're using a *nix environment emulator like cygwin, it will return a unix-style path
@@ -91,8 +108,8 @@ fn state_file_location() -> Result<Utf8PathBuf> {
         home.push("Local");
         home.try_into().expect("non utf8 path")
     };
-    
-    out.push(FILE_NAME);
+
+    out.push(file_name);
 
     Ok(out)
 }
@@ -103,3 +120,35 @@ pub struct LoginDetails {
     pub creds: Credentials,
     pub remember: bool,
 }
+
+/// Read some state saved with [`save_state`] from the given file in our state directory
+pub(crate) fn load_state<T: DeserializeOwned>(file_name: &str) -> Result<T> {
+    let path = state_file_location(file_name)?;
+    let file = File::open(&path).with_context(|| format!("error opening {}", path))?;
+
+    serde_json::from_reader(&file).with_context(|| format!("error deserialising {}", path))
+}
+
+/// Save some state to the given file in our state directory, creating the directory if needed
+pub(crate) fn save_state<T: Serialize>(file_name: &str, state: &T) -> Result<()> {
+    let path = state_file_location(file_name)?;
+    create_dir_all(path.parent().unwrap())?;
+    let mut file = File::create(&path).with_context(|| format!("error opening {}", path))?;
+
+    serde_json::to_writer(&mut file, state).with_context(|| format!("error serialising {}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_corrupt_auth_state() {
+        let cache: AuthCache =
+            serde_json::from_str(r#"{"creds": ["user", "pass"], "auth_state": [110, 111, 116]}"#)
+                .unwrap();
+
+        // the credentials are still usable, so we can login again
+        assert_eq!(cache.into_client().creds().0, "user");
+    }
+}
@@ -0,0 +1,40 @@
+//! Access to the system clipboard
+use anyhow::{anyhow, Result};
+use arboard::Clipboard;
+
+/// Get the text on the clipboard, if there is any.
+/// Returns an error if the clipboard can't be accessed, or holds something other than text.
+pub fn paste() -> Result<Option<String>> {
+    let text = match Clipboard::new()?.get_text() {
+        Ok(t) => t,
+        Err(arboard::Error::ContentNotAvailable) => return Ok(None),
+        Err(arboard::Error::ConversionFailure) => {
+            return Err(anyhow!("clipboard doesn't contain text"))
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(Some(text).filter(|t| !t.is_empty()))
+}
+
+/// Puts text on the clipboard.
+///
+/// On X11 and Wayland, whatever we copy is only available while this is alive, unless a clipboard manager takes a copy.
+/// So this should be kept around for as long as the user might want to paste.
+#[derive(Default)]
+pub struct ClipboardOwner {
+    clipboard: Option<Clipboard>,
+}
+
+impl ClipboardOwner {
+    /// Put the given text on the clipboard
+    pub fn copy(&mut self, text: &str) -> Result<()> {
+        let clipboard = match &mut self.clipboard {
+            Some(c) => c,
+            None => self.clipboard.insert(Clipboard::new()?),
+        };
+        clipboard.set_text(text)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,428 @@
+use std::{
+    ffi::OsStr,
+    fs::File,
+    io::{self, Read},
+    process::{Child, Command, Output, Stdio},
+    str::FromStr,
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use bbml::{RenderOptions, TableBorders};
+use camino::{Utf8Path, Utf8PathBuf};
+use edlearn_client::content::ContentPayload;
+use log::warn;
+use serde::{de::value::StrDeserializer, Deserialize};
+
+use crate::{auth_cache::state_file_location, styles::Theme};
+
+/// User configuration.
+/// This is read from `learn-tui-config.json` in the state directory, and any missing keys take their default value.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Draw tables with plain ASCII characters, for terminals which don't display unicode properly
+    pub ascii_tables: bool,
+
+    /// How often to check our session is still valid, in seconds. 0 disables checking.
+    pub health_check_interval: u64,
+
+    /// Command used to open links and downloaded files, instead of the system default.
+    /// The target is appended as the last argument.
+    pub open_command: Option<String>,
+
+    /// Which pane is focused on startup
+    pub start_focus: StartFocus,
+
+    /// What the viewer shows on startup
+    pub start_document: StartDocument,
+
+    /// How many pages to fetch in the background when a folder is opened. 0 disables prefetching.
+    pub prefetch_pages: usize,
+
+    /// How downloaded files are arranged
+    pub download_layout: DownloadLayout,
+
+    /// How many lines the viewer scrolls by for each press of `j`/`k`
+    pub scroll_step: u16,
+
+    /// How far `Ctrl-D`/`Ctrl-U` scroll the viewer
+    pub jump_size: JumpSize,
+
+    /// Capture mouse events. If disabled, the terminal's own text selection works instead.
+    pub mouse: bool,
+
+    /// Which colours to use
+    pub theme: ThemeName,
+
+    /// Show a desktop notification when a download finishes.
+    /// This only works if we were built with the `notifications` feature.
+    pub download_notifications: bool,
+
+    /// Open every file once it's downloaded, as if it was downloaded with `D`
+    pub open_downloads: bool,
+
+    /// Kinds of content to leave out of the navigation tree, unless the user asks to show everything
+    pub hidden_content: Vec<ContentKind>,
+}
+
+/// The kind of some content, without any of its details
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentKind {
+    Link,
+    Folder,
+    Page,
+    File,
+    Placement,
+    Assessment,
+    Other,
+}
+
+impl ContentKind {
+    /// Get the kind of the given payload
+    pub fn of(payload: &ContentPayload) -> Self {
+        match payload {
+            ContentPayload::Link(_) => ContentKind::Link,
+            ContentPayload::Folder => ContentKind::Folder,
+            ContentPayload::Page => ContentKind::Page,
+            ContentPayload::File { .. } => ContentKind::File,
+            ContentPayload::Placement { .. } => ContentKind::Placement,
+            ContentPayload::Assessment { .. } => ContentKind::Assessment,
+            ContentPayload::Other => ContentKind::Other,
+        }
+    }
+}
+
+/// One of the colour palettes in [`Theme`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeName {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+}
+
+impl FromStr for ThemeName {
+    type Err = anyhow::Error;
+
+    /// Parse the same names as the config file, so `--theme` and `theme` always agree
+    fn from_str(s: &str) -> Result<Self> {
+        ThemeName::deserialize(StrDeserializer::<serde::de::value::Error>::new(s))
+            .map_err(|_| anyhow!("unknown theme {}, expected dark, light or high_contrast", s))
+    }
+}
+
+/// A pane which can be focused on startup
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartFocus {
+    #[default]
+    Navigation,
+    Viewer,
+}
+
+/// A document which can be shown on startup
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartDocument {
+    /// Whatever was being viewed last session
+    #[default]
+    Last,
+    Welcome,
+    Recent,
+    Downloads,
+}
+
+/// How downloaded files are arranged
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadLayout {
+    /// All files go straight into the download directory
+    #[default]
+    Flat,
+
+    /// Files go into folders for their course and the folders they're in on Learn
+    Structured,
+}
+
+/// How far to jump when scrolling by more than a line
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JumpSize {
+    #[default]
+    HalfPage,
+
+    /// A page, less one line so there's some context
+    FullPage,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            ascii_tables: false,
+            health_check_interval: 300,
+            open_command: None,
+            start_focus: StartFocus::default(),
+            start_document: StartDocument::default(),
+            prefetch_pages: 5,
+            download_layout: DownloadLayout::default(),
+            scroll_step: 1,
+            jump_size: JumpSize::default(),
+            mouse: true,
+            theme: ThemeName::default(),
+            download_notifications: false,
+            open_downloads: false,
+            hidden_content: vec![],
+        }
+    }
+}
+
+const FILE_NAME: &str = "learn-tui-config.json";
+
+impl Config {
+    /// Load the config file, or the default config if there isn't one
+    pub fn load() -> Result<Self> {
+        let path = state_file_location(FILE_NAME)?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let file = File::open(path).context("error opening config")?;
+        let config = serde_json::from_reader(&file).context("error deserialising config")?;
+
+        Ok(config)
+    }
+
+    /// Get how often to check our session is still valid, if at all
+    pub fn health_check_interval(&self) -> Option<Duration> {
+        match self.health_check_interval {
+            0 => None,
+            s => Some(Duration::from_secs(s)),
+        }
+    }
+
+    /// Open a link or file, with the configured command or the system default.
+    ///
+    /// If a custom command finishes quickly, this returns an error if it failed, or what it printed if it succeeded.
+    /// Commands which keep running, ie a browser, are left to finish in the background.
+    pub fn open(&self, target: impl AsRef<OsStr>) -> io::Result<Option<String>> {
+        let Some(cmd) = &self.open_command else {
+            return open::that(target).map(|_| None);
+        };
+
+        let mut parts = cmd.split_whitespace();
+        let Some(program) = parts.next() else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "open_command is empty",
+            ));
+        };
+
+        let mut child = Command::new(program)
+            .args(parts)
+            .arg(target)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let started = Instant::now();
+        while started.elapsed() < OPEN_COMMAND_WAIT {
+            if child.try_wait()?.is_some() {
+                return open_command_result(child.wait_with_output()?);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        reap_in_background(child);
+        Ok(None)
+    }
+
+    /// Show a file in the file manager, selecting it where the platform supports that.
+    /// Otherwise, or if a custom open command is set, just open the folder containing it.
+    /// Returns the path that was opened.
+    pub fn reveal(&self, file: &Utf8Path) -> io::Result<Utf8PathBuf> {
+        let file = file.canonicalize_utf8()?;
+
+        if self.open_command.is_none() {
+            #[cfg(target_os = "macos")]
+            {
+                Command::new("open").arg("-R").arg(&file).spawn()?;
+                return Ok(file);
+            }
+
+            #[cfg(target_os = "windows")]
+            {
+                Command::new("explorer")
+                    .arg(format!("/select,{}", file))
+                    .spawn()?;
+                return Ok(file);
+            }
+        }
+
+        let dir = file
+            .parent()
+            .map(Utf8Path::to_path_buf)
+            .unwrap_or_else(|| file.clone());
+        self.open(&dir)?;
+
+        Ok(dir)
+    }
+
+    /// Get how many lines to jump by when scrolling an area with the given height
+    pub fn jump_lines(&self, height: u16) -> u16 {
+        match self.jump_size {
+            JumpSize::HalfPage => height / 2,
+            JumpSize::FullPage => height.saturating_sub(1),
+        }
+    }
+
+    /// Check if content with the given payload should be left out of the navigation tree
+    pub fn is_hidden(&self, payload: &ContentPayload) -> bool {
+        self.hidden_content.contains(&ContentKind::of(payload))
+    }
+
+    /// Get the colours to use
+    pub fn theme(&self) -> &'static Theme {
+        match self.theme {
+            ThemeName::Dark => &Theme::DARK,
+            ThemeName::Light => &Theme::LIGHT,
+            ThemeName::HighContrast => &Theme::HIGH_CONTRAST,
+        }
+    }
+
+    /// Get the options to use when rendering bbml into an area with the given width
+    pub fn render_options(&self, width: usize) -> RenderOptions {
+        RenderOptions {
+            width,
+            table_borders: if self.ascii_tables {
+                TableBorders::Ascii
+            } else {
+                TableBorders::Unicode
+            },
+            link_base: Some(edlearn_client::LEARN_BASE.to_string()),
+            theme: self.theme().page,
+        }
+    }
+}
+
+/// How long to wait for a custom open command to finish, so we can tell the user if it failed
+const OPEN_COMMAND_WAIT: Duration = Duration::from_millis(300);
+
+/// Turn a finished open command into an error if it failed, or what it printed if anything
+fn open_command_result(output: Output) -> io::Result<Option<String>> {
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(format!(
+            "open_command {}: {}",
+            output.status,
+            stderr.trim()
+        )));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok((!stdout.is_empty()).then_some(stdout))
+}
+
+/// Wait for a command which is still running on another thread, so it doesn't stay around as a zombie once it exits
+fn reap_in_background(mut child: Child) {
+    // keep reading its output, so it doesn't block once the pipes fill
+    let outputs: [Option<Box<dyn Read + Send>>; 2] = [
+        child.stdout.take().map(|o| Box::new(o) as _),
+        child.stderr.take().map(|o| Box::new(o) as _),
+    ];
+    for mut output in outputs.into_iter().flatten() {
+        thread::spawn(move || io::copy(&mut output, &mut io::sink()));
+    }
+
+    thread::spawn(move || match child.wait() {
+        Ok(status) if !status.success() => warn!("open_command {status}"),
+        Err(e) => warn!("error waiting for open_command: {e}"),
+        Ok(_) => (),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jump_lines() {
+        let mut config = Config::default();
+        assert_eq!(config.jump_lines(40), 20);
+
+        config.jump_size = JumpSize::FullPage;
+        assert_eq!(config.jump_lines(40), 39);
+        assert_eq!(config.jump_lines(0), 0);
+    }
+
+    #[test]
+    fn test_open_failure_is_error() {
+        let config = Config {
+            open_command: Some("learn-tui-nonexistent-command".to_string()),
+            ..Default::default()
+        };
+        assert!(config.open("https://example.com").is_err());
+        assert!(config.reveal(Utf8Path::new("nonexistent-file")).is_err());
+
+        let config = Config {
+            open_command: Some("   ".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.open("https://example.com").unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_open_command_result() {
+        let config = |cmd: &str| Config {
+            open_command: Some(cmd.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            config("echo")
+                .open("https://example.com")
+                .unwrap()
+                .as_deref(),
+            Some("https://example.com")
+        );
+        assert_eq!(config("true").open("https://example.com").unwrap(), None);
+        assert!(config("false").open("https://example.com").is_err());
+
+        // still running, so left in the background
+        assert_eq!(config("sleep").open("5").unwrap(), None);
+    }
+
+    #[test]
+    fn test_theme_names() {
+        let config: Config = serde_json::from_str(r#"{"theme": "high_contrast"}"#).unwrap();
+        assert_eq!(config.theme, ThemeName::HighContrast);
+        assert_eq!(config.render_options(70).theme, bbml::Theme::HIGH_CONTRAST);
+
+        assert_eq!("light".parse::<ThemeName>().unwrap(), ThemeName::Light);
+        assert_eq!(
+            "high_contrast".parse::<ThemeName>().unwrap(),
+            ThemeName::HighContrast
+        );
+        assert!("high-contrast".parse::<ThemeName>().is_err());
+        assert!("solarized".parse::<ThemeName>().is_err());
+    }
+
+    #[test]
+    fn test_hidden_content() {
+        let config: Config =
+            serde_json::from_str(r#"{"hidden_content": ["placement", "assessment"]}"#).unwrap();
+        assert!(config.is_hidden(&ContentPayload::Placement {
+            name: "Zoom",
+            url: String::new(),
+        }));
+        assert!(!config.is_hidden(&ContentPayload::Page));
+        assert!(!Config::default().is_hidden(&ContentPayload::Other));
+    }
+}
@@ -0,0 +1,76 @@
+//! The `dump` subcommand, which prints things to stdout for use in scripts, instead of starting the TUI.
+use anyhow::{anyhow, Context, Result};
+use bbml::render_to_plaintext;
+use edlearn_client::Client;
+use serde_json::json;
+
+use crate::{
+    args::{Args, DumpTarget},
+    auth_cache::AuthCache,
+    config::Config,
+};
+
+/// The width pages are rendered at, since we don't have a terminal to fit
+const PAGE_WIDTH: usize = 80;
+
+/// Print the given target to stdout, logging in with the saved credentials
+pub fn run(target: DumpTarget, args: &Args, config: &Config) -> Result<()> {
+    let cache = AuthCache::load().map_err(|_| {
+        anyhow!("not logged in - run learn-tui and choose to remember your login first")
+    })?;
+    let client = cache.into_client();
+
+    let res = match target {
+        DumpTarget::Courses => dump_courses(&client),
+        DumpTarget::Page => dump_page(&client, args, config),
+    };
+
+    // keep our session, so the next run doesn't have to log in again
+    AuthCache::from_client(&client).save()?;
+
+    res
+}
+
+/// Print a JSON list of the user's courses
+fn dump_courses(client: &Client) -> Result<()> {
+    let me = client.me().context("error getting user")?;
+    let courses = client
+        .user_memberships(&me.id)
+        .context("error getting courses")?
+        .into_iter()
+        .map(|m| {
+            json!({
+                "id": m.course.id,
+                "course_id": m.course.course_id,
+                "name": m.course.name,
+                "term_id": m.course.term_id,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    println!("{}", serde_json::to_string_pretty(&courses)?);
+
+    Ok(())
+}
+
+/// Print the text of a page, followed by the links in it
+fn dump_page(client: &Client, args: &Args, config: &Config) -> Result<()> {
+    let (Some(course_id), Some(content_id)) = (&args.course, &args.content) else {
+        return Err(anyhow!("dump page requires --course and --content"));
+    };
+
+    let html = client
+        .page_text(course_id, content_id)
+        .context("error getting page")?;
+    let (text, links) = render_to_plaintext(&html, &config.render_options(PAGE_WIDTH));
+
+    println!("{}", text);
+    if !links.is_empty() {
+        println!();
+        for (i, link) in links.iter().enumerate() {
+            println!("[{}] {}", i, link);
+        }
+    }
+
+    Ok(())
+}
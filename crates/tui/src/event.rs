@@ -23,8 +23,13 @@ pub enum Event {
     /// Terminal resize.
     Resize(u16, u16),
 
+    /// Text pasted into the terminal.
+    /// Use [`strip_control`] before inserting it anywhere.
+    Paste(String),
+
     /// Some data for the store, sent by the worker.
-    Store(store::Event),
+    /// It's only meant for the store with the given epoch.
+    Store(store::Epoch, store::Event),
 }
 
 /// The event bus aggregates events from multiple threads, and joins all the threads back when required.
@@ -100,6 +105,7 @@ impl EventBus {
                     }
                     CrosstermEvent::Mouse(e) => sender.send(Event::Mouse(e)),
                     CrosstermEvent::Resize(w, h) => sender.send(Event::Resize(w, h)),
+                    CrosstermEvent::Paste(s) => sender.send(Event::Paste(s)),
                     _ => Ok(()),
                 }
                 .expect("failed to send terminal event");
@@ -111,6 +117,11 @@ impl EventBus {
     }
 }
 
+/// Remove control characters (including newlines) from pasted text, so it can go in a single-line input.
+pub fn strip_control(s: &str) -> String {
+    s.chars().filter(|c| !c.is_control()).collect()
+}
+
 impl Drop for EventBus {
     fn drop(&mut self) {
         self.running.store(false, Ordering::Relaxed);
@@ -1,36 +1,46 @@
 use std::rc::Rc;
 
 use crate::{
+    args::Args,
     auth_cache::LoginDetails,
-    event::{Event, EventBus},
+    clipboard,
+    config::Config,
+    event::{strip_control, Event, EventBus},
     main_screen::MainScreen,
     ExitState, Screen,
 };
 use anyhow::Result;
 use crossterm::event::{KeyCode, KeyModifiers};
+use edlearn_client::{validate_credentials, AuthError};
+use log::debug;
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Paragraph, Wrap},
 };
+use zeroize::Zeroizing;
 
 /// Prompts the user for their credentials
 pub struct LoginPrompt {
     username: String,
-    password: String,
+    password: Zeroizing<String>,
     remember: bool,
     selected: SelectedInput,
     message: &'static str,
     events: Rc<EventBus>,
+    config: Rc<Config>,
+    args: Rc<Args>,
 }
 
 impl LoginPrompt {
     /// Create a blank form for credentials.
-    /// The given [`EventBus`] will be used to initialise the [`MainScreen`] once the user submits.
-    pub fn new(events: Rc<EventBus>) -> Self {
+    /// The given [`EventBus`], [`Config`] and [`Args`] will be used to initialise the [`MainScreen`] once the user submits.
+    pub fn new(events: Rc<EventBus>, config: Rc<Config>, args: Rc<Args>) -> Self {
         Self {
             events,
+            config,
+            args,
             username: String::new(),
-            password: String::new(),
+            password: Default::default(),
             remember: false,
             selected: SelectedInput::Username,
             message: "",
@@ -39,16 +49,49 @@ impl LoginPrompt {
 
     /// Create a blank form with the given message.
     /// This can be used to re-prompt for authentication, etc.
-    pub fn new_with_msg(events: Rc<EventBus>, message: &'static str) -> Self {
+    pub fn new_with_msg(
+        events: Rc<EventBus>,
+        config: Rc<Config>,
+        args: Rc<Args>,
+        message: &'static str,
+    ) -> Self {
         Self {
             events,
+            config,
+            args,
             username: String::new(),
-            password: String::new(),
+            password: Default::default(),
             remember: false,
             selected: SelectedInput::Username,
             message,
         }
     }
+
+    /// Add text to the end of the selected field, removing any control characters
+    fn insert_text(&mut self, text: &str) {
+        match self.selected {
+            SelectedInput::Username => self.username.push_str(&strip_control(text)),
+            SelectedInput::Password => self.password.push_str(&strip_control(text)),
+            SelectedInput::Remember => (),
+        };
+    }
+
+    /// Toggle whether to save the login, unless `--no-remember` was passed
+    fn toggle_remember(&mut self) {
+        self.remember = !self.remember && !self.args.no_remember;
+    }
+
+    /// Add the clipboard's contents to the selected field
+    fn paste_from_clipboard(&mut self) {
+        match clipboard::paste() {
+            Ok(Some(text)) => self.insert_text(&text),
+            Ok(None) => self.message = "Clipboard is empty.",
+            Err(e) => {
+                debug!("error reading clipboard: {:?}", e);
+                self.message = "Couldn't read text from the clipboard.";
+            }
+        }
+    }
 }
 
 impl Screen for LoginPrompt {
@@ -76,10 +119,11 @@ impl Screen for LoginPrompt {
         let password_para =
             Paragraph::new(format!("Password: {}", "*".repeat(self.password.len())))
                 .block(Block::new().borders(self.selected.borders_for(SelectedInput::Password)));
-        let remember_para = Paragraph::new(format!(
-            "Remember? {}",
-            if self.remember { "Y" } else { "N" }
-        ))
+        let remember_para = Paragraph::new(match (self.args.no_remember, self.remember) {
+            (true, _) => "Remember? N (--no-remember)",
+            (false, true) => "Remember? Y",
+            (false, false) => "Remember? N",
+        })
         .block(Block::new().borders(self.selected.borders_for(SelectedInput::Remember)));
 
         let header_para = Paragraph::new("Login")
@@ -97,6 +141,10 @@ impl Screen for LoginPrompt {
         frame.render_widget(message_para, layout[6]);
     }
     fn handle_event(&mut self, event: Event) -> Result<ExitState> {
+        if let Event::Paste(s) = &event {
+            self.insert_text(s);
+        }
+
         if let Event::Key(k) = event {
             match k.code {
                 // Quit shortcuts
@@ -105,6 +153,11 @@ impl Screen for LoginPrompt {
                     return Ok(ExitState::Quit);
                 }
 
+                // Paste from the clipboard, ie from a password manager
+                KeyCode::Char('y') | KeyCode::Char('Y') if k.modifiers == KeyModifiers::CONTROL => {
+                    self.paste_from_clipboard();
+                }
+
                 // Navigate form fields
                 KeyCode::Tab | KeyCode::Down => self.selected.down(),
                 KeyCode::BackTab | KeyCode::Up => self.selected.up(),
@@ -114,7 +167,7 @@ impl Screen for LoginPrompt {
                 KeyCode::Char(c) if !c.is_control() => match self.selected {
                     SelectedInput::Username => self.username.push(c),
                     SelectedInput::Password => self.password.push(c),
-                    SelectedInput::Remember => self.remember = !self.remember,
+                    SelectedInput::Remember => self.toggle_remember(),
                 },
                 KeyCode::Backspace => match self.selected {
                     SelectedInput::Username => {
@@ -123,20 +176,25 @@ impl Screen for LoginPrompt {
                     SelectedInput::Password => {
                         self.password.pop();
                     }
-                    SelectedInput::Remember => self.remember = !self.remember,
+                    SelectedInput::Remember => self.toggle_remember(),
                 },
 
                 // Submit
                 KeyCode::Enter => {
-                    if self.username.is_empty() {
-                        self.message = "Username is empty!";
-                    } else if self.password.is_empty() {
-                        self.message = "Password is empty!";
+                    if let Err(AuthError::InvalidCredentialsFormat(msg)) =
+                        validate_credentials(&self.username, &self.password)
+                    {
+                        self.message = msg;
                     } else {
                         return Ok(ExitState::ChangeScreen(Box::new(MainScreen::new(
                             self.events.clone(),
+                            self.config.clone(),
+                            self.args.clone(),
                             LoginDetails {
-                                creds: (self.username.clone(), self.password.clone().into()),
+                                creds: (
+                                    self.username.clone(),
+                                    std::mem::take(&mut *self.password).into(),
+                                ),
                                 remember: self.remember,
                             },
                         ))));
@@ -19,34 +19,65 @@
 //!
 //! The latter 2 receive commands from their own channels, and are driven by methods in [`store::Store`].
 use anyhow::Result;
+use camino::Utf8PathBuf;
 use event::{Event, EventBus};
 use log::debug;
 use main_screen::MainScreen;
 use ratatui::prelude::*;
 use simplelog::{LevelFilter, WriteLogger};
-use std::{env, fs::File, io, rc::Rc};
+use std::{
+    env,
+    fs::{create_dir_all, File},
+    io,
+    rc::Rc,
+    str::FromStr,
+};
 
 use crate::{
-    auth_cache::{AuthCache, LoginDetails},
+    args::Args,
+    auth_cache::{state_file_location, AuthCache, LoginDetails},
+    config::Config,
     login_prompt::LoginPrompt,
 };
 
+pub mod args;
 pub mod auth_cache;
+pub mod clipboard;
+pub mod config;
+pub mod dump;
 pub mod event;
 pub mod login_prompt;
 pub mod main_screen;
+pub mod notifications;
 pub mod store;
 pub mod styles;
 pub mod tui;
 
+const LOG_FILE_NAME: &str = "learn-tui.log";
+
 pub fn main() -> Result<()> {
+    let args = Rc::new(Args::parse()?);
+    if args.version {
+        println!("{}", args::version());
+        return Ok(());
+    }
+
     init_logging();
+    let mut config = Config::load()?;
+    if let Some(theme) = args.theme {
+        config.theme = theme;
+    }
+    let config = Rc::new(config);
+
+    if let Some(target) = args.dump {
+        return dump::run(target, &args, &config);
+    }
 
     // Initialise terminal
     let mut terminal = Terminal::new(CrosstermBackend::new(io::stderr()))?;
-    tui::init(&mut terminal)?;
+    tui::init(&mut terminal, config.mouse && !args.no_mouse)?;
 
-    let res = run_in_terminal(&mut terminal);
+    let res = run_in_terminal(&mut terminal, config, args);
 
     // Cleanup
     debug!("exiting");
@@ -59,20 +90,36 @@ pub fn main() -> Result<()> {
     Ok(())
 }
 
-fn run_in_terminal<B: Backend>(terminal: &mut Terminal<B>) -> Result<()> {
+fn run_in_terminal<B: Backend>(
+    terminal: &mut Terminal<B>,
+    config: Rc<Config>,
+    args: Rc<Args>,
+) -> Result<()> {
     let bus = Rc::new(EventBus::new());
     bus.spawn_terminal_listener();
 
     // Login screen if needed, or just the app
-    let app: Box<dyn Screen> = match AuthCache::load() {
+    let app: Box<dyn Screen> = match AuthCache::load_unless_forgetting(&args) {
+        // we won't make any requests, so credentials don't matter
+        _ if args.offline => Box::new(MainScreen::new(
+            bus.clone(),
+            config,
+            args,
+            LoginDetails {
+                creds: (String::new(), String::new().into()),
+                remember: false,
+            },
+        )),
         Ok(a) => Box::new(MainScreen::new(
             bus.clone(),
+            config,
+            args,
             LoginDetails {
                 creds: a.creds,
                 remember: true,
             },
         )),
-        Err(_) => Box::new(LoginPrompt::new(bus.clone())),
+        Err(_) => Box::new(LoginPrompt::new(bus.clone(), config, args)),
     };
 
     // Start everything
@@ -120,14 +167,28 @@ pub fn main_loop<B: Backend>(
     Ok(())
 }
 
+/// Start logging, if requested.
+///
+/// `LEARN_TUI_LOG` enables logging, and may be set to a level (ie `info`). If it isn't a valid level, we log everything at debug level.
+/// `LEARN_TUI_LOG_FILE` overrides where we log to, which is otherwise the state directory.
 fn init_logging() {
-    // Log if environment variable set
-    if env::var("LEARN_TUI_LOG").is_ok() {
-        WriteLogger::init(
-            LevelFilter::Debug,
-            simplelog::Config::default(),
-            File::create(".learn-tui.log").unwrap(),
-        )
-        .unwrap();
+    let Ok(level) = env::var("LEARN_TUI_LOG") else {
+        return;
+    };
+    let level = LevelFilter::from_str(&level).unwrap_or(LevelFilter::Debug);
+
+    let path = match env::var("LEARN_TUI_LOG_FILE") {
+        Ok(p) => Utf8PathBuf::from(p),
+        Err(_) => state_file_location(LOG_FILE_NAME).unwrap_or_else(|_| LOG_FILE_NAME.into()),
+    };
+    if let Some(parent) = path.parent().filter(|p| !p.as_str().is_empty()) {
+        create_dir_all(parent).unwrap();
     }
+
+    WriteLogger::init(
+        level,
+        simplelog::Config::default(),
+        File::create(path).unwrap(),
+    )
+    .unwrap();
 }
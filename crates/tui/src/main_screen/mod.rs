@@ -6,19 +6,28 @@ use edlearn_client::Client;
 use log::{debug, error};
 use ratatui::{
     prelude::{Constraint, Direction, Layout, Rect},
-    text::Text,
-    widgets::{Block, Borders, Paragraph},
+    text::{Span, Text},
+    widgets::{Block, Borders, Paragraph, Wrap},
     Frame,
 };
 
 use crate::{
+    args::Args,
     auth_cache::{AuthCache, LoginDetails},
+    clipboard::ClipboardOwner,
+    config::{Config, StartDocument, StartFocus},
     event::{Event, EventBus},
     login_prompt::LoginPrompt,
-    store::Store,
+    store::{Event as StoreEvent, Store},
+    styles::{error_text, pane_border, Theme},
     ExitState, Screen,
 };
 
+/// The smallest terminal we'll try to draw the main screen in.
+/// Below this, we just ask the user to make it bigger.
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 10;
+
 pub mod panes;
 use panes::{Document, Navigation};
 
@@ -35,11 +44,17 @@ pub enum Action {
     /// Tell the viewer to show something, and focus the viewer
     Show(Document),
 
+    /// Tell the viewer to show something, but leave the focus where it is
+    Preview(Document),
+
+    /// Put the given text on the clipboard, ie a command to bookmark or share
+    Copy(String),
+
     /// Focus the navigation pane
     FocusNavigation,
 
-    /// Go back to the login screen
-    Reauthenticate,
+    /// Go back to the login screen, showing the given message
+    Reauthenticate(&'static str),
 
     /// Display the given string at the bottom of the screen
     Flash(Text<'static>),
@@ -62,14 +77,24 @@ pub struct MainScreen {
 
     flash: Text<'static>,
 
+    /// Kept for the whole session, since copied text may disappear once it's dropped
+    clipboard: ClipboardOwner,
+
     events: Rc<EventBus>,
+    config: Rc<Config>,
+    args: Rc<Args>,
 }
 
 impl MainScreen {
-    /// Create a new app using the given event bus and login details
-    pub fn new(events: Rc<EventBus>, login_details: LoginDetails) -> Self {
-        let client = match AuthCache::load() {
-            Ok(c) => c.into_client().unwrap(),
+    /// Create a new app using the given event bus, config, arguments and login details
+    pub fn new(
+        events: Rc<EventBus>,
+        config: Rc<Config>,
+        args: Rc<Args>,
+        login_details: LoginDetails,
+    ) -> Self {
+        let client = match AuthCache::load_unless_forgetting(&args).map(AuthCache::into_client) {
+            Ok(c) => c,
             Err(e) => {
                 debug!("error loading config: {:?}", e);
 
@@ -77,20 +102,68 @@ impl MainScreen {
             }
         };
 
+        let save_auth_state = login_details.remember && !args.no_remember;
+
+        let mut store = Store::new(
+            &events,
+            client.clone_sharing_state(),
+            config.clone(),
+            args.offline,
+            !args.no_remember,
+        );
+        if let Some(link) = args.deep_link() {
+            store.set_deep_link(link);
+        }
+
+        let mut viewer = Viewer::default();
+        let start_document = match config.start_document {
+            StartDocument::Last => None,
+            StartDocument::Welcome => Some(Document::Welcome),
+            StartDocument::Recent => Some(Document::Recent),
+            StartDocument::Downloads => Some(Document::Downloads),
+        };
+        if let Some(doc) = start_document {
+            store.skip_restore();
+            viewer.show(doc);
+        }
+        let viewer_focused = config.start_focus == StartFocus::Viewer;
+
         Self {
-            store: Store::new(&events, client.clone_sharing_state()),
+            store,
             events,
+            config,
+            args,
             client,
             navigation: Navigation::default(),
-            viewer: Viewer::default(),
-            viewer_focused: false,
-            save_auth_state: login_details.remember,
+            viewer,
+            viewer_focused,
+            save_auth_state,
             flash: Text::raw(""),
+            clipboard: Default::default(),
+        }
+    }
+
+    /// Show the given document in the viewer, without changing focus
+    fn show(&mut self, doc: Document) {
+        self.store.cancel_restore();
+        if let Document::Content(idx) = doc {
+            self.store.mark_viewed(idx);
+            // it's now read, so the tree needs updating
+            self.navigation.invalidate_render();
         }
+        self.viewer.show(doc);
     }
 
-    /// Quit the application, saving the auth state
+    /// Quit the application, saving the auth state and the store's state unless we were asked not to.
+    /// Offline sessions aren't saved either, so they don't overwrite the real one.
     pub fn quit(&mut self) -> Result<ExitState> {
+        if !self.args.no_remember && !self.store.is_offline() {
+            self.store.set_last_document(&self.viewer.document());
+            if let Err(e) = self.store.save() {
+                error!("error saving state: {}", e);
+            }
+        }
+
         if self.save_auth_state {
             debug!("saving auth state");
             if let Err(e) = AuthCache::from_client(&self.client).save() {
@@ -102,52 +175,114 @@ impl MainScreen {
     }
 }
 
+/// Split the screen into the navigation pane, the gap between the panes, and the viewer, leaving space for borders.
+fn split_panes(size: Rect) -> Rc<[Rect]> {
+    let content_rect = Rect {
+        x: size.x + 1,
+        y: size.y + 1,
+        width: size.width.saturating_sub(2),
+        height: size.height.saturating_sub(2),
+    };
+
+    // 30/70 split the two panes
+    Layout::new(
+        Direction::Horizontal,
+        [
+            Constraint::Percentage(30),
+            Constraint::Length(1),
+            Constraint::Percentage(70),
+        ],
+    )
+    .split(content_rect)
+}
+
+/// If the terminal is too small to draw in, say so and return true.
+fn draw_too_small(frame: &mut Frame) -> bool {
+    let size = frame.size();
+    if size.width >= MIN_WIDTH && size.height >= MIN_HEIGHT {
+        return false;
+    }
+
+    frame.render_widget(
+        Paragraph::new(format!(
+            "Terminal too small (need at least {}x{})",
+            MIN_WIDTH, MIN_HEIGHT
+        ))
+        .wrap(Wrap { trim: true }),
+        size,
+    );
+    true
+}
+
+/// Draw borders around both panes, highlighting the focused one.
+/// The borders share the column between the panes, so the focused one is drawn last to take it over.
+fn draw_pane_borders(frame: &mut Frame, theme: &Theme, layout: &[Rect], viewer_focused: bool) {
+    let size = frame.size();
+    let nav_rect = Rect {
+        x: size.x,
+        y: size.y,
+        width: layout[2].x.saturating_sub(size.x),
+        height: size.height,
+    };
+    let viewer_rect = Rect {
+        x: layout[1].x,
+        y: size.y,
+        width: size.width.saturating_sub(layout[1].x),
+        height: size.height,
+    };
+
+    let nav = (nav_rect, "[NAV]", !viewer_focused);
+    let viewer = (viewer_rect, "[VIEW]", viewer_focused);
+    let (unfocused, focused) = if viewer_focused {
+        (nav, viewer)
+    } else {
+        (viewer, nav)
+    };
+
+    for (rect, title, focused) in [unfocused, focused] {
+        let style = pane_border(theme, focused);
+        frame.render_widget(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(style)
+                .title(Span::styled(title, style)),
+            rect,
+        );
+    }
+}
+
 impl Screen for MainScreen {
     fn draw(&mut self, frame: &mut Frame) {
-        let size = frame.size();
-
-        // Add margin for borders
-        let content_rect = Rect {
-            x: size.x + 1,
-            y: size.y + 1,
-            width: size.width - 2,
-            height: size.height - 2,
-        };
+        if draw_too_small(frame) {
+            return;
+        }
 
-        // 30/70 split the two panes
-        let layout = Layout::new(
-            Direction::Horizontal,
-            [
-                Constraint::Percentage(30),
-                Constraint::Length(1),
-                Constraint::Percentage(70),
-            ],
-        )
-        .split(content_rect);
+        let size = frame.size();
+        let layout = split_panes(size);
 
         self.navigation.draw(&self.store, frame, layout[0]);
         self.viewer.draw(&self.store, frame, layout[2]);
 
-        // Draw a focus rectangle around one of them.
-        let focus_rect = if !self.viewer_focused {
-            Rect {
-                x: size.x,
-                y: size.y,
-                width: layout[2].x - size.x,
-                height: size.height,
-            }
-        } else {
-            Rect {
-                x: layout[1].x,
-                y: size.y,
-                width: size.width - layout[1].x,
-                height: size.height,
-            }
-        };
-
-        frame.render_widget(Block::default().borders(Borders::ALL), focus_rect);
+        draw_pane_borders(frame, self.config.theme(), &layout, self.viewer_focused);
+
+        if self.store.is_offline() {
+            frame.render_widget(
+                Paragraph::new("(offline)"),
+                Rect {
+                    x: size.x + 1,
+                    y: size.height.saturating_sub(1),
+                    width: 9.min(layout[0].width),
+                    height: 1,
+                },
+            );
+        }
 
-        let bottom_bar = Paragraph::new(self.flash.clone());
+        // Show the full title of the selected item if it's cut off, unless there's something more important
+        let bottom_text = match self.navigation.selected_title_if_truncated(&self.store) {
+            Some(title) if !self.viewer_focused && self.flash.width() == 0 => Text::raw(title),
+            _ => self.flash.clone(),
+        };
+        let bottom_bar = Paragraph::new(bottom_text);
         frame.render_widget(
             bottom_bar,
             Rect {
@@ -175,7 +310,27 @@ impl Screen for MainScreen {
 
         // Dispatch to pane or store
         let action = match event {
-            Event::Store(s) => self.store.event(s),
+            // C-D jumps to downloads, except where the content viewer uses it to scroll
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('d') | KeyCode::Char('D'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            }) if !(self.viewer_focused && self.viewer.is_content()) => {
+                Action::Show(Document::Downloads)
+            }
+            Event::Store(epoch, s) => {
+                // newly loaded content can change which courses are badged as new
+                if matches!(s, StoreEvent::CourseContent { .. }) {
+                    self.navigation.invalidate_render();
+                }
+                self.store.event(epoch, s)
+            }
+            Event::Resize(..) => {
+                // Rendered text may depend on width
+                self.viewer.invalidate_render();
+                self.navigation.invalidate_render();
+                Action::None
+            }
             x => match self.viewer_focused {
                 true => self.viewer.handle_event(&mut self.store, x),
                 false => self.navigation.handle_event(&mut self.store, x),
@@ -191,23 +346,82 @@ impl Screen for MainScreen {
                 return self.quit();
             }
             Action::Show(doc) => {
-                self.viewer.show(doc);
+                self.show(doc);
                 self.viewer_focused = true;
             }
+            Action::Preview(doc) => self.show(doc),
             Action::FocusNavigation => self.viewer_focused = false,
-            Action::Reauthenticate => {
+            Action::Reauthenticate(msg) => {
                 return Ok(ExitState::ChangeScreen(Box::new(
                     LoginPrompt::new_with_msg(
                         self.events.clone(),
-                        "Authentication failed, please double check your username & password.",
+                        self.config.clone(),
+                        self.args.clone(),
+                        msg,
                     ),
                 )));
             }
             Action::Flash(s) => {
                 self.flash = s;
             }
+            Action::Copy(text) => {
+                self.flash = match self.clipboard.copy(&text) {
+                    Ok(()) => format!("Copied {text}").into(),
+                    Err(e) => error_text(
+                        self.config.theme(),
+                        format!("Error copying to clipboard: {e}"),
+                    ),
+                };
+            }
         };
 
         Ok(ExitState::Running)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{backend::TestBackend, Terminal};
+
+    use super::*;
+
+    #[test]
+    fn test_too_small_1x1() {
+        let mut terminal = Terminal::new(TestBackend::new(1, 1)).unwrap();
+        terminal
+            .draw(|frame| assert!(draw_too_small(frame)))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_split_panes_tiny() {
+        for (width, height) in [(0, 0), (1, 1), (2, 2), (MIN_WIDTH, MIN_HEIGHT)] {
+            let layout = split_panes(Rect::new(0, 0, width, height));
+            assert_eq!(layout.len(), 3);
+        }
+    }
+
+    #[test]
+    fn test_focused_border_highlighted() {
+        let mut terminal = Terminal::new(TestBackend::new(MIN_WIDTH, MIN_HEIGHT)).unwrap();
+        let theme = &Theme::DARK;
+        for viewer_focused in [false, true] {
+            terminal
+                .draw(|frame| {
+                    let layout = split_panes(frame.size());
+                    draw_pane_borders(frame, theme, &layout, viewer_focused)
+                })
+                .unwrap();
+
+            let buf = terminal.backend().buffer();
+            let bottom = MIN_HEIGHT - 1;
+            let nav_corner = buf.get(0, bottom);
+            let viewer_corner = buf.get(MIN_WIDTH - 1, bottom);
+            assert_eq!(Some(nav_corner.fg), pane_border(theme, !viewer_focused).fg);
+            assert_eq!(
+                Some(viewer_corner.fg),
+                pane_border(theme, viewer_focused).fg
+            );
+        }
+    }
+}
@@ -1,9 +1,16 @@
 use crossterm::event::KeyCode;
+use edlearn_client::content::ContentPayload;
 use ratatui::{prelude::Rect, Frame};
 use tui_tree_widget::{Tree, TreeItem, TreeState};
+use unicode_width::UnicodeWidthStr;
 
 use super::{Action, Document, Pane};
-use crate::{event::Event, store::Store, styles::error_text};
+use crate::{
+    args::DeepLink,
+    event::{strip_control, Event},
+    store::{CourseIdx, SearchResult, Store},
+    styles::error_text,
+};
 
 mod tree;
 use tree::*;
@@ -15,35 +22,53 @@ pub struct Navigation {
     nav_tree: Vec<NavTree>,
     cached_view_tree: Option<Vec<TreeItem<'static, TreeId>>>,
     last_download_summary: (usize, usize),
+
+    /// The width we last drew at
+    last_width: u16,
+
+    /// Set when `g` is pressed, until the next key
+    pending_g: bool,
+
+    /// What the user has typed so far, while jumping to a course
+    course_entry: Option<String>,
 }
 
 impl Pane for Navigation {
     fn draw(&mut self, store: &Store, frame: &mut Frame, area: Rect) {
+        self.last_width = area.width;
         if self.refresh_tree(store) || self.cached_view_tree.is_none() {
             // changed, so refresh view tree
-            self.cached_view_tree = Some(
-                self.nav_tree
-                    .iter()
-                    .map(|i| i.as_treeitem(store))
-                    .collect(),
-            );
+            self.cached_view_tree = Some(self.view_tree(store));
         }
 
         frame.render_stateful_widget(
             Tree::new(self.cached_view_tree.clone().unwrap())
                 .unwrap()
-                .highlight_symbol(">>"),
+                .highlight_symbol(">>")
+                .highlight_style(store.config().theme().tree_highlight),
             area,
             &mut self.tree_state,
         );
     }
 
     fn handle_event(&mut self, store: &mut Store, event: Event) -> Action {
+        if self.course_entry.is_some() {
+            return self.handle_course_entry(store, event);
+        }
+
         let Event::Key(key) = event else {
             return Action::None;
         };
 
+        if std::mem::take(&mut self.pending_g) && key.code == KeyCode::Char('c') {
+            self.course_entry = Some(String::new());
+            return self.course_entry_prompt(store);
+        }
+
         match key.code {
+            KeyCode::Char('g') => {
+                self.pending_g = true;
+            }
             KeyCode::Esc | KeyCode::Char('q') => {
                 return Action::Exit;
             }
@@ -71,26 +96,74 @@ impl Pane for Navigation {
                         ty,
                         children: children @ NavTreeChildren::NotRequested,
                     } => {
-                        ty.request_children(store);
-                        *children = NavTreeChildren::Loading;
+                        // Children may have been prefetched already
+                        let loaded = ty.new_children_loaded(store);
+                        let loading = loaded.is_none();
+                        *children = match loaded {
+                            Some(cs) => NavTreeChildren::Done(cs),
+                            None => {
+                                ty.request_children(store);
+                                NavTreeChildren::Loading
+                            }
+                        };
                         self.tree_state.open(sel);
                         self.cached_view_tree = None;
+
+                        // Courses open on their overview page, if they have one
+                        if let NodeTy::Course(course_idx) = ty {
+                            store.visit_course(*course_idx);
+                            if loading {
+                                store.show_overview_when_loaded(*course_idx);
+                            } else if let Some(overview) = store.course_overview(*course_idx) {
+                                return Action::Preview(Document::Content(overview));
+                            }
+                        }
                     }
 
-                    // show in viewer
+                    // placements are only useful in the browser, so skip the viewer
                     NavTree::ContentLeaf { content_idx } => {
+                        let content = store.content(*content_idx);
+                        if let ContentPayload::Placement { name, .. } = &content.payload {
+                            return match store.config().open(content.browser_link()) {
+                                Ok(Some(output)) => Action::Flash(output.into()),
+                                Ok(None) => {
+                                    Action::Flash(format!("Opened {name} in browser").into())
+                                }
+                                Err(e) => Action::Flash(error_text(
+                                    store.config().theme(),
+                                    format!("Error opening in browser: {e}"),
+                                )),
+                            };
+                        }
+
+                        // show in viewer
                         return Action::Show(Document::Content(*content_idx));
                     }
+                    NavTree::CourseTool { course_idx, tool } => {
+                        return Action::Show(tool.document(*course_idx));
+                    }
                     NavTree::Header {
                         ty: HeaderTy::Welcome,
                     } => {
                         return Action::Show(Document::Welcome);
                     }
+                    NavTree::Header {
+                        ty: HeaderTy::Recent,
+                    } => {
+                        return Action::Show(Document::Recent);
+                    }
                     NavTree::Header {
                         ty: HeaderTy::Downloads,
                     } => {
                         return Action::Show(Document::Downloads);
                     }
+                    NavTree::Header {
+                        ty: HeaderTy::Term(term_idx),
+                    } => {
+                        let term_idx = *term_idx;
+                        store.set_term_collapsed(term_idx, !store.is_term_collapsed(term_idx));
+                        self.cached_view_tree = None;
+                    }
 
                     // do nothing on loading stuff
                     NavTree::Node {
@@ -101,6 +174,110 @@ impl Pane for Navigation {
                     NavTree::Header { .. } => (),
                 }
             }
+            KeyCode::Char('m') => {
+                let sel = self.tree_state.selected();
+                if let NavTree::ContentLeaf { content_idx } =
+                    NavTree::navigate_mut(&mut self.nav_tree, &sel)
+                {
+                    let content_idx = *content_idx;
+                    let read = !store.is_read(content_idx);
+                    store.set_read(content_idx, read);
+                    self.cached_view_tree = None;
+                    return Action::Flash(
+                        if read {
+                            "Marked as read"
+                        } else {
+                            "Marked as unread"
+                        }
+                        .into(),
+                    );
+                }
+            }
+            KeyCode::Char('r') => {
+                if store.is_offline() {
+                    return Action::Flash(error_text(
+                        store.config().theme(),
+                        "Can't refresh while offline",
+                    ));
+                }
+
+                // refresh the selected course or folder, or whichever one the selection is in
+                let sel = self.tree_state.selected();
+                let Some(len) = (1..=sel.len()).rev().find(|len| {
+                    matches!(
+                        NavTree::navigate_mut(&mut self.nav_tree, &sel[..*len]),
+                        NavTree::Node {
+                            children: NavTreeChildren::Done(_),
+                            ..
+                        }
+                    )
+                }) else {
+                    return Action::None;
+                };
+
+                let NavTree::Node { ty, children } =
+                    NavTree::navigate_mut(&mut self.nav_tree, &sel[..len])
+                else {
+                    unreachable!();
+                };
+                match ty {
+                    NodeTy::Course(i) => store.refresh_course_content(*i),
+                    NodeTy::Content(i) => store.refresh_content_children(*i),
+                }
+                *children = NavTreeChildren::Loading;
+                self.tree_state.select(sel[..len].to_vec());
+                self.cached_view_tree = None;
+
+                return Action::Flash("Refreshing...".into());
+            }
+            KeyCode::Char('Y') => {
+                let link = match self.tree_state.selected().last() {
+                    Some(TreeId::Course(i)) => DeepLink {
+                        course_id: store.course(*i).id.clone(),
+                        content_id: None,
+                    },
+                    Some(TreeId::Content(i)) => {
+                        let content = store.content(*i);
+                        DeepLink {
+                            course_id: content.course_id.clone(),
+                            content_id: Some(content.id.clone()),
+                        }
+                    }
+                    _ => return Action::None,
+                };
+                return Action::Copy(link.command());
+            }
+            KeyCode::Char('H') => {
+                if store.config().hidden_content.is_empty() {
+                    return Action::Flash(
+                        "No content is hidden. Set hidden_content in the config to hide some."
+                            .into(),
+                    );
+                }
+
+                let show = !store.show_hidden_content();
+                store.set_show_hidden_content(show);
+                for item in self.nav_tree.iter_mut() {
+                    item.refilter(store);
+                }
+
+                // the selected item may have just been hidden
+                let mut sel = self.tree_state.selected();
+                while sel.len() > 1 && !NavTree::exists(&self.nav_tree, &sel) {
+                    sel.pop();
+                }
+                self.tree_state.select(sel);
+                self.cached_view_tree = None;
+
+                return Action::Flash(
+                    if show {
+                        "Showing hidden content"
+                    } else {
+                        "Hiding content"
+                    }
+                    .into(),
+                );
+            }
             KeyCode::Char('b') => {
                 let sel = self.tree_state.selected();
                 let sel_node = NavTree::navigate_mut(&mut self.nav_tree, &sel);
@@ -111,8 +288,15 @@ impl Pane for Navigation {
                 } = sel_node
                 {
                     let content = store.content(*content_idx);
-                    if let Err(e) = open::that(content.browser_link()) {
-                        return Action::Flash(error_text(format!("Error opening in browser: {e}")));
+                    match store.config().open(content.browser_link()) {
+                        Ok(Some(output)) => return Action::Flash(output.into()),
+                        Ok(None) => (),
+                        Err(e) => {
+                            return Action::Flash(error_text(
+                                store.config().theme(),
+                                format!("Error opening in browser: {e}"),
+                            ))
+                        }
                     }
                 }
             }
@@ -124,6 +308,152 @@ impl Pane for Navigation {
 }
 
 impl Navigation {
+    /// Discard the cached tree, so it is rebuilt on next draw.
+    pub fn invalidate_render(&mut self) {
+        self.cached_view_tree = None;
+    }
+
+    /// Get the full title of the selected item, if it's too long to show in the tree.
+    pub fn selected_title_if_truncated(&self, store: &Store) -> Option<String> {
+        let selected = self.tree_state.selected();
+        let (title, badge_width) = match selected.last()? {
+            TreeId::Course(i) => (
+                store.course(*i).name.clone(),
+                course_new_badge(store, *i).map_or(0, |b| b.width()),
+            ),
+            TreeId::Content(i) => {
+                let content = store.content(*i);
+                let mut badge_width = if store.is_new(*i) { NEW_BADGE.len() } else { 0 };
+                if !content.is_container() && !store.is_read(*i) {
+                    badge_width += UNREAD_MARKER.width();
+                }
+                (content.title.clone(), badge_width)
+            }
+            _ => return None,
+        };
+
+        // highlight symbol, indentation, the open/closed marker, and any badge
+        let used = 2 + 2 * selected.len() + 2 + badge_width;
+        if title.width() + used > self.last_width as usize {
+            Some(title)
+        } else {
+            None
+        }
+    }
+
+    /// Handle an event while the user is typing a course to jump to
+    fn handle_course_entry(&mut self, store: &mut Store, event: Event) -> Action {
+        let Some(entry) = self.course_entry.as_mut() else {
+            return Action::None;
+        };
+
+        match event {
+            Event::Paste(s) => entry.push_str(&strip_control(&s)),
+            Event::Key(key) => match key.code {
+                KeyCode::Char(c) if !c.is_control() => entry.push(c),
+                KeyCode::Backspace => {
+                    entry.pop();
+                }
+                KeyCode::Esc => {
+                    self.course_entry = None;
+                    return Action::Flash("".into());
+                }
+                KeyCode::Enter => {
+                    let query = self.course_entry.take().unwrap();
+                    if query.trim().is_empty() {
+                        return Action::Flash("".into());
+                    }
+
+                    let Some(course_idx) = first_matching_course(store, &query) else {
+                        return Action::Flash(error_text(
+                            store.config().theme(),
+                            format!("No courses matching {}", query),
+                        ));
+                    };
+                    self.jump_to_course(store, course_idx);
+                    return Action::Flash("".into());
+                }
+                _ => (),
+            },
+            _ => (),
+        };
+
+        self.course_entry_prompt(store)
+    }
+
+    /// Show what the user has typed so far, and which course it matches
+    fn course_entry_prompt(&self, store: &Store) -> Action {
+        let entry = self.course_entry.as_deref().unwrap_or_default();
+        let prompt = match first_matching_course(store, entry) {
+            Some(course_idx) => format!(
+                "Go to course: {} ({})",
+                entry,
+                store.course(course_idx).name
+            ),
+            None => format!("Go to course: {}", entry),
+        };
+
+        Action::Flash(prompt.into())
+    }
+
+    /// Select and expand the given course, showing its term if it's collapsed
+    fn jump_to_course(&mut self, store: &mut Store, course_idx: CourseIdx) {
+        let term_idx = store
+            .courses_by_term()
+            .and_then(|terms| terms.iter().position(|(_, cs)| cs.contains(&course_idx)));
+        if let Some(term_idx) = term_idx {
+            store.set_term_collapsed(term_idx, false);
+        }
+
+        self.open_course(store, course_idx);
+        store.visit_course(course_idx);
+        self.cached_view_tree = None;
+    }
+
+    /// Build the tree to display, leaving out courses in collapsed terms
+    fn view_tree(&self, store: &Store) -> Vec<TreeItem<'static, TreeId>> {
+        let mut hidden = false;
+        self.nav_tree
+            .iter()
+            .filter(|item| match item {
+                NavTree::Header {
+                    ty: HeaderTy::Term(term_idx),
+                } => {
+                    hidden = store.is_term_collapsed(*term_idx);
+                    true
+                }
+                NavTree::Header { .. } => {
+                    hidden = false;
+                    true
+                }
+                _ => !hidden,
+            })
+            .map(|item| item.as_treeitem(store))
+            .collect()
+    }
+
+    /// Select the given course, and start loading its content
+    fn open_course(&mut self, store: &Store, course_idx: CourseIdx) {
+        let Some(node) = self
+            .nav_tree
+            .iter_mut()
+            .find(|n| matches!(n, NavTree::Node { ty: NodeTy::Course(i), .. } if *i == course_idx))
+        else {
+            return;
+        };
+
+        if let NavTree::Node {
+            ty,
+            children: children @ NavTreeChildren::NotRequested,
+        } = node
+        {
+            ty.request_children(store);
+            *children = NavTreeChildren::Loading;
+        }
+        self.tree_state.select(vec![TreeId::Course(course_idx)]);
+        self.tree_state.open(vec![TreeId::Course(course_idx)]);
+    }
+
     fn refresh_tree(&mut self, store: &Store) -> bool {
         if self.nav_tree.is_empty() {
             // first call, add courses / loading
@@ -142,9 +472,17 @@ impl Navigation {
                 self.nav_tree.push(NavTree::Header {
                     ty: HeaderTy::Welcome,
                 });
+                self.nav_tree.push(NavTree::Header {
+                    ty: HeaderTy::Recent,
+                });
                 self.nav_tree.push(NavTree::Header {
                     ty: HeaderTy::Downloads,
                 });
+                if store.my_courses().is_some_and(|cs| cs.is_empty()) {
+                    self.nav_tree.push(NavTree::Header {
+                        ty: HeaderTy::NoCourses,
+                    });
+                }
                 for (term_idx, (_, courses)) in all_courses.iter().enumerate() {
                     self.nav_tree.push(NavTree::Header {
                         ty: HeaderTy::Term(term_idx),
@@ -158,6 +496,9 @@ impl Navigation {
                 }
 
                 self.tree_state.select(vec![TreeId::Welcome]);
+                if let Some(course_idx) = store.deep_link_course() {
+                    self.open_course(store, course_idx);
+                }
                 changed = true;
             } else {
                 // still loading
@@ -206,6 +547,7 @@ impl Navigation {
                 ..
             } => false,
             NavTree::ContentLeaf { .. } => false,
+            NavTree::CourseTool { .. } => false,
             NavTree::Loading => false,
             NavTree::Header { .. } => false,
 
@@ -239,3 +581,14 @@ impl Navigation {
         }
     }
 }
+
+/// Get the first course whose code or name contains what the user typed
+fn first_matching_course(store: &Store, query: &str) -> Option<CourseIdx> {
+    store
+        .search_content(query)
+        .into_iter()
+        .find_map(|result| match result {
+            SearchResult::Course(course_idx) => Some(course_idx),
+            SearchResult::Content(_) => None,
+        })
+}
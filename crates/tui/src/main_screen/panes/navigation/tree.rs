@@ -1,10 +1,13 @@
 use ratatui::{
     style::{Color, Modifier, Style},
-    text::Text,
+    text::{Line, Span, Text},
 };
 use tui_tree_widget::TreeItem;
 
-use crate::store::{ContentIdx, CourseIdx, Store, TermIdx};
+use crate::{
+    main_screen::panes::Document,
+    store::{ContentIdx, CourseIdx, Store, TermIdx},
+};
 
 /// Our navigation tree, but with only IDs, loading information, etc.
 /// This is a sort of 'abstract' tree that gets compiled into a [`TreeItem`] which is then rendered.
@@ -18,6 +21,12 @@ pub enum NavTree {
     /// An item which will never have children
     ContentLeaf { content_idx: ContentIdx },
 
+    /// A course page that isn't content, shown above the course's content
+    CourseTool {
+        course_idx: CourseIdx,
+        tool: CourseTool,
+    },
+
     /// A placeholder to show that the whole tree is loading.
     Loading,
 
@@ -32,18 +41,84 @@ pub enum NodeTy {
     Content(ContentIdx),
 }
 
+/// Pages shown under every course, besides its content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CourseTool {
+    Announcements,
+    Grades,
+}
+
+impl CourseTool {
+    const ALL: [CourseTool; 2] = [CourseTool::Announcements, CourseTool::Grades];
+
+    /// Get the document to show for this tool in the given course
+    pub fn document(&self, course_idx: CourseIdx) -> Document {
+        match self {
+            CourseTool::Announcements => Document::Announcements(course_idx),
+            CourseTool::Grades => Document::Grades(course_idx),
+        }
+    }
+
+    fn id(&self, course_idx: CourseIdx) -> TreeId {
+        match self {
+            CourseTool::Announcements => TreeId::Announcements(course_idx),
+            CourseTool::Grades => TreeId::Grades(course_idx),
+        }
+    }
+
+    fn treeitem(&self, store: &Store, course_idx: CourseIdx) -> TreeItem<'static, TreeId> {
+        let title = match self {
+            CourseTool::Announcements => "Announcements",
+            CourseTool::Grades => "Grades",
+        };
+        TreeItem::new_leaf(
+            self.id(course_idx),
+            Text::styled(
+                title,
+                Style::new()
+                    .fg(store.config().theme().accent)
+                    .add_modifier(Modifier::ITALIC),
+            ),
+        )
+    }
+}
+
 /// The type of a header, mostly to uniquely identify it
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum HeaderTy {
     Welcome,
+    Recent,
     Downloads,
     Term(TermIdx),
+
+    /// Placeholder for when the user has no courses at all
+    NoCourses,
 }
 impl HeaderTy {
     fn treeitem(&self, store: &Store) -> TreeItem<'static, TreeId> {
+        if let HeaderTy::NoCourses = self {
+            let hint = if store.is_offline() {
+                "No courses available offline."
+            } else {
+                "No courses found. Check you're enrolled, or favourite courses on Learn to pin them here."
+            };
+            return TreeItem::new_leaf(
+                self.id(),
+                Text::styled(hint, Style::new().fg(store.config().theme().hint)),
+            );
+        }
+
         let title = match self {
-            HeaderTy::Term(idx) => store.courses_by_term().unwrap()[*idx].0.clone(),
+            HeaderTy::Term(idx) => {
+                let marker = if store.is_term_collapsed(*idx) {
+                    "▸"
+                } else {
+                    "▾"
+                };
+                format!("{} {}", marker, store.courses_by_term().unwrap()[*idx].0)
+            }
             HeaderTy::Welcome => "Welcome".to_string(),
+            HeaderTy::Recent => "Recently viewed".to_string(),
             HeaderTy::Downloads => {
                 let (completed, total) = store.download_queue_summary();
                 if total > 0 {
@@ -52,13 +127,16 @@ impl HeaderTy {
                     "Downloads".to_string()
                 }
             }
+            HeaderTy::NoCourses => unreachable!(),
         };
 
         TreeItem::new_leaf(
             self.id(),
             Text::styled(
                 title,
-                Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                Style::new()
+                    .fg(store.config().theme().header)
+                    .add_modifier(Modifier::BOLD),
             ),
         )
     }
@@ -67,7 +145,9 @@ impl HeaderTy {
         match self {
             HeaderTy::Term(i) => TreeId::TermHeader(*i),
             HeaderTy::Welcome => TreeId::Welcome,
+            HeaderTy::Recent => TreeId::Recent,
             HeaderTy::Downloads => TreeId::Downloads,
+            HeaderTy::NoCourses => TreeId::NoCourses,
         }
     }
 }
@@ -93,9 +173,13 @@ pub enum TreeId {
     CourseLoading(CourseIdx),
     Content(CourseIdx),
     ContentLoading(CourseIdx),
+    Announcements(CourseIdx),
+    Grades(CourseIdx),
     Loading,
     Welcome,
+    Recent,
     Downloads,
+    NoCourses,
 }
 
 impl NavTree {
@@ -129,6 +213,59 @@ impl NavTree {
         }
     }
 
+    /// Check if some selector points to an element of the tree, ie it's safe to pass to [`Self::navigate_mut`].
+    pub fn exists(leafs: &[Self], ids: &[TreeId]) -> bool {
+        let Some(first) = ids.first() else {
+            return false;
+        };
+        if matches!(first, TreeId::Loading) {
+            return !leafs.is_empty();
+        }
+
+        let Some(next) = leafs.iter().find(|x| x.matches(*first)) else {
+            return false;
+        };
+        if ids.len() == 1 || matches!(ids[1], TreeId::CourseLoading(_) | TreeId::ContentLoading(_))
+        {
+            return true;
+        }
+
+        match next {
+            NavTree::Node {
+                children: NavTreeChildren::Done(cs),
+                ..
+            } => Self::exists(cs, &ids[1..]),
+            _ => false,
+        }
+    }
+
+    /// Rebuild the loaded children of this element and everything below it, after which content is hidden changes.
+    /// Children that are still shown keep whatever they had loaded.
+    pub fn refilter(&mut self, store: &Store) {
+        let NavTree::Node {
+            ty,
+            children: NavTreeChildren::Done(cs),
+        } = self
+        else {
+            return;
+        };
+        let Some(fresh) = ty.new_children_loaded(store) else {
+            return;
+        };
+
+        let mut old = std::mem::take(cs);
+        *cs = fresh
+            .into_iter()
+            .map(|new| match old.iter().position(|o| o.id() == new.id()) {
+                Some(i) => old.swap_remove(i),
+                None => new,
+            })
+            .collect();
+        for c in cs.iter_mut() {
+            c.refilter(store);
+        }
+    }
+
     fn matches(&self, id: TreeId) -> bool {
         match (self, id) {
             (NavTree::Node { ty, .. }, id) => ty.matches(id),
@@ -136,6 +273,7 @@ impl NavTree {
             | (NavTree::ContentLeaf { content_idx }, TreeId::ContentLoading(idx)) => {
                 *content_idx == idx
             }
+            (NavTree::CourseTool { course_idx, tool }, id) => tool.id(*course_idx) == id,
             (
                 NavTree::Header {
                     ty: HeaderTy::Term(term_idx),
@@ -148,12 +286,24 @@ impl NavTree {
                 },
                 TreeId::Welcome,
             ) => true,
+            (
+                NavTree::Header {
+                    ty: HeaderTy::Recent,
+                },
+                TreeId::Recent,
+            ) => true,
             (
                 NavTree::Header {
                     ty: HeaderTy::Downloads,
                 },
                 TreeId::Downloads,
             ) => true,
+            (
+                NavTree::Header {
+                    ty: HeaderTy::NoCourses,
+                },
+                TreeId::NoCourses,
+            ) => true,
             _ => false,
         }
     }
@@ -164,9 +314,16 @@ impl NavTree {
             // base case: nodes with no children
             NavTree::ContentLeaf { content_idx } => TreeItem::new_leaf(
                 TreeId::Content(*content_idx),
-                store.content(*content_idx).title.to_string(),
+                content_title(store, *content_idx, true),
+            ),
+            NavTree::CourseTool { course_idx, tool } => tool.treeitem(store, *course_idx),
+            NavTree::Loading => TreeItem::new_leaf(
+                TreeId::Loading,
+                match store.login_progress() {
+                    Some(service) => format!("Logging in: contacting {service}..."),
+                    None => "Loading your courses...".to_string(),
+                },
             ),
-            NavTree::Loading => TreeItem::new_leaf(TreeId::Loading, LOADING),
             NavTree::Node {
                 ty,
                 children: NavTreeChildren::NotRequested,
@@ -195,6 +352,7 @@ impl NavTree {
         match self {
             NavTree::Node { ty, .. } => ty.id(),
             NavTree::ContentLeaf { content_idx } => TreeId::Content(*content_idx),
+            NavTree::CourseTool { course_idx, tool } => tool.id(*course_idx),
             NavTree::Loading => TreeId::Loading,
             NavTree::Header { ty } => ty.id(),
         }
@@ -210,26 +368,41 @@ impl NodeTy {
         }
     }
 
-    /// Check if the children have been loaded, and if so return them
+    /// Check if the children have been loaded, and if so return them.
+    /// Courses also get their [`CourseTool`]s, before their content.
     pub fn new_children_loaded(&self, store: &Store) -> Option<Vec<NavTree>> {
-        let idxs = match self {
-            NodeTy::Course(i) => store.course_content(*i),
-            NodeTy::Content(i) => store.content_children(*i),
-        }?;
+        let (tools, idxs) = match self {
+            NodeTy::Course(i) => (
+                CourseTool::ALL
+                    .iter()
+                    .map(|tool| NavTree::CourseTool {
+                        course_idx: *i,
+                        tool: *tool,
+                    })
+                    .collect(),
+                store.course_content(*i)?,
+            ),
+            NodeTy::Content(i) => (vec![], store.content_children(*i)?),
+        };
         Some(
-            idxs.map(|content_idx| {
-                let content = store.content(content_idx);
-
-                if content.is_container() {
-                    NavTree::Node {
-                        ty: NodeTy::Content(content_idx),
-                        children: NavTreeChildren::NotRequested,
-                    }
-                } else {
-                    NavTree::ContentLeaf { content_idx }
-                }
-            })
-            .collect(),
+            tools
+                .into_iter()
+                .chain(
+                    idxs.filter(|i| !store.is_content_hidden(*i))
+                        .map(|content_idx| {
+                            let content = store.content(content_idx);
+
+                            if content.is_container() {
+                                NavTree::Node {
+                                    ty: NodeTy::Content(content_idx),
+                                    children: NavTreeChildren::NotRequested,
+                                }
+                            } else {
+                                NavTree::ContentLeaf { content_idx }
+                            }
+                        }),
+                )
+                .collect(),
         )
     }
 
@@ -245,10 +418,29 @@ impl NodeTy {
     }
 
     /// Get the display name for this node.
-    fn display_name(&self, store: &Store) -> String {
+    /// Courses which have ended are greyed out, and others are shown in their colour from Learn if they have one.
+    fn display_name(&self, store: &Store) -> Text<'static> {
         match self {
-            NodeTy::Course(i) => store.course(*i).name.clone(),
-            NodeTy::Content(i) => store.content(*i).title.clone(),
+            NodeTy::Course(i) => {
+                let course = store.course(*i);
+                let style = if course.has_ended() {
+                    Style::new().fg(store.config().theme().inactive)
+                } else if let Some((r, g, b)) = course.rgb() {
+                    Style::new().fg(Color::Rgb(r, g, b))
+                } else {
+                    Style::new()
+                };
+
+                let mut spans = vec![Span::styled(course.name.clone(), style)];
+                if let Some(badge) = course_new_badge(store, *i) {
+                    spans.push(Span::styled(
+                        badge,
+                        Style::new().fg(store.config().theme().badge),
+                    ));
+                }
+                Line::from(spans).into()
+            }
+            NodeTy::Content(i) => content_title(store, *i, false),
         }
     }
 
@@ -288,3 +480,38 @@ impl Default for TreeId {
         Self::Loading
     }
 }
+
+/// Marker shown after content which has changed since the last session
+pub const NEW_BADGE: &str = " *new*";
+
+/// Get the marker shown after a course with new content, if it has any
+pub fn course_new_badge(store: &Store, course_idx: CourseIdx) -> Option<String> {
+    match store.new_content_count(course_idx) {
+        0 => None,
+        n => Some(format!(" *{} new*", n)),
+    }
+}
+
+/// Marker shown before content the user hasn't read
+pub const UNREAD_MARKER: &str = "● ";
+
+/// Get the display name for a content item, badged if it's new.
+/// If `show_unread` is set, it's also marked if the user hasn't read it.
+fn content_title(store: &Store, content_idx: ContentIdx, show_unread: bool) -> Text<'static> {
+    let mut spans = vec![];
+    if show_unread && !store.is_read(content_idx) {
+        spans.push(Span::styled(
+            UNREAD_MARKER,
+            Style::new().fg(store.config().theme().accent),
+        ));
+    }
+    spans.push(Span::raw(store.content(content_idx).title.clone()));
+    if store.is_new(content_idx) {
+        spans.push(Span::styled(
+            NEW_BADGE,
+            Style::new().fg(store.config().theme().badge),
+        ));
+    }
+
+    Line::from(spans).into()
+}
@@ -0,0 +1,140 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    prelude::{Margin, Rect},
+    style::Stylize,
+    text::{Line, Text},
+    widgets::{Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap},
+    Frame,
+};
+
+use crate::{
+    event::Event,
+    main_screen::{panes::Pane, Action},
+    store::{CourseIdx, Store},
+    styles::error_text,
+};
+
+/// Shows a course's announcements, most recent first
+#[derive(Debug)]
+pub struct AnnouncementsViewer {
+    course_idx: CourseIdx,
+    y_offset: u16,
+
+    /// The rendered announcements, and the width they were rendered at
+    cached_render: Option<(u16, Text<'static>)>,
+}
+
+impl AnnouncementsViewer {
+    pub(crate) fn new(course_idx: CourseIdx) -> Self {
+        Self {
+            course_idx,
+            y_offset: 0,
+            cached_render: None,
+        }
+    }
+
+    /// Get the course whose announcements are being shown
+    pub(crate) fn course_idx(&self) -> CourseIdx {
+        self.course_idx
+    }
+
+    /// Render the announcements, if they're loaded
+    fn render(&self, store: &Store, width: u16) -> Option<Text<'static>> {
+        let Some(announcements) = store.announcements(self.course_idx) else {
+            store.request_announcements(self.course_idx);
+            return None;
+        };
+
+        let theme = store.config().theme();
+        let mut text = Text::from(Line::from(
+            format!("Announcements - {}", store.course(self.course_idx).name)
+                .fg(theme.title)
+                .bold(),
+        ));
+        match announcements {
+            None => text.extend(error_text(
+                store.config().theme(),
+                "Couldn't load announcements.",
+            )),
+            Some([]) => text.extend(Text::raw("No announcements.")),
+            Some(announcements) => {
+                for announcement in announcements {
+                    text.lines.push(Line::default());
+                    text.lines.push(Line::from(
+                        announcement.title.clone().fg(theme.header).bold(),
+                    ));
+                    if let Some(created) = announcement.created {
+                        text.lines.push(Line::from(
+                            created
+                                .format("%Y-%m-%d %H:%M")
+                                .to_string()
+                                .fg(theme.inactive),
+                        ));
+                    }
+                    if let Some(body) = &announcement.body {
+                        let (body, _) = bbml::render_text_with_options(
+                            body,
+                            &store.config().render_options(width as usize),
+                        );
+                        text.extend(body);
+                    }
+                }
+            }
+        };
+
+        Some(text)
+    }
+}
+
+impl Pane for AnnouncementsViewer {
+    fn draw(&mut self, store: &Store, frame: &mut Frame, area: Rect) {
+        // Account for the margin we leave for the scrollbar
+        let width = area.width.saturating_sub(2);
+        let text = match &self.cached_render {
+            Some((w, text)) if *w == width => text.clone(),
+            _ => {
+                let Some(text) = self.render(store, width) else {
+                    frame.render_widget(Paragraph::new("Loading..."), area);
+                    return;
+                };
+                self.cached_render = Some((width, text.clone()));
+                text
+            }
+        };
+
+        let rendered = Paragraph::new(text).wrap(Wrap { trim: false });
+        let max_y_offset = (rendered.line_count(width) as u16).saturating_sub(area.height);
+        self.y_offset = self.y_offset.min(max_y_offset);
+
+        let mut scrollbar_state =
+            ScrollbarState::new(max_y_offset as usize).position(self.y_offset as usize);
+        frame.render_widget(
+            rendered.scroll((self.y_offset, 0)),
+            area.inner(&Margin {
+                vertical: 0,
+                horizontal: 1,
+            }),
+        );
+        frame.render_stateful_widget(
+            Scrollbar::default().orientation(ScrollbarOrientation::VerticalRight),
+            area,
+            &mut scrollbar_state,
+        );
+    }
+
+    fn handle_event(&mut self, _: &mut Store, event: Event) -> Action {
+        let Event::Key(key) = event else {
+            return Action::None;
+        };
+
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => self.y_offset = self.y_offset.saturating_add(1),
+            KeyCode::Up | KeyCode::Char('k') => self.y_offset = self.y_offset.saturating_sub(1),
+            KeyCode::Char('g') => self.y_offset = 0,
+            KeyCode::Char('G') => self.y_offset = u16::MAX,
+            _ => (),
+        };
+
+        Action::None
+    }
+}
@@ -1,30 +1,137 @@
-use crossterm::event::{KeyCode, KeyModifiers};
-use edlearn_client::content::ContentPayload;
+use bbml::RenderOptions;
+use chrono::Local;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use edlearn_client::{
+    content::{parse_content_link, ContentPayload},
+    gradebook::Attempt,
+};
 use log::debug;
 use ratatui::{
     prelude::Margin,
-    style::{Color, Modifier, Style, Stylize},
-    text::Line,
-    widgets::{Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState},
+    style::{Modifier, Style, Stylize},
+    text::{Line, Text},
+    widgets::{Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap},
+};
+use std::{
+    fs::{self, create_dir_all},
+    ops::RangeInclusive,
 };
 
 use crate::{
-    event::Event,
+    args::{self, DeepLink},
+    auth_cache::state_file_location,
+    event::{strip_control, Event},
     main_screen::{panes::Pane, Action},
-    store::{ContentIdx, DownloadState, Store},
-    styles::error_text,
+    store::{ContentIdx, DownloadState, PageRender, Store},
+    styles::{error_text, highlight_matches},
 };
 
+/// Commands bound to keys in the content viewer.
+///
+/// | Key                 | Binding                            |
+/// |---------------------|------------------------------------|
+/// | `g` / `G`           | Top / bottom                       |
+/// | `Home` / `End`      | Top / bottom                       |
+/// | `j` / `k`           | Down / up `scroll_step` lines      |
+/// | `Ctrl-D` / `Ctrl-U` | Down / up half or a whole page     |
+/// | `PgDn` / `PgUp`     | Down / up a whole page             |
+/// | `b`                 | Open in browser                    |
+/// | `o`                 | Open downloaded file               |
+/// | `O`                 | Show downloaded file in its folder |
+/// | `d`                 | Download                           |
+/// | `D`                 | Download, then open the file       |
+/// | `f`                 | Start entering a link number       |
+/// | `F`                 | Start entering a range of links    |
+/// | `/`, `n`, `N`       | Search, next match, previous match |
+/// | `Y`                 | Copy a command to open this again  |
+/// | `Ctrl-R`            | Save the page's raw HTML           |
+/// | `Ctrl-E`            | Save a report for filing an issue  |
+///
+/// Keys with `Ctrl` held never change anything on Learn or start a download, so `Ctrl-D` only ever scrolls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Binding {
+    Top,
+    Bottom,
+    Down,
+    Up,
+    HalfPageDown,
+    HalfPageUp,
+    PageDown,
+    PageUp,
+    OpenInBrowser,
+    OpenFile,
+    OpenFolder,
+    Download,
+    DownloadAndOpen,
+    LinkEntry,
+    LinkRangeEntry,
+    Search,
+    NextMatch,
+    PrevMatch,
+    CopyDeepLink,
+    SaveRaw,
+    ReportIssue,
+}
+
+/// Get the command bound to the given key, if any.
+/// Keys whose meaning depends on state (digits, `Enter`, `Esc`) aren't bound here.
+fn binding(key: &KeyEvent) -> Option<Binding> {
+    let c = match key.code {
+        KeyCode::Char(c) => c,
+        KeyCode::Home => return Some(Binding::Top),
+        KeyCode::End => return Some(Binding::Bottom),
+        KeyCode::PageDown => return Some(Binding::PageDown),
+        KeyCode::PageUp => return Some(Binding::PageUp),
+        _ => return None,
+    };
+
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        return match c.to_ascii_lowercase() {
+            'd' => Some(Binding::HalfPageDown),
+            'u' => Some(Binding::HalfPageUp),
+            'r' => Some(Binding::SaveRaw),
+            'e' => Some(Binding::ReportIssue),
+            _ => None,
+        };
+    }
+
+    match c {
+        'g' => Some(Binding::Top),
+        'G' => Some(Binding::Bottom),
+        'j' => Some(Binding::Down),
+        'k' => Some(Binding::Up),
+        'b' => Some(Binding::OpenInBrowser),
+        'o' => Some(Binding::OpenFile),
+        'O' => Some(Binding::OpenFolder),
+        'd' => Some(Binding::Download),
+        'D' => Some(Binding::DownloadAndOpen),
+        'f' => Some(Binding::LinkEntry),
+        'F' => Some(Binding::LinkRangeEntry),
+        '/' => Some(Binding::Search),
+        'n' => Some(Binding::NextMatch),
+        'N' => Some(Binding::PrevMatch),
+        'Y' => Some(Binding::CopyDeepLink),
+        _ => None,
+    }
+}
+
 pub struct ContentViewer {
     content_idx: ContentIdx,
 
-    /// Scroll status
+    /// Scroll status. `jump_y_offset` is half or a whole page, depending on config, and `page_y_offset` a whole page.
     y_offset: u16,
     jump_y_offset: u16,
+    page_y_offset: u16,
 
     /// A cached render of what we're displaying, to avoid constantly re-rendering.
     cached_render: Option<Paragraph<'static>>,
 
+    /// The rendered text of a page, if that's what we're displaying. Used for searching.
+    page_text: Option<Text<'static>>,
+
+    /// The width we last drew at
+    last_width: u16,
+
     /// A list of links we're displaying. The user can specify an index to visit them
     displayed_links: Vec<String>,
 
@@ -32,6 +139,15 @@ pub struct ContentViewer {
     link_idx_max_digits: usize,
     link_entry_acc: usize,
     link_entry_digits: Option<usize>,
+
+    /// The start of the range of links being entered, once the user has typed `-`.
+    /// When `link_entry_multi` is set, we wait for `Enter` instead of opening as soon as enough digits are typed.
+    link_range_start: Option<usize>,
+    link_entry_multi: bool,
+
+    /// State for searching. `search_entry` is set while the user is typing a query.
+    search: Option<String>,
+    search_entry: Option<String>,
 }
 impl ContentViewer {
     pub(crate) fn new(content_idx: ContentIdx) -> ContentViewer {
@@ -39,26 +155,60 @@ impl ContentViewer {
             content_idx,
             y_offset: 0,
             jump_y_offset: 0,
+            page_y_offset: 0,
             cached_render: None,
+            page_text: None,
+            last_width: 0,
             displayed_links: vec![],
             link_idx_max_digits: 0,
             link_entry_acc: 0,
             link_entry_digits: None,
+            link_range_start: None,
+            link_entry_multi: false,
+            search: None,
+            search_entry: None,
         }
     }
 
-    /// Render the referenced content item, if it is loaded
-    fn render_content(&mut self, store: &Store) -> Paragraph<'static> {
+    /// Get the content item being shown
+    pub(crate) fn content_idx(&self) -> ContentIdx {
+        self.content_idx
+    }
+
+    /// Check if we want to handle the given key ourselves, rather than it being treated as a shortcut.
+    pub(crate) fn captures_key(&self, key: &KeyEvent) -> bool {
+        self.search_entry.is_some() || (key.code == KeyCode::Esc && self.search.is_some())
+    }
+
+    /// Clear the cached render, so it is re-rendered on next draw.
+    pub(crate) fn invalidate_render(&mut self) {
+        self.cached_render = None;
+    }
+
+    /// Render the referenced content item, if it is loaded.
+    /// `width` is the width of the area it will be displayed in.
+    fn render_content(&mut self, store: &Store, width: u16) -> Paragraph<'static> {
         let content = store.content(self.content_idx);
         match &content.payload {
             ContentPayload::Page => {
-                let Some(text) = store.page_text(self.content_idx) else {
-                    store.request_page_text(self.content_idx);
-                    return Paragraph::new("Loading...");
-                };
-                let (text, links) = bbml::render(text);
+                let PageRender { text, links, .. } =
+                    match store.page_render(self.content_idx, width) {
+                        Some(r) => r,
+                        None => {
+                            let Some(doc) = store.page_doc(self.content_idx) else {
+                                store.request_page_text(self.content_idx);
+                                return Paragraph::new("Loading...");
+                            };
+                            let (text, links) =
+                                doc.render(&store.config().render_options(width as usize));
+                            let render = PageRender { width, text, links };
+                            store.set_page_render(self.content_idx, render.clone());
+                            render
+                        }
+                    };
                 self.set_displayed_links(links);
-                self.cached_render = Some(text);
+                self.page_text = Some(text.clone());
+                self.cached_render = Some(Paragraph::new(text).wrap(Wrap { trim: false }));
                 self.cached_render.clone().unwrap()
             }
             ContentPayload::Link(l) => {
@@ -66,11 +216,35 @@ impl ContentViewer {
                 self.cached_render.clone().unwrap()
             }
             ContentPayload::Placement { name, .. } => {
-                self.cached_render = Some(Paragraph::new(format!("Link to {}. Open with b", name)));
+                self.cached_render = Some(Paragraph::new(vec![
+                    format!("{} placement", name)
+                        .fg(store.config().theme().title)
+                        .bold()
+                        .into(),
+                    Line::raw("Open with b (or Enter from the navigation pane)"),
+                ]));
                 self.cached_render.clone().unwrap()
             }
             ContentPayload::Folder => {
-                self.cached_render = Some(Paragraph::new("Folder"));
+                let mut text = Text::from(Line::from(
+                    content
+                        .title
+                        .clone()
+                        .fg(store.config().theme().title)
+                        .bold(),
+                ));
+                if let Some(description) = &content.description {
+                    let (description, links) = render_description(
+                        description,
+                        &store.config().render_options(width as usize),
+                    );
+                    text.extend(description);
+                    self.set_displayed_links(links);
+                } else {
+                    text.extend(Text::raw("Folder"));
+                }
+
+                self.cached_render = Some(Paragraph::new(text).wrap(Wrap { trim: false }));
                 self.cached_render.clone().unwrap()
             }
             ContentPayload::File {
@@ -79,45 +253,127 @@ impl ContentViewer {
                 ..
             } => {
                 let mut ls = vec![
-                    file_name.to_string().blue().bold().into(),
+                    file_name
+                        .to_string()
+                        .fg(store.config().theme().title)
+                        .bold()
+                        .into(),
                     Line::raw(mime_type.clone()),
                     Line::raw("Open with b"),
                 ];
+                if let Some(description) = &content.description {
+                    let (description, links) = render_description(
+                        description,
+                        &store.config().render_options(width as usize),
+                    );
+                    ls.push(Line::default());
+                    ls.extend(description.lines);
+                    self.set_displayed_links(links);
+                }
                 if let Some((req, state)) = store.download_status(self.content_idx) {
                     match state {
                         DownloadState::Queued => ls.push(Line::styled(
                             "Queued for download",
-                            Style::new().fg(Color::Gray),
+                            Style::new().fg(store.config().theme().hint),
                         )),
                         DownloadState::InProgress(p) => ls.push(Line::styled(
                             format!("Downloading - {:.2}%", p * 100.0),
-                            Style::new().fg(Color::Blue),
+                            Style::new().fg(store.config().theme().progress),
                         )),
                         DownloadState::Completed => ls.push(Line::styled(
-                            format!("Downloaded to {}. Press o to open.", req.dest),
-                            Style::new().fg(Color::Green),
+                            format!(
+                                "Downloaded to {}. Press o to open, or O to show in folder.",
+                                req.dest
+                            ),
+                            Style::new().fg(store.config().theme().success),
                         )),
-                        DownloadState::Errored(e) => ls.extend(error_text(e.to_string()).lines),
+                        DownloadState::Errored(e) => {
+                            ls.extend(error_text(store.config().theme(), e.to_string()).lines)
+                        }
                     }
                 } else {
                     self.cached_render = Some(Paragraph::new(ls.clone()));
                 }
                 Paragraph::new(ls)
             }
-            ContentPayload::Assessment { name, due_date } => {
-                self.cached_render = Some(Paragraph::new(vec![
+            ContentPayload::Assessment {
+                name,
+                due_date,
+                column_id,
+                ..
+            } => {
+                let mut ls = vec![
                     format!("Assessment: {}", name).into(),
                     format!("Due: {}", due_date).into(),
-                ]));
+                ];
+
+                let (attempts, details) = match (
+                    column_id,
+                    store.attempts(self.content_idx),
+                    store.assessment_details(self.content_idx),
+                ) {
+                    (None, _, _) => (None, None),
+                    (Some(_), Some(attempts), Some(details)) => (attempts, details),
+                    (Some(_), attempts, details) => {
+                        if attempts.is_none() {
+                            store.request_attempts(self.content_idx);
+                        }
+                        if details.is_none() {
+                            store.request_assessment_details(self.content_idx);
+                        }
+                        return Paragraph::new(ls);
+                    }
+                };
+
+                if let Some(possible) = details.and_then(|d| d.possible) {
+                    ls.push(format!("Out of: {}", possible).into());
+                }
+                if let Some(allowed) = details.and_then(|d| d.attempts_allowed) {
+                    let used = attempts.map_or(0, |a| a.len());
+                    ls.push(format!("Attempts: {} of {}", used, allowed).into());
+                }
+                match attempts {
+                    Some(attempts) if attempts.iter().any(Attempt::is_submitted) => ls.push(
+                        Line::styled("Submitted", Style::new().fg(store.config().theme().success)),
+                    ),
+                    Some(_) if *due_date < Local::now() => ls.push(Line::styled(
+                        "Not submitted - past due",
+                        Style::new().fg(store.config().theme().error),
+                    )),
+                    Some(_) => ls.push(Line::styled(
+                        "Not submitted",
+                        Style::new().fg(store.config().theme().warning),
+                    )),
+                    None => (),
+                };
+                ls.push(Line::raw("Open the submission page with b"));
+
+                let description = details
+                    .and_then(|d| d.description.as_ref())
+                    .or(content.description.as_ref());
+                if let Some(description) = description {
+                    let (description, links) = render_description(
+                        description,
+                        &store.config().render_options(width as usize),
+                    );
+                    ls.push(Line::default());
+                    ls.extend(description.lines);
+                    self.set_displayed_links(links);
+                }
+
+                self.cached_render = Some(Paragraph::new(ls).wrap(Wrap { trim: false }));
                 self.cached_render.clone().unwrap()
             }
             ContentPayload::Other => {
                 self.cached_render = Some(Paragraph::new(vec![
                     Line::styled(
                         "Unknown content type.",
-                        Style::new().fg(Color::Red).add_modifier(Modifier::BOLD),
+                        Style::new()
+                            .fg(store.config().theme().error)
+                            .add_modifier(Modifier::BOLD),
                     ),
-                    Line::raw("File an issue, and in the meantime open in your browser with b."),
+                    Line::raw("File an issue, attaching the report saved with Ctrl-E."),
+                    Line::raw("In the meantime, open it in your browser with b."),
                 ]));
                 self.cached_render.clone().unwrap()
             }
@@ -140,19 +396,266 @@ impl ContentViewer {
         );
     }
 
-    fn open_referenced_link(&mut self) -> Action {
-        let Some(href) = self.displayed_links.get(self.link_entry_acc) else {
-            return Action::Flash(error_text("No link found".to_string()));
+    /// Save the raw HTML of the page to the state directory, so it can be attached to a bug report
+    fn save_raw(&self, store: &Store) -> Action {
+        let Some(text) = store.page_text(self.content_idx).filter(|t| !t.is_empty()) else {
+            return Action::Flash(error_text(store.config().theme(), "No page text to save."));
+        };
+
+        let content = store.content(self.content_idx);
+        let res = state_file_location(&format!("page-{}-{}.html", content.course_id, content.id))
+            .and_then(|path| {
+                create_dir_all(path.parent().unwrap())?;
+                fs::write(&path, text)?;
+                Ok(path)
+            });
+        match res {
+            Ok(path) => Action::Flash(format!("Saved raw page to {}", path).into()),
+            Err(e) => Action::Flash(error_text(
+                store.config().theme(),
+                format!("Error saving raw page: {e}"),
+            )),
+        }
+    }
+
+    /// Save a report with everything needed to file an issue about this item, and say where to file it.
+    /// Anything identifying the user is removed, and cookies are never included.
+    fn save_report(&self, store: &Store) -> Action {
+        let content = store.content(self.content_idx);
+        let mut report = format!(
+            "{}\nLearn version: {}\n\n== Content ==\n{:#?}\n",
+            args::version(),
+            store.learn_version().unwrap_or("unknown"),
+            content
+        );
+        if let Some(text) = store.page_text(self.content_idx).filter(|t| !t.is_empty()) {
+            report.push_str("\n== Page HTML ==\n");
+            report.push_str(text);
+            report.push('\n');
+        }
+        let report = store.scrub_personal(&report);
+
+        let res = state_file_location(&format!("report-{}-{}.txt", content.course_id, content.id))
+            .and_then(|path| {
+                create_dir_all(path.parent().unwrap())?;
+                fs::write(&path, report)?;
+                Ok(path)
+            });
+        match res {
+            Ok(path) => Action::Flash(
+                format!(
+                    "Saved report to {} - attach it at {}/issues",
+                    path,
+                    env!("CARGO_PKG_REPOSITORY")
+                )
+                .into(),
+            ),
+            Err(e) => Action::Flash(error_text(
+                store.config().theme(),
+                format!("Error saving report: {e}"),
+            )),
+        }
+    }
+
+    /// Handle text pasted while the user is typing a search query
+    fn handle_search_paste(&mut self, pasted: &str) -> Action {
+        let Some(entry) = self.search_entry.as_mut() else {
+            return Action::None;
+        };
+        entry.push_str(&strip_control(pasted));
+
+        Action::Flash(format!("/{}", entry).into())
+    }
+
+    /// Handle a key press while the user is typing a search query
+    fn handle_search_entry(&mut self, store: &Store, key: KeyEvent) -> Action {
+        let Some(entry) = self.search_entry.as_mut() else {
+            return Action::None;
+        };
+
+        match key.code {
+            KeyCode::Char(c) if !c.is_control() => entry.push(c),
+            KeyCode::Backspace => {
+                entry.pop();
+            }
+            KeyCode::Esc => {
+                self.search_entry = None;
+                return Action::Flash("".into());
+            }
+            KeyCode::Enter => {
+                let query = self.search_entry.take().unwrap();
+                if query.is_empty() {
+                    self.search = None;
+                    return Action::Flash("".into());
+                }
+
+                self.search = Some(query);
+                return self.jump_to_match(store, true, true);
+            }
+            _ => (),
+        };
+
+        Action::Flash(format!("/{}", entry).into())
+    }
+
+    /// Scroll to the next (or previous) line containing the current search query.
+    /// If `inclusive`, a match on the current line counts.
+    fn jump_to_match(&mut self, store: &Store, forward: bool, inclusive: bool) -> Action {
+        let (Some(query), Some(text)) = (&self.search, &self.page_text) else {
+            return Action::None;
+        };
+        let query = query.to_ascii_lowercase();
+
+        // Find where each matching line starts, accounting for wrapping
+        let mut offset = 0;
+        let mut match_offsets = vec![];
+        for line in text.lines.iter() {
+            let content = line
+                .spans
+                .iter()
+                .map(|s| s.content.as_ref())
+                .collect::<String>();
+            if content.to_ascii_lowercase().contains(&query) {
+                match_offsets.push(offset);
+            }
+            offset += Paragraph::new(line.clone())
+                .wrap(Wrap { trim: false })
+                .line_count(self.last_width.saturating_sub(2)) as u16;
+        }
+
+        if match_offsets.is_empty() {
+            return Action::Flash(error_text(
+                store.config().theme(),
+                format!("No matches for {}", query),
+            ));
+        }
+
+        let next = if forward {
+            match_offsets
+                .iter()
+                .position(|o| *o > self.y_offset || (inclusive && *o == self.y_offset))
+                .unwrap_or(0)
+        } else {
+            match_offsets
+                .iter()
+                .rposition(|o| *o < self.y_offset)
+                .unwrap_or(match_offsets.len() - 1)
         };
+        self.y_offset = match_offsets[next];
 
-        if let Err(e) = open::that(href) {
-            return Action::Flash(error_text(format!("Error opening in browser: {e}")));
+        Action::Flash(
+            format!(
+                "/{} - match {} of {} (n/N for next/previous, Esc to clear)",
+                query,
+                next + 1,
+                match_offsets.len()
+            )
+            .into(),
+        )
+    }
+
+    /// Start entering a link number, or a range of them if `multi` is set
+    fn start_link_entry(&mut self, multi: bool) -> Action {
+        if self.link_idx_max_digits == 0 {
+            return Action::None;
         }
 
+        self.link_entry_acc = 0;
+        self.link_entry_digits = Some(0);
+        self.link_range_start = None;
+        self.link_entry_multi = multi;
+
+        Action::Flash(if multi {
+            "Open links... (type a range like 3-7, then RET)".into()
+        } else {
+            "Go to... (type the number after the link)".into()
+        })
+    }
+
+    /// Treat the number typed so far as the start of a range of links
+    fn start_link_range(&mut self) {
+        self.link_range_start = Some(self.link_entry_acc);
+        self.link_entry_acc = 0;
+        self.link_entry_digits = Some(0);
+        self.link_entry_multi = true;
+    }
+
+    /// Get what the user has typed so far in link entry, ie `3-7`
+    fn link_entry_prompt(&self) -> String {
+        match self.link_range_start {
+            Some(start) if self.link_entry_digits == Some(0) => format!("{start}-"),
+            Some(start) => format!("{start}-{}", self.link_entry_acc),
+            None => self.link_entry_acc.to_string(),
+        }
+    }
+
+    /// Get the indices of the links the user has entered, if they're valid
+    fn entered_links(&self) -> Option<RangeInclusive<usize>> {
+        let start = self.link_range_start.unwrap_or(self.link_entry_acc);
+        let end = match (self.link_range_start, self.link_entry_digits) {
+            // `3-` opens everything from 3 onwards
+            (Some(_), Some(0)) => self.displayed_links.len().checked_sub(1)?,
+            _ => self.link_entry_acc,
+        };
+
+        (start <= end && end < self.displayed_links.len()).then_some(start..=end)
+    }
+
+    fn open_referenced_link(&mut self, store: &mut Store) -> Action {
+        let entered = self.entered_links();
+        let prompt = self.link_entry_prompt();
         self.link_entry_acc = 0;
         self.link_entry_digits = None;
+        self.link_range_start = None;
+        self.link_entry_multi = false;
+
+        let Some(range) = entered else {
+            return Action::Flash(error_text(
+                store.config().theme(),
+                format!("No links found for {prompt}"),
+            ));
+        };
+
+        let count = range.clone().count();
+        if count == 1 {
+            // Links to content in the user's courses are opened here rather than in the browser
+            let href = &self.displayed_links[*range.start()];
+            if let Some(action) = parse_content_link(href).and_then(|(course_id, content_id)| {
+                store.open_linked_content(&course_id, &content_id)
+            }) {
+                return action;
+            }
+        }
+
+        for href in &self.displayed_links[range] {
+            let output = match store.config().open(href) {
+                Ok(output) => output,
+                Err(e) => {
+                    return Action::Flash(error_text(
+                        store.config().theme(),
+                        format!("Error opening in browser: {e}"),
+                    ))
+                }
+            };
+            if count == 1 {
+                return Action::Flash(
+                    output
+                        .unwrap_or_else(|| format!("Opened {href} in browser"))
+                        .into(),
+                );
+            }
+        }
+
+        Action::Flash(format!("Opened {count} links in browser").into())
+    }
+}
 
-        Action::Flash(format!("Opened {href} in browser").into())
+/// Render a content item's description, which may be plain text or BbML.
+fn render_description(description: &str, options: &RenderOptions) -> (Text<'static>, Vec<String>) {
+    if description.contains('<') {
+        bbml::render_text_with_options(description, options)
+    } else {
+        (Text::raw(description.to_string()), vec![])
     }
 }
 
@@ -163,13 +666,22 @@ impl Pane for ContentViewer {
         frame: &mut ratatui::Frame,
         area: ratatui::prelude::Rect,
     ) {
-        let rendered = self
+        // Account for the margin we leave for the scrollbar
+        let mut rendered = self
             .cached_render
             .clone()
-            .unwrap_or_else(|| self.render_content(store));
+            .unwrap_or_else(|| self.render_content(store, area.width.saturating_sub(2)));
+        self.last_width = area.width;
+
+        // Highlight search matches on a copy, so the cached render stays clean
+        if let (Some(query), Some(text)) = (&self.search, &self.page_text) {
+            rendered = Paragraph::new(highlight_matches(store.config().theme(), text, query))
+                .wrap(Wrap { trim: false });
+        }
 
         let line_count = rendered.line_count(area.width);
-        self.jump_y_offset = area.height / 2;
+        self.jump_y_offset = store.config().jump_lines(area.height);
+        self.page_y_offset = area.height;
 
         let max_y_offset = (line_count as u16).saturating_sub(area.height);
         self.y_offset = self.y_offset.min(max_y_offset);
@@ -196,74 +708,147 @@ impl Pane for ContentViewer {
         store: &mut crate::store::Store,
         event: crate::event::Event,
     ) -> crate::main_screen::Action {
-        let Event::Key(key) = event else {
-            return Action::None;
+        let key = match event {
+            Event::Key(key) => key,
+            Event::Paste(s) => return self.handle_search_paste(&s),
+            _ => return Action::None,
         };
 
-        match key.code {
-            // Basic vim-like navigation
-            KeyCode::Char('g') => self.y_offset = 0,
-            KeyCode::Char('G') => self.y_offset = u16::MAX,
+        if self.search_entry.is_some() {
+            return self.handle_search_entry(store, key);
+        }
 
-            KeyCode::Char('j') => self.y_offset += 1,
-            KeyCode::Char('k') => self.y_offset = self.y_offset.saturating_sub(1),
+        match (binding(&key), key.code) {
+            // Searching
+            (Some(Binding::Search), _) if self.page_text.is_some() => {
+                self.search_entry = Some(String::new());
+                return Action::Flash("/".into());
+            }
+            (Some(Binding::NextMatch), _) => return self.jump_to_match(store, true, false),
+            (Some(Binding::PrevMatch), _) => return self.jump_to_match(store, false, false),
+            (None, KeyCode::Esc) if self.search.is_some() => {
+                self.search = None;
+                return Action::Flash("".into());
+            }
 
-            KeyCode::Char('u') | KeyCode::Char('U')
-                if key.modifiers.contains(KeyModifiers::CONTROL) =>
-            {
+            // Basic vim-like navigation
+            (Some(Binding::Top), _) => self.y_offset = 0,
+            (Some(Binding::Bottom), _) => self.y_offset = u16::MAX,
+            (Some(Binding::Down), _) => {
+                self.y_offset = self.y_offset.saturating_add(store.config().scroll_step)
+            }
+            (Some(Binding::Up), _) => {
+                self.y_offset = self.y_offset.saturating_sub(store.config().scroll_step)
+            }
+            (Some(Binding::HalfPageUp), _) => {
                 self.y_offset = self.y_offset.saturating_sub(self.jump_y_offset)
             }
-            KeyCode::Char('d') | KeyCode::Char('D')
-                if key.modifiers.contains(KeyModifiers::CONTROL) =>
-            {
-                self.y_offset += self.jump_y_offset
+            (Some(Binding::HalfPageDown), _) => self.y_offset += self.jump_y_offset,
+            (Some(Binding::PageUp), _) => {
+                self.y_offset = self.y_offset.saturating_sub(self.page_y_offset)
+            }
+            // clamped to the bottom of the page when drawing
+            (Some(Binding::PageDown), _) => {
+                self.y_offset = self.y_offset.saturating_add(self.page_y_offset)
             }
 
             // Open in browser / open downloaded file
-            KeyCode::Char('b') => {
+            (Some(Binding::OpenInBrowser), _) => {
                 self.link_entry_digits = None;
                 let content = store.content(self.content_idx);
-                if let Err(e) = open::that(content.browser_link()) {
-                    return Action::Flash(error_text(format!("Error opening in browser: {e}")));
+                match store.config().open(content.browser_link()) {
+                    Ok(Some(output)) => return Action::Flash(output.into()),
+                    Ok(None) => (),
+                    Err(e) => {
+                        return Action::Flash(error_text(
+                            store.config().theme(),
+                            format!("Error opening in browser: {e}"),
+                        ))
+                    }
                 }
             }
-            KeyCode::Char('o') => {
+            (Some(Binding::OpenFile), _) => {
                 self.link_entry_digits = None;
                 if let Some((req, DownloadState::Completed)) =
                     store.download_status(self.content_idx)
                 {
-                    if let Err(e) = open::that(&req.dest) {
-                        return Action::Flash(error_text(format!("Error opening file: {e}")));
+                    match store.config().open(&req.dest) {
+                        Ok(Some(output)) => return Action::Flash(output.into()),
+                        Ok(None) => (),
+                        Err(e) => {
+                            return Action::Flash(error_text(
+                                store.config().theme(),
+                                format!("Error opening file: {e}"),
+                            ))
+                        }
                     }
                 }
             }
+            (Some(Binding::OpenFolder), _) => {
+                self.link_entry_digits = None;
+                if let Some((req, DownloadState::Completed)) =
+                    store.download_status(self.content_idx)
+                {
+                    return match store.config().reveal(&req.dest) {
+                        Ok(path) => Action::Flash(format!("Opened {}", path).into()),
+                        Err(e) => Action::Flash(error_text(
+                            store.config().theme(),
+                            format!("Error opening folder: {e}"),
+                        )),
+                    };
+                }
+            }
+
+            (Some(Binding::CopyDeepLink), _) => {
+                let content = store.content(self.content_idx);
+                let link = DeepLink {
+                    course_id: content.course_id.clone(),
+                    content_id: Some(content.id.clone()),
+                };
+                return Action::Copy(link.command());
+            }
+            (Some(Binding::SaveRaw), _) => return self.save_raw(store),
+            (Some(Binding::ReportIssue), _) => return self.save_report(store),
 
             // Queue download
-            KeyCode::Char('d') => {
-                store.download_content(self.content_idx);
+            (Some(binding @ (Binding::Download | Binding::DownloadAndOpen)), _) => {
+                if store.is_offline() {
+                    return Action::Flash(error_text(
+                        store.config().theme(),
+                        "Can't download while offline.",
+                    ));
+                }
+                store.download_content(self.content_idx, binding == Binding::DownloadAndOpen);
                 self.cached_render = None;
                 return Action::Flash("Queued for download".into());
             }
 
             // Link index entry
-            KeyCode::Char('f') => {
-                if self.link_idx_max_digits > 0 {
-                    self.link_entry_acc = 0;
-                    self.link_entry_digits = Some(0);
-
-                    return Action::Flash(
-                        "Go to... (type the number after the link)"
-                            .to_string()
-                            .into(),
-                    );
-                }
+            (Some(Binding::LinkEntry), _) => return self.start_link_entry(false),
+            (Some(Binding::LinkRangeEntry), _) => return self.start_link_entry(true),
+            (None, KeyCode::Enter) if self.link_entry_digits.is_some() => {
+                return self.open_referenced_link(store);
             }
-            KeyCode::Enter if self.link_entry_digits.is_some() => {
-                return self.open_referenced_link();
+            (None, KeyCode::Char('-'))
+                if self.link_entry_digits.is_some_and(|d| d > 0)
+                    && self.link_range_start.is_none() =>
+            {
+                self.start_link_range();
+                return Action::Flash(
+                    format!("Open links... {} (RET to open)", self.link_entry_prompt()).into(),
+                );
             }
 
-            KeyCode::Char(n) if n.is_ascii_digit() => {
+            (None, KeyCode::Char(n)) if n.is_ascii_digit() => {
                 if let Some(idx) = self.link_entry_digits.as_mut() {
+                    // no link has a longer number, and the number could overflow if we kept going
+                    if *idx == self.link_idx_max_digits {
+                        return Action::Flash(error_text(
+                            store.config().theme(),
+                            format!("No links found for {}{n}", self.link_entry_prompt()),
+                        ));
+                    }
+
                     // add new digit to end of number
                     self.link_entry_acc *= 10;
                     self.link_entry_acc += n.to_digit(10).unwrap() as usize;
@@ -274,13 +859,18 @@ impl Pane for ContentViewer {
                         "entered {idx} digits / {}. acc = {}",
                         self.link_idx_max_digits, self.link_entry_acc
                     );
-                    if *idx == self.link_idx_max_digits {
-                        return self.open_referenced_link();
+                    if *idx == self.link_idx_max_digits && !self.link_entry_multi {
+                        return self.open_referenced_link(store);
+                    } else if self.link_entry_multi {
+                        return Action::Flash(
+                            format!("Open links... {} (RET to open)", self.link_entry_prompt())
+                                .into(),
+                        );
                     } else {
                         return Action::Flash(
                             format!(
-                                "Go to... {} (RET to open, or keep typing numbers)",
-                                self.link_entry_acc
+                                "Go to... {} (RET to open, - for a range, or keep typing numbers)",
+                                self.link_entry_prompt()
                             )
                             .into(),
                         );
@@ -293,7 +883,133 @@ impl Pane for ContentViewer {
 
         // Every branch where we do more digit entry returns, so if we've stopped doing that then exit that mode
         self.link_entry_digits = None;
+        self.link_range_start = None;
+        self.link_entry_multi = false;
 
         Action::None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_d_downloads() {
+        let key = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE);
+        assert_eq!(binding(&key), Some(Binding::Download));
+    }
+
+    #[test]
+    fn test_shift_d_downloads_and_opens() {
+        let key = KeyEvent::new(KeyCode::Char('D'), KeyModifiers::SHIFT);
+        assert_eq!(binding(&key), Some(Binding::DownloadAndOpen));
+    }
+
+    #[test]
+    fn test_ctrl_d_scrolls() {
+        let key = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(binding(&key), Some(Binding::HalfPageDown));
+
+        // some terminals report shift as well
+        let key = KeyEvent::new(
+            KeyCode::Char('D'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+        );
+        assert_eq!(binding(&key), Some(Binding::HalfPageDown));
+    }
+
+    /// Get a viewer showing `n` links, with the given link entry typed
+    fn entering_links(n: usize, keys: &str) -> ContentViewer {
+        let mut viewer = ContentViewer::new(0);
+        viewer.set_displayed_links((0..n).map(|i| format!("https://example.com/{i}")).collect());
+        viewer.start_link_entry(true);
+        for c in keys.chars() {
+            if c == '-' {
+                viewer.start_link_range();
+            } else {
+                viewer.link_entry_acc =
+                    viewer.link_entry_acc * 10 + c.to_digit(10).unwrap() as usize;
+                viewer.link_entry_digits = viewer.link_entry_digits.map(|d| d + 1);
+            }
+        }
+        viewer
+    }
+
+    #[test]
+    fn test_link_ranges() {
+        assert_eq!(entering_links(10, "3-7").entered_links(), Some(3..=7));
+        assert_eq!(entering_links(10, "4").entered_links(), Some(4..=4));
+        assert_eq!(entering_links(10, "8-").entered_links(), Some(8..=9));
+        assert_eq!(entering_links(10, "7-3").entered_links(), None);
+        assert_eq!(entering_links(10, "3-12").entered_links(), None);
+    }
+
+    #[test]
+    fn test_link_entry_digits_limited() {
+        let (mut store, _channels) = Store::for_test(Default::default());
+        let mut viewer = ContentViewer::new(0);
+        viewer.set_displayed_links(
+            (0..12)
+                .map(|i| format!("https://example.com/{i}"))
+                .collect(),
+        );
+
+        let key = |c| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        viewer.handle_event(&mut store, key('F'));
+        for c in "1-99999999999999999999999".chars() {
+            viewer.handle_event(&mut store, key(c));
+        }
+        assert_eq!(viewer.link_entry_prompt(), "1-99");
+        assert_eq!(viewer.entered_links(), None);
+    }
+
+    #[test]
+    fn test_folder_description() {
+        let (text, links) = render_description(
+            "<p>Slides for <a href=\"https://example.com\">week 1</a></p>",
+            &RenderOptions::default(),
+        );
+        let rendered = text
+            .lines
+            .iter()
+            .flat_map(|l| l.spans.iter().map(|s| s.content.as_ref()))
+            .collect::<String>();
+        assert!(rendered.contains("Slides for") && rendered.contains("week 1"));
+        assert_eq!(links, vec!["https://example.com"]);
+
+        let (text, links) = render_description("Just text", &RenderOptions::default());
+        assert_eq!(text, Text::raw("Just text"));
+        assert!(links.is_empty());
+    }
+
+    #[test]
+    fn test_page_keys() {
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        assert_eq!(binding(&key(KeyCode::PageDown)), Some(Binding::PageDown));
+        assert_eq!(binding(&key(KeyCode::PageUp)), Some(Binding::PageUp));
+        assert_eq!(binding(&key(KeyCode::Home)), Some(Binding::Top));
+        assert_eq!(binding(&key(KeyCode::End)), Some(Binding::Bottom));
+    }
+
+    #[test]
+    fn test_ctrl_r_saves_raw() {
+        let key = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert_eq!(binding(&key), Some(Binding::SaveRaw));
+
+        let key = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE);
+        assert_eq!(binding(&key), None);
+    }
+
+    #[test]
+    fn test_ctrl_e_reports_issue() {
+        let key = KeyEvent::new(KeyCode::Char('e'), KeyModifiers::CONTROL);
+        assert_eq!(binding(&key), Some(Binding::ReportIssue));
+    }
+
+    #[test]
+    fn test_ctrl_other_unbound() {
+        let key = KeyEvent::new(KeyCode::Char('b'), KeyModifiers::CONTROL);
+        assert_eq!(binding(&key), None);
+    }
+}
@@ -1,4 +1,10 @@
-use ratatui::{prelude::Rect, style::Stylize, text::Line, widgets::Paragraph, Frame};
+use ratatui::{
+    prelude::Rect,
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Gauge, Paragraph},
+    Frame,
+};
 
 use crate::{
     event::Event,
@@ -11,32 +17,66 @@ pub struct DownloadsViewer {}
 
 impl Pane for DownloadsViewer {
     fn draw(&mut self, store: &Store, frame: &mut Frame, area: Rect) {
-        let lines = store
-            .download_queue()
-            .flat_map(|(req, state)| {
-                vec![
-                    vec![
-                        req.orig_filename.to_string().blue(),
-                        match &state {
-                            DownloadState::Queued => " - Queued".gray(),
-                            DownloadState::InProgress(p) => format!(" - {:.2}%", p * 100.0).blue(),
-                            DownloadState::Completed => " - Completed".green(),
-                            DownloadState::Errored(e) => format!(" - {e}").red(),
-                        },
-                    ]
-                    .into(),
-                    vec![req.dest.to_string().gray()].into(),
-                ]
-            })
-            .collect::<Vec<Line>>();
-
-        let p = if lines.is_empty() {
-            Paragraph::new("No downloads started.")
-        } else {
-            Paragraph::new(lines)
+        let theme = store.config().theme();
+        let mut queue = store.download_queue().peekable();
+        if queue.peek().is_none() {
+            frame.render_widget(Paragraph::new("No downloads started."), area);
+            return;
+        }
+
+        // Each download gets a row for its name and status, then one for a progress bar if it's in progress, then its destination.
+        let mut y = area.y;
+        let bottom = area.y + area.height;
+        let mut next_row = || {
+            let r = (y < bottom).then_some(Rect {
+                x: area.x,
+                y,
+                width: area.width,
+                height: 1,
+            });
+            y += 1;
+            r
         };
 
-        frame.render_widget(p, area);
+        for (req, state) in queue {
+            let title: Line = vec![
+                req.orig_filename.to_string().fg(theme.title),
+                match &state {
+                    DownloadState::Queued => " - Queued".fg(theme.hint),
+                    DownloadState::InProgress(p) => {
+                        format!(" - {:.2}%", p * 100.0).fg(theme.progress)
+                    }
+                    DownloadState::Completed => " - Completed".fg(theme.success),
+                    DownloadState::Errored(e) => format!(" - {e}").fg(theme.error),
+                },
+            ]
+            .into();
+            let Some(title_area) = next_row() else {
+                break;
+            };
+            frame.render_widget(Paragraph::new(title), title_area);
+
+            if let DownloadState::InProgress(p) = state {
+                let Some(gauge_area) = next_row() else {
+                    break;
+                };
+                frame.render_widget(
+                    Gauge::default()
+                        .gauge_style(Style::new().fg(theme.progress).bg(theme.inactive))
+                        .ratio(p.clamp(0.0, 1.0) as f64)
+                        .label(""),
+                    gauge_area,
+                );
+            }
+
+            let Some(dest_area) = next_row() else {
+                break;
+            };
+            frame.render_widget(
+                Paragraph::new(req.dest.to_string().fg(theme.hint)),
+                dest_area,
+            );
+        }
     }
 
     fn handle_event(&mut self, _: &mut Store, _: Event) -> Action {
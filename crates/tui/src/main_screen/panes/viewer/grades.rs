@@ -0,0 +1,87 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    prelude::Rect,
+    style::Stylize,
+    text::{Line, Text},
+    widgets::Paragraph,
+    Frame,
+};
+
+use crate::{
+    event::Event,
+    main_screen::{panes::Pane, Action},
+    store::{CourseIdx, Store},
+    styles::error_text,
+};
+
+/// Shows our grades in a course
+#[derive(Debug)]
+pub struct GradesViewer {
+    course_idx: CourseIdx,
+    y_offset: u16,
+}
+
+impl GradesViewer {
+    pub(crate) fn new(course_idx: CourseIdx) -> Self {
+        Self {
+            course_idx,
+            y_offset: 0,
+        }
+    }
+
+    /// Get the course whose grades are being shown
+    pub(crate) fn course_idx(&self) -> CourseIdx {
+        self.course_idx
+    }
+}
+
+impl Pane for GradesViewer {
+    fn draw(&mut self, store: &Store, frame: &mut Frame, area: Rect) {
+        let Some(grades) = store.grades(self.course_idx) else {
+            store.request_grades(self.course_idx);
+            frame.render_widget(Paragraph::new("Loading..."), area);
+            return;
+        };
+
+        let theme = store.config().theme();
+        let mut text = Text::from(Line::from(
+            format!("Grades - {}", store.course(self.course_idx).name)
+                .fg(theme.title)
+                .bold(),
+        ));
+        text.lines.push(Line::default());
+        match grades {
+            None => text.extend(error_text(theme, "Couldn't load grades.")),
+            Some([]) => text.extend(Text::raw("No grades.")),
+            Some(grades) => {
+                for grade in grades {
+                    text.lines.push(Line::from(vec![
+                        format!("{}: ", grade.column_name).into(),
+                        match grade.display() {
+                            Some(g) => g.fg(theme.success),
+                            None => "-".fg(theme.inactive),
+                        },
+                    ]));
+                }
+            }
+        };
+
+        let max_y_offset = (text.height() as u16).saturating_sub(area.height);
+        self.y_offset = self.y_offset.min(max_y_offset);
+        frame.render_widget(Paragraph::new(text).scroll((self.y_offset, 0)), area);
+    }
+
+    fn handle_event(&mut self, _: &mut Store, event: Event) -> Action {
+        let Event::Key(key) = event else {
+            return Action::None;
+        };
+
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => self.y_offset = self.y_offset.saturating_add(1),
+            KeyCode::Up | KeyCode::Char('k') => self.y_offset = self.y_offset.saturating_sub(1),
+            _ => (),
+        };
+
+        Action::None
+    }
+}
@@ -3,17 +3,23 @@ use ratatui::{prelude::Rect, Frame};
 
 use crate::{
     event::Event,
-    store::{ContentIdx, Store},
+    store::{ContentIdx, CourseIdx, Store},
 };
 
 use super::{Action, Pane};
 
+mod announcements;
 mod content;
 mod downloads;
+mod grades;
+mod recent;
 mod welcome;
 
+use announcements::AnnouncementsViewer;
 use content::ContentViewer;
 use downloads::DownloadsViewer;
+use grades::GradesViewer;
+use recent::RecentViewer;
 use welcome::WelcomeViewer;
 
 /// Something we want to show in the viewer
@@ -26,6 +32,15 @@ pub enum Document {
     /// The list of downloads
     Downloads,
 
+    /// Recently viewed content
+    Recent,
+
+    /// A course's announcements
+    Announcements(CourseIdx),
+
+    /// Our grades in a course
+    Grades(CourseIdx),
+
     /// A content item
     Content(ContentIdx),
 }
@@ -35,6 +50,9 @@ pub enum Document {
 pub enum Viewer {
     Welcome(WelcomeViewer),
     Downloads(DownloadsViewer),
+    Recent(RecentViewer),
+    Announcements(AnnouncementsViewer),
+    Grades(GradesViewer),
     Content(ContentViewer),
 }
 
@@ -50,9 +68,38 @@ impl Viewer {
         match d {
             Document::Welcome => *self = Self::Welcome(Default::default()),
             Document::Downloads => *self = Self::Downloads(Default::default()),
+            Document::Recent => *self = Self::Recent(Default::default()),
+            Document::Announcements(idx) => {
+                *self = Self::Announcements(AnnouncementsViewer::new(idx))
+            }
+            Document::Grades(idx) => *self = Self::Grades(GradesViewer::new(idx)),
             Document::Content(idx) => *self = Self::Content(ContentViewer::new(idx)),
         };
     }
+
+    /// Check if we're showing a content item
+    pub fn is_content(&self) -> bool {
+        matches!(self, Viewer::Content(_))
+    }
+
+    /// Get the document currently being shown
+    pub fn document(&self) -> Document {
+        match self {
+            Viewer::Welcome(_) => Document::Welcome,
+            Viewer::Downloads(_) => Document::Downloads,
+            Viewer::Recent(_) => Document::Recent,
+            Viewer::Announcements(viewer) => Document::Announcements(viewer.course_idx()),
+            Viewer::Grades(viewer) => Document::Grades(viewer.course_idx()),
+            Viewer::Content(viewer) => Document::Content(viewer.content_idx()),
+        }
+    }
+
+    /// Discard any cached renders, for instance because the available space has changed.
+    pub fn invalidate_render(&mut self) {
+        if let Viewer::Content(viewer) = self {
+            viewer.invalidate_render();
+        }
+    }
 }
 
 impl Pane for Viewer {
@@ -60,22 +107,36 @@ impl Pane for Viewer {
         match self {
             Viewer::Welcome(viewer) => viewer.draw(store, frame, area),
             Viewer::Downloads(viewer) => viewer.draw(store, frame, area),
+            Viewer::Recent(viewer) => viewer.draw(store, frame, area),
+            Viewer::Announcements(viewer) => viewer.draw(store, frame, area),
+            Viewer::Grades(viewer) => viewer.draw(store, frame, area),
             Viewer::Content(viewer) => viewer.draw(store, frame, area),
         }
     }
 
     fn handle_event(&mut self, store: &mut Store, event: Event) -> Action {
-        let Event::Key(key) = event else {
-            return Action::None;
+        let key = match event {
+            Event::Key(key) => key,
+            Event::Paste(_) => {
+                return match self {
+                    Viewer::Content(viewer) => viewer.handle_event(store, event),
+                    _ => Action::None,
+                };
+            }
+            _ => return Action::None,
         };
 
-        if let KeyCode::Char('q') | KeyCode::Esc = key.code {
+        let captured = matches!(self, Viewer::Content(viewer) if viewer.captures_key(&key));
+        if let (KeyCode::Char('q') | KeyCode::Esc, false) = (key.code, captured) {
             return Action::FocusNavigation;
         };
 
         match self {
             Viewer::Welcome(viewer) => viewer.handle_event(store, event),
             Viewer::Downloads(viewer) => viewer.handle_event(store, event),
+            Viewer::Recent(viewer) => viewer.handle_event(store, event),
+            Viewer::Announcements(viewer) => viewer.handle_event(store, event),
+            Viewer::Grades(viewer) => viewer.handle_event(store, event),
             Viewer::Content(viewer) => viewer.handle_event(store, event),
         }
     }
@@ -0,0 +1,98 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    prelude::Rect,
+    style::{Modifier, Style, Stylize},
+    text::Line,
+    widgets::{List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::{
+    event::Event,
+    main_screen::{
+        panes::{Document, Pane},
+        Action,
+    },
+    store::Store,
+    styles::error_text,
+};
+
+/// Shows recently viewed content, and lets the user jump back to it.
+#[derive(Debug, Default)]
+pub struct RecentViewer {
+    list_state: ListState,
+}
+
+impl Pane for RecentViewer {
+    fn draw(&mut self, store: &Store, frame: &mut Frame, area: Rect) {
+        let recent = store.recent();
+        if recent.is_empty() {
+            frame.render_widget(Paragraph::new("Nothing viewed recently."), area);
+            return;
+        }
+
+        let theme = store.config().theme();
+        let items = recent
+            .iter()
+            .map(|item| {
+                // Items from previous sessions might not be loaded yet
+                if store
+                    .find_content(&item.course_id, &item.content_id)
+                    .is_some()
+                {
+                    ListItem::new(Line::from(item.title.clone().fg(theme.title)))
+                } else {
+                    ListItem::new(Line::from(vec![
+                        item.title.clone().fg(theme.hint),
+                        " (not loaded)".fg(theme.inactive),
+                    ]))
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if self.list_state.selected().is_none() {
+            self.list_state.select(Some(0));
+        }
+
+        frame.render_stateful_widget(
+            List::new(items)
+                .highlight_symbol(">>")
+                .highlight_style(Style::new().add_modifier(Modifier::BOLD)),
+            area,
+            &mut self.list_state,
+        );
+    }
+
+    fn handle_event(&mut self, store: &mut Store, event: Event) -> Action {
+        let Event::Key(key) = event else {
+            return Action::None;
+        };
+
+        let len = store.recent().iter().count();
+        let selected = self.list_state.selected().unwrap_or(0);
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') if selected + 1 < len => {
+                self.list_state.select(Some(selected + 1));
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.list_state.select(Some(selected.saturating_sub(1)));
+            }
+            KeyCode::Enter => {
+                let Some(item) = store.recent().iter().nth(selected) else {
+                    return Action::None;
+                };
+
+                return match store.find_content(&item.course_id, &item.content_id) {
+                    Some(idx) => Action::Show(Document::Content(idx)),
+                    None => Action::Flash(error_text(
+                        store.config().theme(),
+                        "Not loaded yet - expand its course in the navigation pane first.",
+                    )),
+                };
+            }
+            _ => (),
+        };
+
+        Action::None
+    }
+}
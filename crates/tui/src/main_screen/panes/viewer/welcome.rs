@@ -6,17 +6,19 @@ use ratatui::{
 };
 
 use crate::{
+    args,
     event::Event,
     main_screen::{self, panes::Pane, Action},
     store::Store,
+    styles::Theme,
 };
 
 #[derive(Debug, Default)]
 pub struct WelcomeViewer {}
 
 impl Pane for WelcomeViewer {
-    fn draw(&mut self, _: &Store, frame: &mut Frame, area: Rect) {
-        frame.render_widget(welcome_message(), area);
+    fn draw(&mut self, store: &Store, frame: &mut Frame, area: Rect) {
+        frame.render_widget(welcome_message(store.config().theme()), area);
     }
 
     fn handle_event(&mut self, _: &mut Store, _: Event) -> main_screen::Action {
@@ -24,42 +26,103 @@ impl Pane for WelcomeViewer {
     }
 }
 
-fn welcome_message() -> Paragraph<'static> {
+fn welcome_message(theme: &Theme) -> Paragraph<'static> {
     Paragraph::new(vec![
-        vec!["Welcome to learn-tui!\n".blue().bold()].into(),
+        vec!["Welcome to learn-tui!\n".fg(theme.title).bold()].into(),
         vec![
             "Use ".into(),
-            "j/k or ↓/↑".blue(),
+            "j/k or ↓/↑".fg(theme.title),
             " to navigate up and down, then ".into(),
-            "Enter".blue(),
+            "Enter".fg(theme.title),
             " to select an item.".into(),
         ]
         .into(),
+        vec![
+            "Select a term's heading to collapse or expand it. Courses in collapsed terms aren't loaded in the background."
+                .into(),
+        ]
+        .into(),
         vec![
             "When an item is selected, you can scroll the viewer pane using ".into(),
-            "j/k ↓/↑ g/G PgUp/PgDn".blue(),
+            "j/k ↓/↑ g/G PgUp/PgDn".fg(theme.title),
             " and go back to the navigation pane with ".into(),
-            "q".blue(),
+            "q".fg(theme.title),
             ".".into(),
         ]
         .into(),
+        vec![
+            "Search the text of a page with ".into(),
+            "/".fg(theme.title),
+            ", then use ".into(),
+            "n/N".fg(theme.title),
+            " to go to the next or previous match.".into(),
+        ]
+        .into(),
         vec![
             "Links have ".into(),
-            "blue".blue(),
+            "coloured".fg(theme.page.link),
             " text and a number after them. Hit ".into(),
-            "f".blue(),
-            " then type the number to open them.".into(),
+            "f".fg(theme.title),
+            " then type the number to open them, or ".into(),
+            "F".fg(theme.title),
+            " then a range like ".into(),
+            "3-7".fg(theme.title),
+            " to open several at once. Links to content in your courses open here instead of in your browser.".into(),
         ]
         .into(),
         vec![
             "At any point, use ".into(),
-            "b".blue(),
+            "b".fg(theme.title),
             " to try to open the selected item in your browser, or ".into(),
-            "d".blue(),
-            " to try to download it.".into(),
+            "d".fg(theme.title),
+            " to try to download it. ".into(),
+            "D".fg(theme.title),
+            " downloads it, then opens it.".into(),
+        ]
+        .into(),
+        vec![
+            "Items you haven't read are marked with ".into(),
+            "●".fg(theme.accent),
+            ". Viewing an item marks it as read, or toggle it with ".into(),
+            "m".fg(theme.title),
+            " in the navigation pane.".into(),
+        ]
+        .into(),
+        vec![
+            "If you've set ".into(),
+            "hidden_content".fg(theme.title),
+            " in your config, toggle showing those items with ".into(),
+            "H".fg(theme.title),
+            ".".into(),
+        ]
+        .into(),
+        vec![
+            "If something's changed on Learn, reload the selected course or folder with ".into(),
+            "r".fg(theme.title),
+            ".".into(),
+        ]
+        .into(),
+        vec![
+            "Copy a command that opens the selected course or item with ".into(),
+            "Y".fg(theme.title),
+            ", to bookmark or share it.".into(),
+        ]
+        .into(),
+        vec![
+            "Jump to a course by typing part of its code or name after ".into(),
+            "gc".fg(theme.title),
+            " in the navigation pane.".into(),
+        ]
+        .into(),
+        vec![
+            "Jump to your downloads with ".into(),
+            "Ctrl-D".fg(theme.title),
+            " (from the navigation pane, when reading a page).".into(),
         ]
         .into(),
-        vec!["Use ".into(), "Ctrl-C".blue(), " to quit.".into()].into(),
+        vec!["Use ".into(), "Ctrl-C".fg(theme.title), " to quit.".into()].into(),
+        vec![].into(),
+        vec![args::version().fg(theme.inactive)].into(),
     ])
     .wrap(Wrap { trim: false })
 }
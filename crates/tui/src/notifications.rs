@@ -0,0 +1,20 @@
+//! Desktop notifications, if we're built with the `notifications` feature
+
+/// Show a desktop notification.
+/// Failures are only logged, since some platforms and desktops don't support notifications.
+#[cfg(feature = "notifications")]
+pub fn notify(summary: &str, body: &str) {
+    if let Err(e) = notify_rust::Notification::new()
+        .appname("learn-tui")
+        .summary(summary)
+        .body(body)
+        .show()
+    {
+        log::warn!("error showing notification: {}", e);
+    }
+}
+
+/// Show a desktop notification.
+/// This does nothing, since we were built without the `notifications` feature.
+#[cfg(not(feature = "notifications"))]
+pub fn notify(_summary: &str, _body: &str) {}
@@ -0,0 +1,245 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::{Index, Range},
+};
+
+use edlearn_client::content::Content;
+
+use super::{ContentIdx, CourseIdx};
+
+/// Something which can be stored in a [`ContentTree`]
+pub trait ContentIds {
+    fn course_id(&self) -> &str;
+    fn content_id(&self) -> &str;
+}
+
+impl ContentIds for Content {
+    fn course_id(&self) -> &str {
+        &self.course_id
+    }
+
+    fn content_id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// All the content we've loaded, and which folders and courses it's in.
+///
+/// Items are only ever appended, so a [`ContentIdx`] always points to the same item.
+/// When a folder or course is refreshed, the items that were in it are marked as removed rather than deleted.
+/// They can still be looked up by index, but are otherwise skipped.
+#[derive(Debug)]
+pub struct ContentTree<C = Content> {
+    contents: Vec<C>,
+
+    /// The latest item with each `(course_id, content_id)`
+    by_id: HashMap<(String, String), ContentIdx>,
+
+    children: HashMap<ContentIdx, Range<ContentIdx>>,
+    course_contents: HashMap<CourseIdx, Range<ContentIdx>>,
+
+    /// Items which have been replaced by a refresh
+    removed: HashSet<ContentIdx>,
+}
+
+impl<C> Default for ContentTree<C> {
+    fn default() -> Self {
+        Self {
+            contents: vec![],
+            by_id: HashMap::new(),
+            children: HashMap::new(),
+            course_contents: HashMap::new(),
+            removed: HashSet::new(),
+        }
+    }
+}
+
+impl<C: ContentIds> ContentTree<C> {
+    /// Get the top-level content of a course, if it's loaded
+    pub fn course_content(&self, course_idx: CourseIdx) -> Option<Range<ContentIdx>> {
+        self.course_contents.get(&course_idx).cloned()
+    }
+
+    /// Get the children of a folder, if they're loaded
+    pub fn children(&self, content_idx: ContentIdx) -> Option<Range<ContentIdx>> {
+        self.children.get(&content_idx).cloned()
+    }
+
+    /// Iterate over the courses whose content is loaded
+    pub fn loaded_courses(&self) -> impl Iterator<Item = CourseIdx> + '_ {
+        self.course_contents.keys().copied()
+    }
+
+    /// Get the folder the given item is in, or `None` if it's at the top of its course
+    pub fn parent(&self, content_idx: ContentIdx) -> Option<ContentIdx> {
+        self.children
+            .iter()
+            .find(|(_, range)| range.contains(&content_idx))
+            .map(|(&parent, _)| parent)
+    }
+
+    /// Find a loaded item by its IDs
+    pub fn find(&self, course_id: &str, content_id: &str) -> Option<ContentIdx> {
+        self.by_id
+            .get(&(course_id.to_string(), content_id.to_string()))
+            .copied()
+    }
+
+    /// Check if the given item has been replaced by a refresh
+    pub fn is_removed(&self, content_idx: ContentIdx) -> bool {
+        self.removed.contains(&content_idx)
+    }
+
+    /// Iterate over every item which hasn't been removed
+    pub fn iter(&self) -> impl Iterator<Item = (ContentIdx, &C)> {
+        self.contents
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !self.removed.contains(idx))
+    }
+
+    /// Add a course's top-level content, replacing whatever was there before
+    pub fn insert_course_content(
+        &mut self,
+        course_idx: CourseIdx,
+        content: Vec<C>,
+    ) -> Range<ContentIdx> {
+        self.remove_course_content(course_idx);
+        let range = self.push(content);
+        self.course_contents.insert(course_idx, range.clone());
+
+        range
+    }
+
+    /// Add the children of a folder, replacing whatever was there before.
+    /// If the folder itself has been removed, nothing is added and the returned range is empty.
+    pub fn insert_children(&mut self, parent: ContentIdx, children: Vec<C>) -> Range<ContentIdx> {
+        if self.is_removed(parent) {
+            return 0..0;
+        }
+
+        self.remove_children(parent);
+        let range = self.push(children);
+        self.children.insert(parent, range.clone());
+
+        range
+    }
+
+    /// Remove a course's content, so it can be loaded again.
+    /// Returns everything that was removed.
+    pub fn remove_course_content(&mut self, course_idx: CourseIdx) -> Vec<ContentIdx> {
+        let mut removed = vec![];
+        if let Some(range) = self.course_contents.remove(&course_idx) {
+            self.remove_range(range, &mut removed);
+        }
+
+        removed
+    }
+
+    /// Remove the children of a folder, and everything below them, so they can be loaded again.
+    /// Returns everything that was removed.
+    pub fn remove_children(&mut self, parent: ContentIdx) -> Vec<ContentIdx> {
+        let mut removed = vec![];
+        if let Some(range) = self.children.remove(&parent) {
+            self.remove_range(range, &mut removed);
+        }
+
+        removed
+    }
+
+    fn remove_range(&mut self, range: Range<ContentIdx>, removed: &mut Vec<ContentIdx>) {
+        for idx in range {
+            if let Some(children) = self.children.remove(&idx) {
+                self.remove_range(children, removed);
+            }
+
+            let item = &self.contents[idx];
+            let key = (item.course_id().to_string(), item.content_id().to_string());
+            if self.by_id.get(&key) == Some(&idx) {
+                self.by_id.remove(&key);
+            }
+            self.removed.insert(idx);
+            removed.push(idx);
+        }
+    }
+
+    fn push(&mut self, items: Vec<C>) -> Range<ContentIdx> {
+        let start = self.contents.len();
+        for (i, item) in items.iter().enumerate() {
+            self.by_id.insert(
+                (item.course_id().to_string(), item.content_id().to_string()),
+                start + i,
+            );
+        }
+        self.contents.extend(items);
+
+        start..self.contents.len()
+    }
+}
+
+impl<C> Index<ContentIdx> for ContentTree<C> {
+    type Output = C;
+
+    fn index(&self, index: ContentIdx) -> &C {
+        &self.contents[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Item(&'static str);
+    impl ContentIds for Item {
+        fn course_id(&self) -> &str {
+            "_1_1"
+        }
+
+        fn content_id(&self) -> &str {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_refresh_folder() {
+        let mut tree = ContentTree::default();
+        let top = tree.insert_course_content(0, vec![Item("week1"), Item("week2")]);
+        let week1 = tree.insert_children(top.start, vec![Item("slides"), Item("lab")]);
+        let lab = tree.insert_children(week1.start + 1, vec![Item("sheet")]);
+        let week2 = tree.insert_children(top.start + 1, vec![Item("notes")]);
+
+        let removed = tree.remove_children(top.start);
+        assert_eq!(removed.len(), 3);
+        assert!(removed.iter().all(|idx| tree.is_removed(*idx)));
+        assert_eq!(tree.find("_1_1", "sheet"), None);
+        assert_eq!(tree.parent(lab.start), None);
+
+        // other folders are untouched
+        assert_eq!(tree.children(top.start + 1), Some(week2.clone()));
+        assert_eq!(tree.find("_1_1", "notes"), Some(week2.start));
+        assert_eq!(tree.parent(week2.start), Some(top.start + 1));
+        assert_eq!(tree[week2.start].0, "notes");
+
+        let refreshed = tree.insert_children(top.start, vec![Item("slides"), Item("video")]);
+        assert_eq!(tree.find("_1_1", "slides"), Some(refreshed.start));
+        assert_eq!(tree.children(top.start), Some(refreshed));
+
+        // removed items are still there, but not listed
+        assert_eq!(tree[week1.start].0, "slides");
+        let live = tree.iter().map(|(_, c)| c.0).collect::<Vec<_>>();
+        assert_eq!(live, vec!["week1", "week2", "notes", "slides", "video"]);
+    }
+
+    #[test]
+    fn test_children_of_removed_folder_ignored() {
+        let mut tree = ContentTree::default();
+        let old = tree.insert_course_content(0, vec![Item("week1")]);
+        let new = tree.insert_course_content(0, vec![Item("week1")]);
+        assert!(tree.is_removed(old.start));
+        assert_eq!(tree.find("_1_1", "week1"), Some(new.start));
+
+        // a late response for a folder that was just refreshed away
+        assert_eq!(tree.insert_children(old.start, vec![Item("lab")]), 0..0);
+        assert_eq!(tree.find("_1_1", "lab"), None);
+    }
+}
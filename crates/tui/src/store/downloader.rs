@@ -3,12 +3,12 @@ use camino::Utf8PathBuf;
 use edlearn_client::Client;
 use log::debug;
 use std::{
-    fs::File,
+    fs::{self, File, OpenOptions},
     io::Write,
     sync::mpsc::{channel, Receiver, Sender},
 };
 
-use super::{ContentIdx, DownloaderRequest, Event};
+use super::{ContentIdx, DownloaderRequest, Epoch, Event};
 use crate::event::{Event as CrateEvent, EventBus};
 
 #[derive(Debug, Clone)]
@@ -16,6 +16,22 @@ pub struct DownloadReq {
     pub url: String,
     pub orig_filename: String,
     pub dest: Utf8PathBuf,
+
+    /// How many bytes were already downloaded by a previous attempt.
+    /// This is set by the downloader when it finds a partial download to resume.
+    pub offset: u64,
+}
+
+impl DownloadReq {
+    /// Where the file is downloaded to until it's complete
+    pub fn part_path(&self) -> Utf8PathBuf {
+        format!("{}.part", self.dest).into()
+    }
+
+    /// Where we keep the `ETag` or `Last-Modified` of the partial download, so we only resume it if the file hasn't changed
+    fn validator_path(&self) -> Utf8PathBuf {
+        format!("{}.part.validator", self.dest).into()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -26,16 +42,42 @@ pub enum DownloadState {
     Errored(String),
 }
 
+/// Make a name from Learn safe to use as a file or folder name, replacing characters most filesystems don't allow.
+pub(crate) fn sanitise_file_name(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>();
+
+    // windows doesn't allow trailing dots or spaces, and `.` or `..` would escape the folder
+    let name = name.trim_start().trim_end_matches(['.', ' ']);
+    if name.is_empty() {
+        "_".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
 /// Performs requests it receives from the main thread, and sends the results back.
 pub struct Downloader {
     client: Client,
     msg_recv: Receiver<DownloaderRequest>,
     event_send: Sender<CrateEvent>,
+    epoch: Epoch,
 }
 
 impl Downloader {
     /// Spawn the store worker on the given event bus, returning a channel to send commands down.
-    pub(crate) fn spawn_on(bus: &EventBus, client: Client) -> Sender<DownloaderRequest> {
+    /// Events it sends are stamped with `epoch`.
+    pub(crate) fn spawn_on(
+        bus: &EventBus,
+        client: Client,
+        epoch: Epoch,
+    ) -> Sender<DownloaderRequest> {
         let (cmd_send, cmd_recv) = channel();
 
         bus.spawn("downloader", move |_, event_send| {
@@ -44,6 +86,7 @@ impl Downloader {
                 client,
                 msg_recv: cmd_recv,
                 event_send,
+                epoch,
             }
             .main()
         });
@@ -57,16 +100,16 @@ impl Downloader {
             let DownloaderRequest::DoDownload(r, req) = msg;
 
             if let Err(e) = match self.do_download(r, req) {
-                Ok(_) => self.event_send.send(CrateEvent::Store(Event::DownloadState(
-                    r,
-                    DownloadState::Completed,
-                ))),
+                Ok(_) => self.event_send.send(CrateEvent::Store(
+                    self.epoch,
+                    Event::DownloadState(r, DownloadState::Completed),
+                )),
                 Err(e) => {
                     let e = format!("{:#}", e);
-                    self.event_send.send(CrateEvent::Store(Event::DownloadState(
-                        r,
-                        DownloadState::Errored(e),
-                    )))
+                    self.event_send.send(CrateEvent::Store(
+                        self.epoch,
+                        Event::DownloadState(r, DownloadState::Errored(e)),
+                    ))
                 }
             } {
                 debug!("error sending event: {:?}", e);
@@ -77,43 +120,116 @@ impl Downloader {
         debug!("shutting down");
     }
 
-    fn do_download(&self, r: ContentIdx, req: DownloadReq) -> Result<(), anyhow::Error> {
+    fn do_download(&self, r: ContentIdx, mut req: DownloadReq) -> Result<(), anyhow::Error> {
         debug!("downloading {req:?} (ref = {r})");
         self.event_send
-            .send(CrateEvent::Store(Event::DownloadState(
-                r,
-                DownloadState::InProgress(0.0),
-            )))
+            .send(CrateEvent::Store(
+                self.epoch,
+                Event::DownloadState(r, DownloadState::InProgress(0.0)),
+            ))
             .unwrap();
 
-        // make the file
-        let mut f = File::create(req.dest.as_std_path())?;
+        // resume from a previous attempt, if we know which version of the file it was for
+        let part_path = req.part_path();
+        let validator_path = req.validator_path();
+        let validator = fs::read_to_string(&validator_path).ok();
+        req.offset = match validator {
+            Some(_) => fs::metadata(&part_path).map(|m| m.len()).unwrap_or(0),
+            None => 0,
+        };
+
+        if let Some(parent) = req.dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
 
         // start download and find length
-        let mut resp = self.client.http().get(req.url).send()?.error_for_status()?;
+        let mut http_req = self.client.http().get(&req.url);
+        if let (true, Some(validator)) = (req.offset > 0, &validator) {
+            debug!("resuming from byte {}", req.offset);
+            http_req = http_req
+                .header("Range", format!("bytes={}-", req.offset))
+                .header("If-Range", validator);
+        }
+        let mut resp = http_req.send()?;
+
+        // the partial download may be invalid, ie the file got shorter, or the server sent the wrong part, so start again
+        let range_start = resp
+            .headers()
+            .get("Content-Range")
+            .and_then(|v| v.to_str().ok())
+            .and_then(content_range_start);
+        let status = resp.status().as_u16();
+        if status == 416 || (status == 206 && range_start != Some(req.offset)) {
+            debug!("can't resume (status {status}, range start {range_start:?}), restarting");
+            req.offset = 0;
+            resp = self.client.http().get(&req.url).send()?;
+        }
+        let mut resp = resp.error_for_status()?;
+
+        // servers which don't support ranges, or whose file has changed, just send the whole file
+        let mut f = if req.offset > 0 && resp.status().as_u16() == 206 {
+            OpenOptions::new()
+                .append(true)
+                .open(part_path.as_std_path())?
+        } else {
+            req.offset = 0;
+            let header = |name| resp.headers().get(name).and_then(|v| v.to_str().ok());
+            match response_validator(header("ETag"), header("Last-Modified")) {
+                Some(v) => fs::write(&validator_path, v)?,
+                None => {
+                    let _ = fs::remove_file(&validator_path);
+                }
+            }
+            File::create(part_path.as_std_path())?
+        };
 
         // prepare a writer that tracks progress
         let mut writer = ProgressWriter {
             dest: &mut f,
             channel: &self.event_send,
+            epoch: self.epoch,
             r,
-            size: resp
-                .content_length()
-                .ok_or_else(|| anyhow!("no content-length header"))?, // TODO: be more graceful about this
-            downloaded: 0,
+            size: req.offset
+                + resp
+                    .content_length()
+                    .ok_or_else(|| anyhow!("no content-length header"))?, // TODO: be more graceful about this
+            downloaded: req.offset,
             last_sent: 0.0,
         };
 
         // do the download
         resp.copy_to(&mut writer)?;
+        fs::rename(&part_path, &req.dest)?;
+        let _ = fs::remove_file(&validator_path);
 
         Ok(())
     }
 }
 
+/// Get the first byte of a `Content-Range` header, ie `bytes 100-199/200`
+fn content_range_start(value: &str) -> Option<u64> {
+    value
+        .strip_prefix("bytes ")?
+        .split_once('-')?
+        .0
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Pick what to send as `If-Range` when resuming, from a response's `ETag` and `Last-Modified` headers.
+/// Weak ETags can't be used for this, so `Last-Modified` is used instead.
+fn response_validator<'a>(
+    etag: Option<&'a str>,
+    last_modified: Option<&'a str>,
+) -> Option<&'a str> {
+    etag.filter(|e| !e.starts_with("W/")).or(last_modified)
+}
+
 struct ProgressWriter<'a> {
     dest: &'a mut File,
     channel: &'a Sender<CrateEvent>,
+    epoch: Epoch,
     r: ContentIdx,
     downloaded: u64,
     size: u64,
@@ -126,10 +242,10 @@ impl<'a> Write for ProgressWriter<'a> {
         let pct = self.downloaded as f32 / self.size as f32;
         if pct - self.last_sent > 0.01 {
             self.channel
-                .send(CrateEvent::Store(Event::DownloadState(
-                    self.r,
-                    DownloadState::InProgress(pct),
-                )))
+                .send(CrateEvent::Store(
+                    self.epoch,
+                    Event::DownloadState(self.r, DownloadState::InProgress(pct)),
+                ))
                 .unwrap();
             self.last_sent = pct;
         }
@@ -141,3 +257,36 @@ impl<'a> Write for ProgressWriter<'a> {
         self.dest.flush()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitise_file_name() {
+        assert_eq!(sanitise_file_name("Week 1"), "Week 1");
+        assert_eq!(sanitise_file_name("Notes: a/b?"), "Notes_ a_b_");
+        assert_eq!(sanitise_file_name(" Slides... "), "Slides");
+        assert_eq!(sanitise_file_name(".."), "_");
+        assert_eq!(sanitise_file_name(""), "_");
+    }
+
+    #[test]
+    fn test_content_range_start() {
+        assert_eq!(content_range_start("bytes 100-199/200"), Some(100));
+        assert_eq!(content_range_start("bytes 0-99/*"), Some(0));
+        assert_eq!(content_range_start("bytes */200"), None);
+        assert_eq!(content_range_start("items 1-2/3"), None);
+    }
+
+    #[test]
+    fn test_response_validator() {
+        let modified = Some("Wed, 21 Oct 2015 07:28:00 GMT");
+        assert_eq!(
+            response_validator(Some("\"abc\""), modified),
+            Some("\"abc\"")
+        );
+        assert_eq!(response_validator(Some("W/\"abc\""), modified), modified);
+        assert_eq!(response_validator(None, None), None);
+    }
+}
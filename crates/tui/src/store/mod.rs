@@ -1,12 +1,31 @@
+use bbml::ParsedDoc;
 use camino::Utf8PathBuf;
+use chrono::{DateTime, Local};
 use edlearn_client::{
-    content::{Content, ContentPayload},
+    announcements::Announcement,
+    content::{Content, ContentNode, ContentPayload},
     course::Course,
+    gradebook::{AssessmentDetails, Attempt, Grade},
     terms::Term,
     users::User,
-    Client,
+    AuthError, Client,
+};
+use log::debug;
+use ratatui::text::Text;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    ops::Range,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::Sender,
+    },
+    time::Instant,
 };
-use std::{collections::HashMap, ops::Range, sync::mpsc::Sender};
+
+mod contents;
+use contents::ContentTree;
 
 mod downloader;
 pub use downloader::Downloader;
@@ -14,32 +33,186 @@ pub use downloader::Downloader;
 mod worker;
 pub use worker::Worker;
 
-use crate::{event::EventBus, main_screen::Action, styles::error_text};
+mod recent;
+pub use recent::{RecentItem, RecentList};
+
+mod read;
+pub use read::ReadSet;
 
+mod session;
+use session::PendingRestore;
+pub use session::{LastSession, SavedDocument};
+
+use crate::{
+    args::DeepLink,
+    config::{Config, DownloadLayout},
+    event::EventBus,
+    main_screen::{panes::Document, Action},
+    notifications,
+    styles::error_text,
+};
+
+use self::downloader::sanitise_file_name;
 pub use self::downloader::{DownloadReq, DownloadState};
 
 pub type TermIdx = usize;
 pub type CourseIdx = usize;
 pub type ContentIdx = usize;
 
+/// Identifies a [`Store`], so its workers' events aren't mistaken for another's.
+/// After logging in again, the new store ignores anything that was still in flight for the old one.
+/// Refreshing part of the tree doesn't need a new epoch, since [`ContentTree`] handles late responses itself.
+pub type Epoch = usize;
+
+/// The epoch to give the next store
+static NEXT_EPOCH: AtomicUsize = AtomicUsize::new(0);
+
+/// How many levels of folders are loaded along with a course when it's refreshed.
+/// This gets the folders the user is likely to open in one go, rather than waiting on each as it's expanded.
+const REFRESH_DEPTH: usize = 1;
+
+/// A page rendered by [`bbml`], at a particular width
+#[derive(Debug, Clone)]
+pub struct PageRender {
+    pub width: u16,
+    pub text: Text<'static>,
+    pub links: Vec<String>,
+}
+
+/// Something found by [`Store::search_content`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchResult {
+    Course(CourseIdx),
+    Content(ContentIdx),
+}
+
 /// Global data store
 pub struct Store {
+    config: Rc<Config>,
+
+    /// Events from workers with a different epoch are ignored
+    epoch: Epoch,
+
     me: Option<User>,
 
     courses_by_term: Vec<(String, Vec<CourseIdx>)>,
     courses: Vec<Course>,
-    contents: Vec<Content>,
-    content_children: HashMap<ContentIdx, Range<ContentIdx>>,
-    course_contents: HashMap<CourseIdx, Range<ContentIdx>>,
+    contents: ContentTree,
+
+    /// Courses whose content has been requested, but hasn't arrived yet.
+    /// Prefetching skips these, so we don't request the same thing twice.
+    course_content_pending: RefCell<HashSet<CourseIdx>>,
 
     page_texts: HashMap<ContentIdx, String>,
 
+    /// Pages whose text has been requested, so we don't request them twice.
+    page_text_pending: RefCell<HashSet<ContentIdx>>,
+
+    /// Pages the user is waiting on, and when they opened them.
+    /// This is only used to log how long they waited, to see how much prefetching helps.
+    page_text_waiting: RefCell<HashMap<ContentIdx, Instant>>,
+
+    /// Our attempts at assessments, or `None` if we couldn't find out
+    attempts: HashMap<ContentIdx, Option<Vec<Attempt>>>,
+
+    /// Details of assessments, or `None` if we couldn't get them
+    assessment_details: HashMap<ContentIdx, Option<AssessmentDetails>>,
+
+    /// Assessments whose attempts or details have been requested, so we don't request them again on every redraw.
+    attempts_pending: RefCell<HashSet<ContentIdx>>,
+    assessment_details_pending: RefCell<HashSet<ContentIdx>>,
+
+    /// Announcements and grades for each course, or `None` if we couldn't get them
+    announcements: HashMap<CourseIdx, Option<Vec<Announcement>>>,
+    grades: HashMap<CourseIdx, Option<Vec<Grade>>>,
+
+    /// Courses whose announcements or grades have been requested, so we don't request them again on every redraw.
+    announcements_pending: RefCell<HashSet<CourseIdx>>,
+    grades_pending: RefCell<HashSet<CourseIdx>>,
+
+    /// Pages we've already rendered, so showing them again is quick.
+    /// This is only a cache, so it's fine to fill while drawing.
+    page_renders: RefCell<HashMap<ContentIdx, PageRender>>,
+
+    /// Pages we've already parsed, so they can be re-rendered at a new width without parsing them again.
+    page_docs: RefCell<HashMap<ContentIdx, Rc<ParsedDoc>>>,
+
     download_queue: HashMap<ContentIdx, (DownloadReq, DownloadState)>,
 
+    /// Downloads to open as soon as they finish
+    open_when_downloaded: HashSet<ContentIdx>,
+
+    recent: RecentList,
+    read: ReadSet,
+
+    session: LastSession,
+    restore: Option<PendingRestore>,
+
+    /// When the last session started. Content modified after this is marked as new.
+    new_since: Option<DateTime<Local>>,
+
+    /// When each course was last opened before this session, by course ID.
+    /// This takes precedence over `new_since`, so content is new until the user opens the course.
+    course_visits: HashMap<String, DateTime<Local>>,
+
+    /// Whether we've told the user which courses have new content yet
+    new_summary_shown: bool,
+
+    /// How much of the loaded content in each course is new, by course ID
+    new_counts: HashMap<String, usize>,
+
+    /// The version Learn reported, once we've checked
+    learn_version: Option<String>,
+
+    /// The service we're contacting while logging in for the first time, until we know who the user is
+    login_progress: Option<&'static str>,
+
+    deep_link: Option<DeepLink>,
+    deep_link_course: Option<CourseIdx>,
+
+    /// A course the user just opened, whose overview should be shown once its content loads
+    pending_overview: Option<CourseIdx>,
+
+    /// Show content even if its kind is in [`Config::hidden_content`]
+    show_hidden_content: bool,
+
+    /// If set, no requests are made and the worker responds with [`Event::Unavailable`].
+    /// There's no cache of Learn's content to fall back on, and nothing is saved when we exit.
+    offline: bool,
+
     worker_channel: Sender<Request>,
+    prefetch_channel: Sender<Request>,
     downloader_channel: Sender<DownloaderRequest>,
 }
 
+/// Everything the store keeps between sessions
+#[derive(Default)]
+struct SavedState {
+    session: LastSession,
+    recent: RecentList,
+    read: ReadSet,
+}
+
+impl SavedState {
+    /// Load whatever was saved last time, starting afresh if it can't be loaded
+    fn load() -> Self {
+        Self {
+            session: LastSession::load().unwrap_or_else(|e| {
+                debug!("error loading last session: {:?}", e);
+                Default::default()
+            }),
+            recent: RecentList::load().unwrap_or_else(|e| {
+                debug!("error loading recent list: {:?}", e);
+                Default::default()
+            }),
+            read: ReadSet::load().unwrap_or_else(|e| {
+                debug!("error loading read list: {:?}", e);
+                Default::default()
+            }),
+        }
+    }
+}
+
 /// Requests sent to the worker thread
 #[derive(Debug)]
 pub(crate) enum Request {
@@ -48,15 +221,48 @@ pub(crate) enum Request {
         course_idx: CourseIdx,
         course_id: String,
     },
+    /// Get a course's content along with its folders' children, down to `max_depth` levels
+    CourseTree {
+        course_idx: CourseIdx,
+        course_id: String,
+        max_depth: usize,
+    },
     ContentChildren {
         content_idx: ContentIdx,
         course_id: String,
         content_id: String,
     },
+    /// Get a page's text. If `prefetch` is set, failures are reported with [`Event::PrefetchFailed`].
     PageText {
         content_idx: ContentIdx,
         course_id: String,
         content_id: String,
+        prefetch: bool,
+    },
+    Attempts {
+        content_idx: ContentIdx,
+        course_id: String,
+        column_id: String,
+    },
+    AssessmentDetails {
+        content_idx: ContentIdx,
+        course_id: String,
+        column_id: String,
+    },
+    Announcements {
+        course_idx: CourseIdx,
+        course_id: String,
+    },
+    Grades {
+        course_idx: CourseIdx,
+        course_id: String,
+        user_id: String,
+    },
+    HealthCheck,
+    CheckVersion,
+    FindContent {
+        course_id: String,
+        content_id: String,
     },
 }
 
@@ -69,16 +275,22 @@ pub(crate) enum DownloaderRequest {
 #[derive(Debug)]
 pub enum Event {
     Error(edlearn_client::Error),
+    /// The current user and their courses.
+    /// Terms and favourites are `None` if they couldn't be fetched, in which case we make do without them.
     Me {
         me: User,
         courses: Vec<Course>,
-        terms: Vec<Term>,
-        favourite_ids: Vec<String>,
+        terms: Option<Vec<Term>>,
+        favourite_ids: Option<Vec<String>>,
     },
     CourseContent {
         course_idx: CourseIdx,
         content: Vec<Content>,
     },
+    CourseTree {
+        course_idx: CourseIdx,
+        tree: Vec<ContentNode>,
+    },
     ContentChildren {
         content_idx: ContentIdx,
         children: Vec<Content>,
@@ -87,50 +299,565 @@ pub enum Event {
         content_idx: ContentIdx,
         text: String,
     },
+
+    /// Prefetching a page's text failed.
+    /// The user hasn't asked for it yet, so this isn't shown - they'll see the error if they open it.
+    PrefetchFailed {
+        content_idx: ContentIdx,
+    },
+    Attempts {
+        content_idx: ContentIdx,
+        attempts: Option<Vec<Attempt>>,
+    },
+    AssessmentDetails {
+        content_idx: ContentIdx,
+        details: Option<AssessmentDetails>,
+    },
+    Announcements {
+        course_idx: CourseIdx,
+        announcements: Option<Vec<Announcement>>,
+    },
+    Grades {
+        course_idx: CourseIdx,
+        grades: Option<Vec<Grade>>,
+    },
     DownloadState(ContentIdx, DownloadState),
+
+    /// Result of a periodic health check.
+    /// Authentication errors are reported with [`Event::Error`] instead.
+    SessionChecked {
+        ok: bool,
+    },
+
+    /// Result of checking Learn's version on startup.
+    /// `version` is `None` if it couldn't be checked.
+    VersionChecked {
+        version: Option<String>,
+        tested: bool,
+    },
+
+    /// A request couldn't be made because we're offline
+    Unavailable,
+
+    /// Result of searching a course for a content item.
+    /// `path` is the content IDs from the top of the course down to it, if it was found.
+    ContentFound {
+        course_id: String,
+        content_id: String,
+        path: Option<Vec<String>>,
+    },
+
+    /// A worker is logging in, and is about to contact the given service
+    LoggingIn {
+        service: &'static str,
+    },
 }
 
 impl Store {
-    pub fn new(bus: &EventBus, client: Client) -> Self {
-        let worker_channel = Worker::spawn_on(bus, client.clone_sharing_state());
-        let downloader_channel = Downloader::spawn_on(bus, client);
+    /// Create a store, starting workers on the given bus.
+    /// If `remember` is false, nothing saved from previous sessions is loaded.
+    pub fn new(
+        bus: &EventBus,
+        client: Client,
+        config: Rc<Config>,
+        offline: bool,
+        remember: bool,
+    ) -> Self {
+        let epoch = NEXT_EPOCH.fetch_add(1, Ordering::Relaxed);
+        let worker_channel = Worker::spawn_on(bus, client.clone_sharing_state(), offline, epoch);
+        if let (Some(interval), false) = (config.health_check_interval(), offline) {
+            Worker::spawn_health_check_on(bus, worker_channel.clone(), interval);
+        }
+        // Nothing is prefetched or downloaded offline, so those threads aren't needed
+        let (prefetch_channel, downloader_channel) = if offline {
+            (worker_channel.clone(), std::sync::mpsc::channel().0)
+        } else {
+            (
+                Worker::spawn_on(bus, client.clone_sharing_state(), offline, epoch),
+                Downloader::spawn_on(bus, client, epoch),
+            )
+        };
+
+        Self::with_channels(
+            config,
+            epoch,
+            offline,
+            worker_channel,
+            prefetch_channel,
+            downloader_channel,
+            if remember {
+                SavedState::load()
+            } else {
+                SavedState::default()
+            },
+        )
+    }
+
+    /// Create a store which sends its requests to the given channels, starting from the given saved state
+    fn with_channels(
+        config: Rc<Config>,
+        epoch: Epoch,
+        offline: bool,
+        worker_channel: Sender<Request>,
+        prefetch_channel: Sender<Request>,
+        downloader_channel: Sender<DownloaderRequest>,
+        saved: SavedState,
+    ) -> Self {
+        let SavedState {
+            mut session,
+            recent,
+            read,
+        } = saved;
+        let new_since = session.started.replace(Local::now());
+        let course_visits = session.course_visits.clone();
 
         Self {
+            config,
+            epoch,
             worker_channel,
+            prefetch_channel,
             downloader_channel,
             me: Default::default(),
             courses_by_term: Default::default(),
             courses: Default::default(),
-            course_contents: Default::default(),
-            content_children: Default::default(),
+            course_content_pending: Default::default(),
             contents: Default::default(),
             page_texts: Default::default(),
+            page_text_pending: Default::default(),
+            attempts_pending: Default::default(),
+            assessment_details_pending: Default::default(),
+            announcements_pending: Default::default(),
+            grades_pending: Default::default(),
+            page_text_waiting: Default::default(),
+            attempts: Default::default(),
+            assessment_details: Default::default(),
+            announcements: Default::default(),
+            grades: Default::default(),
+            page_renders: Default::default(),
+            page_docs: Default::default(),
             download_queue: Default::default(),
+            open_when_downloaded: Default::default(),
+            recent,
+            read,
+            session,
+            restore: None,
+            new_since,
+            course_visits,
+            new_summary_shown: false,
+            new_counts: Default::default(),
+            learn_version: None,
+            login_progress: None,
+            deep_link: None,
+            deep_link_course: None,
+            pending_overview: None,
+            show_hidden_content: false,
+            offline,
+        }
+    }
+
+    /// Save any state which should persist between sessions
+    pub fn save(&self) -> anyhow::Result<()> {
+        self.recent.save()?;
+        self.read.save()?;
+        self.session.save()
+    }
+
+    /// Check if the user has collapsed the given term, hiding its courses
+    pub fn is_term_collapsed(&self, term_idx: TermIdx) -> bool {
+        let name = &self.courses_by_term[term_idx].0;
+        self.session.collapsed_terms.contains(name)
+    }
+
+    /// Collapse or expand the given term. This is remembered between sessions.
+    pub fn set_term_collapsed(&mut self, term_idx: TermIdx, collapsed: bool) {
+        let name = &self.courses_by_term[term_idx].0;
+        self.session.collapsed_terms.retain(|t| t != name);
+        if collapsed {
+            self.session.collapsed_terms.push(name.clone());
+        }
+    }
+
+    /// Get the term a course is listed under
+    fn course_term(&self, course_idx: CourseIdx) -> Option<TermIdx> {
+        self.courses_by_term
+            .iter()
+            .position(|(_, courses)| courses.contains(&course_idx))
+    }
+
+    /// Record the document being shown, so it can be restored next session
+    pub fn set_last_document(&mut self, doc: &Document) {
+        self.session.document = match doc {
+            Document::Welcome => SavedDocument::Welcome,
+            Document::Downloads => SavedDocument::Downloads,
+            Document::Recent => SavedDocument::Recent,
+            Document::Announcements(idx) => SavedDocument::Announcements {
+                course_id: self.courses[*idx].id.clone(),
+            },
+            Document::Grades(idx) => SavedDocument::Grades {
+                course_id: self.courses[*idx].id.clone(),
+            },
+            Document::Content(idx) => {
+                let (course_id, path) = self.content_id_path(*idx);
+                SavedDocument::Content { course_id, path }
+            }
+        };
+    }
+
+    /// Open the given course or content once our courses have loaded, instead of restoring the last session.
+    pub fn set_deep_link(&mut self, link: DeepLink) {
+        self.deep_link = Some(link);
+    }
+
+    /// The course we were asked to open on launch, if it exists
+    pub fn deep_link_course(&self) -> Option<CourseIdx> {
+        self.deep_link_course
+    }
+
+    /// Don't restore the last session's document once our courses load, ie because the user configured something else to show.
+    pub fn skip_restore(&mut self) {
+        self.session.document = SavedDocument::default();
+    }
+
+    /// Stop trying to restore the last session's document or show a course's overview, ie because the user has opened something else.
+    pub fn cancel_restore(&mut self) {
+        self.restore = None;
+        self.pending_overview = None;
+    }
+
+    /// Get the folders containing the given content item, from the top of the course down.
+    fn content_ancestors(&self, content_idx: ContentIdx) -> Vec<ContentIdx> {
+        let mut ancestors = vec![];
+        let mut curr = content_idx;
+        while let Some(parent) = self.contents.parent(curr) {
+            ancestors.push(parent);
+            curr = parent;
+        }
+        ancestors.reverse();
+
+        ancestors
+    }
+
+    /// Get the course ID, and the content IDs leading from the top of the course to the given content item.
+    fn content_id_path(&self, content_idx: ContentIdx) -> (String, Vec<String>) {
+        let path = self
+            .content_ancestors(content_idx)
+            .into_iter()
+            .chain([content_idx])
+            .map(|idx| self.contents[idx].id.clone())
+            .collect();
+
+        (self.contents[content_idx].course_id.clone(), path)
+    }
+
+    /// Get a relative path mirroring where the given content item is on Learn, ie `<course>/<folder>/<folder>`.
+    /// The item itself isn't included, and names are made safe for the filesystem.
+    pub fn content_path(&self, content_idx: ContentIdx) -> Utf8PathBuf {
+        let course_id = &self.contents[content_idx].course_id;
+        let course = self.courses.iter().find(|c| &c.id == course_id);
+
+        course
+            .map(|c| sanitise_file_name(&c.name))
+            .into_iter()
+            .chain(
+                self.content_ancestors(content_idx)
+                    .into_iter()
+                    .map(|idx| sanitise_file_name(&self.contents[idx].title)),
+            )
+            .collect()
+    }
+
+    /// Start restoring the last session's document, now that we know our courses
+    fn start_restore(&mut self) -> Action {
+        if let Some(link) = self.deep_link.take() {
+            return self.start_deep_link(link);
+        }
+
+        match self.session.document.clone() {
+            SavedDocument::Welcome => Action::None,
+            SavedDocument::Downloads => Action::Show(Document::Downloads),
+            SavedDocument::Recent => Action::Show(Document::Recent),
+            SavedDocument::Announcements { course_id } => {
+                match self.courses.iter().position(|c| c.id == course_id) {
+                    Some(idx) => Action::Show(Document::Announcements(idx)),
+                    None => Action::None,
+                }
+            }
+            SavedDocument::Grades { course_id } => {
+                match self.courses.iter().position(|c| c.id == course_id) {
+                    Some(idx) => Action::Show(Document::Grades(idx)),
+                    None => Action::None,
+                }
+            }
+            SavedDocument::Content { course_id, path } => {
+                let Some(course_idx) = self.courses.iter().position(|c| c.id == course_id) else {
+                    return Action::None;
+                };
+                if path.is_empty() {
+                    return Action::None;
+                }
+
+                self.restore = Some(PendingRestore {
+                    course_idx,
+                    waiting_on: None,
+                    path,
+                });
+                self.request_course_content(course_idx);
+
+                Action::None
+            }
+        }
+    }
+
+    /// Start opening the course or content we were asked to on launch
+    fn start_deep_link(&mut self, link: DeepLink) -> Action {
+        let Some(course_idx) = self.courses.iter().position(|c| c.id == link.course_id) else {
+            return Action::Flash(error_text(
+                self.config.theme(),
+                format!("Couldn't find course {}", link.course_id),
+            ));
+        };
+        self.deep_link_course = Some(course_idx);
+        if let Some(term_idx) = self.course_term(course_idx) {
+            self.set_term_collapsed(term_idx, false);
+        }
+
+        if let Some(content_id) = link.content_id {
+            self.worker_channel
+                .send(Request::FindContent {
+                    course_id: link.course_id,
+                    content_id,
+                })
+                .unwrap();
+        }
+
+        Action::None
+    }
+
+    /// Open a content item we have a link to, loading the folders leading to it first if needed.
+    /// Returns `None` if it isn't in one of the user's courses, so the link should go to the browser instead.
+    pub fn open_linked_content(&mut self, course_id: &str, content_id: &str) -> Option<Action> {
+        if let Some(idx) = self.find_content(course_id, content_id) {
+            return Some(Action::Show(Document::Content(idx)));
+        }
+        self.courses.iter().position(|c| c.id == course_id)?;
+
+        self.worker_channel
+            .send(Request::FindContent {
+                course_id: course_id.to_string(),
+                content_id: content_id.to_string(),
+            })
+            .unwrap();
+
+        Some(Action::Flash("Finding linked content...".into()))
+    }
+
+    /// Skip over the steps of a pending restore that are already loaded, and request the first one that isn't.
+    fn resume_restore(&mut self) -> Action {
+        let Some(restore) = self.restore.as_mut() else {
+            return Action::None;
+        };
+        let course_id = &self.courses[restore.course_idx].id;
+        while let Some(found) = restore
+            .path
+            .first()
+            .and_then(|id| self.contents.find(course_id, id))
+        {
+            restore.path.remove(0);
+            if restore.path.is_empty() {
+                self.restore = None;
+                return Action::Show(Document::Content(found));
+            }
+            restore.waiting_on = Some(found);
+        }
+
+        let course_idx = restore.course_idx;
+        match restore.waiting_on {
+            Some(content_idx) => self.request_content_children(content_idx),
+            None if self.contents.course_content(course_idx).is_some() => {
+                self.restore = None;
+                return Action::Flash(error_text(
+                    self.config.theme(),
+                    "The linked item couldn't be found.",
+                ));
+            }
+            None => self.request_course_content(course_idx),
+        }
+
+        Action::None
+    }
+
+    /// Continue restoring the last session's document, if the given content just loaded is the next step.
+    fn continue_restore(
+        &mut self,
+        course_idx: CourseIdx,
+        parent: Option<ContentIdx>,
+        loaded: Range<ContentIdx>,
+    ) -> Action {
+        let Some(restore) = self.restore.as_mut() else {
+            return Action::None;
+        };
+        if restore.course_idx != course_idx || restore.waiting_on != parent {
+            return Action::None;
+        }
+
+        let next_id = restore.path.remove(0);
+        let Some(found) = loaded.into_iter().find(|i| self.contents[*i].id == next_id) else {
+            self.restore = None;
+            return Action::Flash(error_text(
+                self.config.theme(),
+                "The item you were last viewing couldn't be found.",
+            ));
+        };
+
+        if restore.path.is_empty() {
+            self.restore = None;
+            return Action::Show(Document::Content(found));
         }
+
+        restore.waiting_on = Some(found);
+        self.request_content_children(found);
+
+        Action::None
+    }
+
+    /// Check if we're running without making any requests
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Get the user's configuration
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Check if the given content should be left out of the navigation tree
+    pub fn is_content_hidden(&self, content_idx: ContentIdx) -> bool {
+        !self.show_hidden_content && self.config.is_hidden(&self.content(content_idx).payload)
+    }
+
+    /// Check if hidden content is being shown anyway
+    pub fn show_hidden_content(&self) -> bool {
+        self.show_hidden_content
+    }
+
+    /// Set whether to show content even if its kind is hidden
+    pub fn set_show_hidden_content(&mut self, show: bool) {
+        self.show_hidden_content = show;
+    }
+
+    /// Get the service we're contacting, if we're logging in before knowing who the user is
+    pub fn login_progress(&self) -> Option<&'static str> {
+        self.login_progress
+    }
+
+    /// Get the version Learn reported, if we know it
+    pub fn learn_version(&self) -> Option<&str> {
+        self.learn_version.as_deref()
+    }
+
+    /// Remove anything identifying the user from the given text, so it can be shared in a bug report
+    pub fn scrub_personal(&self, text: &str) -> String {
+        let Some(me) = &self.me else {
+            return text.to_string();
+        };
+
+        let mut secrets = [
+            Some(me.display_name()),
+            Some(me.user_name.clone()),
+            Some(me.id.clone()),
+            me.uuid.clone(),
+            me.student_id.clone(),
+            Some(me.given_name.clone()),
+            me.family_name.clone(),
+            me.email_address.clone(),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|s| !s.trim().is_empty())
+        .collect::<Vec<_>>();
+
+        // longest first, so the full name goes before its parts
+        secrets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+        secrets.into_iter().fold(text.to_string(), |text, secret| {
+            text.replace(&secret, "[redacted]")
+        })
     }
 
     pub fn my_courses(&self) -> Option<&[Course]> {
-        self.me.as_ref()?;
+        if !self.offline {
+            self.me.as_ref()?;
+        }
 
         Some(&self.courses)
     }
 
     pub fn courses_by_term(&self) -> Option<&[(String, Vec<CourseIdx>)]> {
-        self.me.as_ref()?;
+        if !self.offline {
+            self.me.as_ref()?;
+        }
 
         Some(&self.courses_by_term)
     }
 
     pub fn request_my_courses(&self) {
+        if self.offline {
+            return;
+        }
+
         self.worker_channel.send(Request::Me).unwrap()
     }
 
     pub fn course_content(&self, course_idx: CourseIdx) -> Option<Range<ContentIdx>> {
-        self.course_contents.get(&course_idx).cloned()
+        self.contents.course_content(course_idx)
+    }
+
+    /// Get a course's overview page, if its content is loaded and it has one
+    pub fn course_overview(&self, course_idx: CourseIdx) -> Option<ContentIdx> {
+        self.course_content(course_idx)?
+            .find(|idx| self.contents[*idx].is_overview())
+    }
+
+    /// Show the given course's overview page once its content has loaded, if it has one
+    pub fn show_overview_when_loaded(&mut self, course_idx: CourseIdx) {
+        self.pending_overview = Some(course_idx);
     }
 
+    /// Request a course's top-level content, unless it's already loaded.
     pub fn request_course_content(&self, course_idx: CourseIdx) {
-        self.worker_channel
+        if self.contents.course_content(course_idx).is_some() {
+            return;
+        }
+
+        self.course_content_pending.borrow_mut().insert(course_idx);
+        self.send_course_content_request(&self.worker_channel, course_idx);
+    }
+
+    /// Load the content of the courses the user is most likely to open, ie their favourites or the most recent term.
+    /// This uses a separate worker, so it doesn't hold up anything the user actually asked for.
+    /// Collapsed terms are skipped, since the user probably isn't interested in them.
+    fn prefetch_course_content(&self) {
+        let Some((_, courses)) = self
+            .courses_by_term
+            .iter()
+            .enumerate()
+            .find(|(term_idx, _)| !self.is_term_collapsed(*term_idx))
+            .map(|(_, t)| t)
+        else {
+            return;
+        };
+        for course_idx in courses {
+            // If this fails, the pending flag is left set, but the user can still request it normally.
+            if self.contents.course_content(*course_idx).is_some()
+                || !self.course_content_pending.borrow_mut().insert(*course_idx)
+            {
+                continue;
+            }
+            self.send_course_content_request(&self.prefetch_channel, *course_idx);
+        }
+    }
+
+    fn send_course_content_request(&self, channel: &Sender<Request>, course_idx: CourseIdx) {
+        channel
             .send(Request::CourseContent {
                 course_idx,
                 course_id: self.my_courses().unwrap()[course_idx].id.clone(),
@@ -143,7 +870,91 @@ impl Store {
             return Some(0..0);
         }
 
-        self.content_children.get(&content_idx).cloned()
+        self.contents.children(content_idx)
+    }
+
+    /// Load a course's content again, forgetting what we had before.
+    /// Anything still holding an index into the old content can keep using it.
+    pub fn refresh_course_content(&mut self, course_idx: CourseIdx) {
+        if self.offline {
+            return;
+        }
+
+        let removed = self.contents.remove_course_content(course_idx);
+        self.forget_content(removed);
+
+        // so they're fetched again next time they're opened
+        self.announcements.remove(&course_idx);
+        self.announcements_pending.borrow_mut().remove(&course_idx);
+        self.grades.remove(&course_idx);
+        self.grades_pending.borrow_mut().remove(&course_idx);
+
+        self.course_content_pending.borrow_mut().insert(course_idx);
+        self.worker_channel
+            .send(Request::CourseTree {
+                course_idx,
+                course_id: self.courses[course_idx].id.clone(),
+                max_depth: REFRESH_DEPTH,
+            })
+            .unwrap();
+    }
+
+    /// Insert a course's content from [`Client::course_tree`], along with the children of every folder that came with it
+    fn insert_course_tree(&mut self, course_idx: CourseIdx, tree: Vec<ContentNode>) {
+        let (content, children): (Vec<_>, Vec<_>) =
+            tree.into_iter().map(|n| (n.content, n.children)).unzip();
+        let range = self.contents.insert_course_content(course_idx, content);
+        self.count_new_content(range.clone());
+        self.insert_subtrees(range, children);
+    }
+
+    fn insert_subtrees(
+        &mut self,
+        parents: Range<ContentIdx>,
+        children: Vec<Option<Vec<ContentNode>>>,
+    ) {
+        for (parent, nodes) in parents.zip(children) {
+            let Some(nodes) = nodes else {
+                continue;
+            };
+            let (content, children): (Vec<_>, Vec<_>) =
+                nodes.into_iter().map(|n| (n.content, n.children)).unzip();
+            let range = self.contents.insert_children(parent, content);
+            self.count_new_content(range.clone());
+            self.insert_subtrees(range, children);
+        }
+    }
+
+    /// Load a folder's children again, forgetting what we had before.
+    /// Anything still holding an index into the old children can keep using it.
+    pub fn refresh_content_children(&mut self, content_idx: ContentIdx) {
+        if self.offline {
+            return;
+        }
+
+        let removed = self.contents.remove_children(content_idx);
+        self.forget_content(removed);
+        self.request_content_children(content_idx);
+    }
+
+    /// Drop anything we loaded for content that's been removed by a refresh
+    fn forget_content(&mut self, removed: Vec<ContentIdx>) {
+        for idx in removed {
+            if self.is_new(idx) {
+                if let Some(count) = self.new_counts.get_mut(&self.contents[idx].course_id) {
+                    *count = count.saturating_sub(1);
+                }
+            }
+            self.page_texts.remove(&idx);
+            self.page_text_pending.borrow_mut().remove(&idx);
+            self.page_text_waiting.borrow_mut().remove(&idx);
+            self.page_renders.borrow_mut().remove(&idx);
+            self.page_docs.borrow_mut().remove(&idx);
+            self.attempts.remove(&idx);
+            self.attempts_pending.borrow_mut().remove(&idx);
+            self.assessment_details.remove(&idx);
+            self.assessment_details_pending.borrow_mut().remove(&idx);
+        }
     }
 
     pub fn request_content_children(&self, content_idx: ContentIdx) {
@@ -169,29 +980,319 @@ impl Store {
         self.page_texts.get(&content_idx).map(|v| v.as_str())
     }
 
+    /// Get the rendered text and links of a page, if it was already rendered at the given width
+    pub fn page_render(&self, content_idx: ContentIdx, width: u16) -> Option<PageRender> {
+        self.page_renders
+            .borrow()
+            .get(&content_idx)
+            .filter(|r| r.width == width)
+            .cloned()
+    }
+
+    /// Save the rendered text and links of a page, for [`Self::page_render`]
+    pub fn set_page_render(&self, content_idx: ContentIdx, render: PageRender) {
+        self.page_renders.borrow_mut().insert(content_idx, render);
+    }
+
+    /// Get the parsed text of a page, if it's loaded. Pages are only parsed once.
+    pub fn page_doc(&self, content_idx: ContentIdx) -> Option<Rc<ParsedDoc>> {
+        if let Some(doc) = self.page_docs.borrow().get(&content_idx) {
+            return Some(doc.clone());
+        }
+
+        let doc = Rc::new(bbml::parse(self.page_text(content_idx)?));
+        self.page_docs.borrow_mut().insert(content_idx, doc.clone());
+
+        Some(doc)
+    }
+
+    /// Request a page's text, unless it's already been requested
     pub fn request_page_text(&self, content_idx: ContentIdx) {
         let content = self.content(content_idx);
         if !matches!(content.payload, ContentPayload::Page) {
             return;
         }
 
+        self.page_text_waiting
+            .borrow_mut()
+            .entry(content_idx)
+            .or_insert_with(Instant::now);
+        if !self.page_text_pending.borrow_mut().insert(content_idx) {
+            return;
+        }
+
         self.worker_channel
             .send(Request::PageText {
                 content_idx,
                 course_id: content.course_id.clone(),
                 content_id: content.id.clone(),
+                prefetch: false,
+            })
+            .unwrap();
+    }
+
+    /// Load the text of the first few pages in the given range, since the user will likely open some of them.
+    /// Like [`Self::prefetch_course_content`], this uses a separate worker so it doesn't hold up anything else.
+    fn prefetch_page_texts(&self, range: Range<ContentIdx>) {
+        if self.offline {
+            return;
+        }
+
+        let pages = range
+            .filter(|idx| matches!(self.contents[*idx].payload, ContentPayload::Page))
+            .filter(|idx| !self.page_texts.contains_key(idx))
+            .take(self.config.prefetch_pages);
+        for content_idx in pages {
+            if !self.page_text_pending.borrow_mut().insert(content_idx) {
+                continue;
+            }
+
+            let content = &self.contents[content_idx];
+            self.prefetch_channel
+                .send(Request::PageText {
+                    content_idx,
+                    course_id: content.course_id.clone(),
+                    content_id: content.id.clone(),
+                    prefetch: true,
+                })
+                .unwrap();
+        }
+    }
+
+    /// Get our attempts at an assessment, if they're loaded.
+    /// The inner value is `None` if they couldn't be fetched.
+    pub fn attempts(&self, content_idx: ContentIdx) -> Option<Option<&[Attempt]>> {
+        self.attempts.get(&content_idx).map(Option::as_deref)
+    }
+
+    /// Request our attempts at an assessment, unless they've already been requested
+    pub fn request_attempts(&self, content_idx: ContentIdx) {
+        let content = self.content(content_idx);
+        let ContentPayload::Assessment {
+            column_id: Some(column_id),
+            ..
+        } = &content.payload
+        else {
+            return;
+        };
+        if !self.attempts_pending.borrow_mut().insert(content_idx) {
+            return;
+        }
+
+        self.worker_channel
+            .send(Request::Attempts {
+                content_idx,
+                course_id: content.course_id.clone(),
+                column_id: column_id.clone(),
+            })
+            .unwrap();
+    }
+
+    /// Get the details of an assessment, if they're loaded.
+    /// The inner value is `None` if they couldn't be fetched.
+    pub fn assessment_details(
+        &self,
+        content_idx: ContentIdx,
+    ) -> Option<Option<&AssessmentDetails>> {
+        self.assessment_details
+            .get(&content_idx)
+            .map(Option::as_ref)
+    }
+
+    /// Request the details of an assessment, ie its description and how many attempts are allowed, unless they've already been requested
+    pub fn request_assessment_details(&self, content_idx: ContentIdx) {
+        let content = self.content(content_idx);
+        let ContentPayload::Assessment {
+            column_id: Some(column_id),
+            ..
+        } = &content.payload
+        else {
+            return;
+        };
+        if !self
+            .assessment_details_pending
+            .borrow_mut()
+            .insert(content_idx)
+        {
+            return;
+        }
+
+        self.worker_channel
+            .send(Request::AssessmentDetails {
+                content_idx,
+                course_id: content.course_id.clone(),
+                column_id: column_id.clone(),
+            })
+            .unwrap();
+    }
+
+    /// Get a course's announcements, if they're loaded.
+    /// The inner value is `None` if they couldn't be fetched.
+    pub fn announcements(&self, course_idx: CourseIdx) -> Option<Option<&[Announcement]>> {
+        self.announcements.get(&course_idx).map(Option::as_deref)
+    }
+
+    /// Request a course's announcements, unless they've already been requested
+    pub fn request_announcements(&self, course_idx: CourseIdx) {
+        if !self.announcements_pending.borrow_mut().insert(course_idx) {
+            return;
+        }
+
+        self.worker_channel
+            .send(Request::Announcements {
+                course_idx,
+                course_id: self.course(course_idx).id.clone(),
+            })
+            .unwrap();
+    }
+
+    /// Get our grades in a course, if they're loaded.
+    /// The inner value is `None` if they couldn't be fetched.
+    pub fn grades(&self, course_idx: CourseIdx) -> Option<Option<&[Grade]>> {
+        self.grades.get(&course_idx).map(Option::as_deref)
+    }
+
+    /// Request our grades in a course, unless they've already been requested
+    pub fn request_grades(&self, course_idx: CourseIdx) {
+        let Some(me) = &self.me else {
+            return;
+        };
+        if !self.grades_pending.borrow_mut().insert(course_idx) {
+            return;
+        }
+
+        self.worker_channel
+            .send(Request::Grades {
+                course_idx,
+                course_id: self.course(course_idx).id.clone(),
+                user_id: me.id.clone(),
             })
             .unwrap();
     }
+
     pub fn content(&self, content_idx: ContentIdx) -> &Content {
         &self.contents[content_idx]
     }
 
+    /// Find courses whose code (ie `INFR11011`) or name contains the given query, and loaded content items whose title does, ignoring case.
+    /// Courses come first, then content in the order it was loaded.
+    pub fn search_content(&self, query: &str) -> Vec<SearchResult> {
+        let course_names = self
+            .courses
+            .iter()
+            .map(|c| format!("{} {}", c.course_id, c.name))
+            .collect::<Vec<_>>();
+        let courses = matching_names(course_names.iter().map(String::as_str), query)
+            .into_iter()
+            .map(SearchResult::Course);
+        let (idxs, titles): (Vec<_>, Vec<_>) = self
+            .contents
+            .iter()
+            .map(|(idx, c)| (idx, c.title.as_str()))
+            .unzip();
+        let contents = matching_names(titles.into_iter(), query)
+            .into_iter()
+            .map(|i| SearchResult::Content(idxs[i]));
+
+        courses.chain(contents).collect()
+    }
+
+    /// Find a loaded content item by its IDs
+    pub fn find_content(&self, course_id: &str, content_id: &str) -> Option<ContentIdx> {
+        self.contents.find(course_id, content_id)
+    }
+
+    /// Record that the given content item was viewed, which also marks it as read
+    pub fn mark_viewed(&mut self, content_idx: ContentIdx) {
+        let content = &self.contents[content_idx];
+        self.read.set(&content.course_id, &content.id, true);
+        self.recent.push(RecentItem {
+            course_id: content.course_id.clone(),
+            content_id: content.id.clone(),
+            title: content.title.clone(),
+        });
+    }
+
+    /// Check if the user has read the given content item
+    pub fn is_read(&self, content_idx: ContentIdx) -> bool {
+        let content = &self.contents[content_idx];
+        self.read.contains(&content.course_id, &content.id)
+    }
+
+    /// Mark the given content item as read or unread
+    pub fn set_read(&mut self, content_idx: ContentIdx, read: bool) {
+        let content = &self.contents[content_idx];
+        self.read.set(&content.course_id, &content.id, read);
+    }
+
+    /// Check if the given content item has changed since the user last opened its course, or the last session if they haven't
+    pub fn is_new(&self, content_idx: ContentIdx) -> bool {
+        let content = &self.contents[content_idx];
+        let since = self
+            .course_visits
+            .get(&content.course_id)
+            .copied()
+            .or(self.new_since);
+        match (since, content.last_modified) {
+            (Some(since), Some(modified)) => modified > since,
+            _ => false,
+        }
+    }
+
+    /// Count the loaded content in a course which is new, see [`Self::is_new`]
+    pub fn new_content_count(&self, course_idx: CourseIdx) -> usize {
+        let course_id = &self.course(course_idx).id;
+        self.new_counts.get(course_id).copied().unwrap_or(0)
+    }
+
+    /// Add any new items in the given content, which was just loaded, to [`Self::new_content_count`]
+    fn count_new_content(&mut self, range: Range<ContentIdx>) {
+        for idx in range {
+            if self.is_new(idx) {
+                let course_id = self.contents[idx].course_id.clone();
+                *self.new_counts.entry(course_id).or_default() += 1;
+            }
+        }
+    }
+
+    /// Record that the user opened a course, so its content is only new if it changes after this.
+    /// This takes effect next session, so the content they're about to look at stays marked.
+    pub fn visit_course(&mut self, course_idx: CourseIdx) {
+        let course_id = self.course(course_idx).id.clone();
+        self.session.course_visits.insert(course_id, Local::now());
+    }
+
+    /// Summarise which courses have new content, once all the content we're loading on startup has arrived
+    fn new_content_summary(&mut self) -> Option<String> {
+        if self.new_summary_shown || !self.course_content_pending.borrow().is_empty() {
+            return None;
+        }
+        self.new_summary_shown = true;
+
+        let counts = self
+            .contents
+            .loaded_courses()
+            .map(|idx| (self.courses[idx].name.as_str(), self.new_content_count(idx)))
+            .collect::<Vec<_>>();
+        summarise_new_content(counts)
+    }
+
+    /// Get recently viewed content, most recent first
+    pub fn recent(&self) -> &RecentList {
+        &self.recent
+    }
+
     pub fn course(&self, course_idx: CourseIdx) -> &Course {
         &self.my_courses().unwrap()[course_idx]
     }
 
-    pub fn download_content(&mut self, content_idx: ContentIdx) {
+    /// Queue the given file for download.
+    /// If `open` is set, or the user configured it, it's opened once it finishes.
+    pub fn download_content(&mut self, content_idx: ContentIdx, open: bool) {
+        if self.offline {
+            return;
+        }
+
         let content = self.content(content_idx);
         if let ContentPayload::File {
             file_name,
@@ -200,14 +1301,24 @@ impl Store {
         } = &content.payload
         {
             // TODO
-            let dest = Utf8PathBuf::from(format!("./{}", file_name));
+            let mut dest = Utf8PathBuf::from(".");
+            if self.config.download_layout == DownloadLayout::Structured {
+                dest.push(self.content_path(content_idx));
+            }
+            dest.push(sanitise_file_name(file_name));
             let req = DownloadReq {
                 url: permanent_url.to_string(),
                 orig_filename: file_name.to_string(),
                 dest,
+                offset: 0,
             };
             self.download_queue
                 .insert(content_idx, (req.clone(), DownloadState::Queued));
+            if open || self.config.open_downloads {
+                self.open_when_downloaded.insert(content_idx);
+            } else {
+                self.open_when_downloaded.remove(&content_idx);
+            }
             self.downloader_channel
                 .send(DownloaderRequest::DoDownload(content_idx, req))
                 .unwrap();
@@ -237,21 +1348,50 @@ impl Store {
         self.download_queue.get(&content_idx)
     }
 
-    pub fn event(&mut self, e: Event) -> Action {
+    /// Handle an event from one of our workers, unless it was meant for an older store
+    pub fn event(&mut self, epoch: Epoch, e: Event) -> Action {
+        if epoch != self.epoch {
+            debug!("ignoring event from epoch {}: {:?}", epoch, e);
+            return Action::None;
+        }
+
+        if matches!(e, Event::Me { .. } | Event::Error(_)) {
+            self.login_progress = None;
+        }
+
         match e {
-            Event::Error(edlearn_client::Error::AuthError(_)) => return Action::Reauthenticate,
-            Event::Error(e) => return Action::Flash(error_text(e.to_string())),
+            Event::Error(edlearn_client::Error::AuthError(AuthError::Timeout(service))) => {
+                return Action::Flash(error_text(self.config.theme(), format!(
+                    "Timed out contacting {service} - check your connection"
+                )))
+            }
+            Event::Error(edlearn_client::Error::AuthError(AuthError::RepeatedFailures)) => {
+                return Action::Reauthenticate(
+                    "Couldn't login after several attempts - check your connection, then your username & password.",
+                )
+            }
+            Event::Error(edlearn_client::Error::AuthError(_)) => {
+                return Action::Reauthenticate(
+                    "Authentication failed, please double check your username & password.",
+                )
+            }
+            Event::Error(e) => return Action::Flash(error_text(self.config.theme(), e.to_string())),
             Event::Me {
                 me,
                 mut courses,
-                mut terms,
+                terms,
                 favourite_ids,
             } => {
                 self.me = Some(me);
 
+                let mut missing = vec![];
+                if favourite_ids.is_none() {
+                    missing.push("favourites");
+                }
+
                 // pull out favourite courses
                 let mut fav_course_idxs = vec![];
-                for fav in favourite_ids {
+                for fav in favourite_ids.unwrap_or_default() {
                     let Some((i, c)) = courses.iter_mut().enumerate().find(|(_, c)| c.id == fav)
                     else {
                         continue;
@@ -261,54 +1401,693 @@ impl Store {
                     c.term_id = Some("__fav".to_string());
                     fav_course_idxs.push(i);
                 }
-                self.courses_by_term
-                    .push(("Favourites".to_string(), fav_course_idxs));
+                if !fav_course_idxs.is_empty() {
+                    self.courses_by_term
+                        .push(("Favourites".to_string(), fav_course_idxs.clone()));
+                }
 
-                terms.reverse();
+                if let Some(mut terms) = terms {
+                    terms.reverse();
 
-                for term in terms {
-                    let term_courses = courses
-                        .iter()
-                        .enumerate()
-                        .filter(|(_, c)| c.term_id.as_ref().map(|i| *i == term.id).unwrap_or(false))
-                        .map(|(i, _)| i)
-                        .collect::<Vec<_>>();
+                    for term in terms {
+                        let term_courses = courses
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, c)| {
+                                c.term_id.as_ref().map(|i| *i == term.id).unwrap_or(false)
+                            })
+                            .map(|(i, _)| i)
+                            .collect::<Vec<_>>();
 
-                    if !term_courses.is_empty() {
-                        self.courses_by_term.push((term.name, term_courses));
+                        if !term_courses.is_empty() {
+                            self.courses_by_term.push((term.name, term_courses));
+                        }
+                    }
+                } else {
+                    // without terms, list everything that isn't a favourite together
+                    missing.push("terms");
+                    let other_courses = (0..courses.len())
+                        .filter(|i| !fav_course_idxs.contains(i))
+                        .collect::<Vec<_>>();
+                    if !other_courses.is_empty() {
+                        self.courses_by_term
+                            .push(("All courses".to_string(), other_courses));
                     }
                 }
 
                 self.courses = courses;
+
+                // Restore first, so the course it needs isn't also prefetched
+                let action = self.start_restore();
+                self.prefetch_course_content();
+                self.worker_channel.send(Request::CheckVersion).unwrap();
+
+                if matches!(action, Action::None) && !missing.is_empty() {
+                    return Action::Flash(error_text(self.config.theme(), format!(
+                        "Couldn't load your {} - showing your courses without them.",
+                        missing.join(" or ")
+                    )));
+                }
+
+                return action;
             }
             Event::CourseContent {
                 course_idx,
                 content,
             } => {
-                self.course_contents.insert(
-                    course_idx,
-                    self.contents.len()..self.contents.len() + content.len(),
-                );
-                self.contents.extend(content);
+                self.course_content_pending.borrow_mut().remove(&course_idx);
+                let removed = self.contents.remove_course_content(course_idx);
+                self.forget_content(removed);
+                let range = self.contents.insert_course_content(course_idx, content);
+                self.count_new_content(range.clone());
+
+                let mut action = self.continue_restore(course_idx, None, range);
+                if self.pending_overview == Some(course_idx) {
+                    self.pending_overview = None;
+                    if let (Action::None, Some(overview)) =
+                        (&action, self.course_overview(course_idx))
+                    {
+                        action = Action::Preview(Document::Content(overview));
+                    }
+                }
+
+                // if something else is being shown, the summary waits for the next course to load
+                if matches!(action, Action::None) {
+                    if let Some(summary) = self.new_content_summary() {
+                        return Action::Flash(summary.into());
+                    }
+                }
+
+                return action;
+            }
+            Event::CourseTree { course_idx, tree } => {
+                self.course_content_pending.borrow_mut().remove(&course_idx);
+                let removed = self.contents.remove_course_content(course_idx);
+                self.forget_content(removed);
+                self.insert_course_tree(course_idx, tree);
+
+                if self
+                    .restore
+                    .as_ref()
+                    .is_some_and(|r| r.course_idx == course_idx)
+                {
+                    return self.resume_restore();
+                }
             }
             Event::ContentChildren {
                 content_idx,
                 children,
             } => {
-                self.content_children.insert(
-                    content_idx,
-                    self.contents.len()..self.contents.len() + children.len(),
-                );
-                self.contents.extend(children);
+                let removed = self.contents.remove_children(content_idx);
+                self.forget_content(removed);
+                let range = self.contents.insert_children(content_idx, children);
+                self.count_new_content(range.clone());
+                self.prefetch_page_texts(range.clone());
+
+                if let Some(restore) = &self.restore {
+                    return self.continue_restore(restore.course_idx, Some(content_idx), range);
+                }
             }
             Event::PageText { content_idx, text } => {
+                self.page_text_pending.borrow_mut().remove(&content_idx);
+                if let Some(opened) = self.page_text_waiting.borrow_mut().remove(&content_idx) {
+                    debug!(
+                        "waited {:?} for the text of page {content_idx}",
+                        opened.elapsed()
+                    );
+                }
+                self.page_renders.borrow_mut().remove(&content_idx);
+                self.page_docs.borrow_mut().remove(&content_idx);
                 self.page_texts.insert(content_idx, text);
             }
+            Event::PrefetchFailed { content_idx } => {
+                // so opening it tries again
+                self.page_text_pending.borrow_mut().remove(&content_idx);
+            }
+            Event::Attempts {
+                content_idx,
+                attempts,
+            } => {
+                self.attempts_pending.borrow_mut().remove(&content_idx);
+                self.attempts.insert(content_idx, attempts);
+            }
+            Event::AssessmentDetails {
+                content_idx,
+                details,
+            } => {
+                self.assessment_details_pending
+                    .borrow_mut()
+                    .remove(&content_idx);
+                self.assessment_details.insert(content_idx, details);
+            }
+            Event::Announcements {
+                course_idx,
+                announcements,
+            } => {
+                self.announcements_pending.borrow_mut().remove(&course_idx);
+                self.announcements.insert(course_idx, announcements);
+            }
+            Event::Grades { course_idx, grades } => {
+                self.grades_pending.borrow_mut().remove(&course_idx);
+                self.grades.insert(course_idx, grades);
+            }
             Event::DownloadState(r, state) => {
+                if matches!(state, DownloadState::Completed) && self.config.download_notifications {
+                    if let Some((req, _)) = self.download_queue.get(&r) {
+                        notifications::notify(
+                            "Download finished",
+                            req.dest.file_name().unwrap_or(req.dest.as_str()),
+                        );
+                    }
+                }
+                let completed = matches!(state, DownloadState::Completed);
+                if matches!(state, DownloadState::Errored(_)) {
+                    self.open_when_downloaded.remove(&r);
+                }
                 self.download_queue.entry(r).and_modify(|s| s.1 = state);
+
+                if completed && self.open_when_downloaded.remove(&r) {
+                    if let Some((req, _)) = self.download_queue.get(&r) {
+                        match self.config.open(&req.dest) {
+                            Ok(Some(output)) => return Action::Flash(output.into()),
+                            Ok(None) => (),
+                            Err(e) => {
+                                return Action::Flash(error_text(
+                                    self.config.theme(),
+                                    format!("Error opening {}: {e}", req.dest),
+                                ))
+                            }
+                        }
+                    }
+                }
+            }
+            Event::ContentFound {
+                course_id,
+                content_id,
+                path,
+            } => {
+                let course_idx = self.courses.iter().position(|c| c.id == course_id);
+                let (Some(path), Some(course_idx)) = (path, course_idx) else {
+                    return Action::Flash(error_text(self.config.theme(), format!(
+                        "Couldn't find content {} in course {}",
+                        content_id, course_id
+                    )));
+                };
+
+                self.restore = Some(PendingRestore {
+                    course_idx,
+                    waiting_on: None,
+                    path,
+                });
+                return self.resume_restore();
+            }
+            Event::Unavailable => {
+                return Action::Flash(error_text(self.config.theme(), "Not available offline."));
+            }
+            Event::VersionChecked {
+                version: Some(version),
+                tested: false,
+            } => {
+                self.learn_version = Some(version.clone());
+                return Action::Flash(error_text(self.config.theme(), format!(
+                    "Learn is running version {version}, which learn-tui hasn't been tested with. If anything looks wrong, please report it at {}/issues",
+                    env!("CARGO_PKG_REPOSITORY")
+                )));
+            }
+            Event::VersionChecked { version, .. } => self.learn_version = version,
+            Event::LoggingIn { service } => {
+                if self.me.is_none() {
+                    self.login_progress = Some(service);
+                }
+            }
+            Event::SessionChecked { ok: true } => (),
+            Event::SessionChecked { ok: false } => {
+                return Action::Flash(error_text(
+                self.config.theme(),
+                    "Couldn't reach Learn - check your internet connection.",
+                ))
             }
         };
 
         Action::None
     }
 }
+
+/// Describe how many new items each course has, ie `3 new items in Course X, 1 in Course Y`.
+/// Courses with the most new items come first, and courses without any are left out.
+fn summarise_new_content(mut counts: Vec<(&str, usize)>) -> Option<String> {
+    counts.retain(|(_, n)| *n > 0);
+    counts.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+
+    let ((first_name, first), rest) = counts.split_first()?;
+    let mut summary = format!(
+        "{} new item{} in {}",
+        first,
+        if *first == 1 { "" } else { "s" },
+        first_name
+    );
+    for (name, n) in rest {
+        summary.push_str(&format!(", {} in {}", n, name));
+    }
+
+    Some(summary)
+}
+
+/// Get the indices of the names which contain the query, ignoring case.
+/// An empty or blank query matches nothing.
+fn matching_names<'a>(names: impl Iterator<Item = &'a str>, query: &str) -> Vec<usize> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return vec![];
+    }
+
+    names
+        .enumerate()
+        .filter(|(_, name)| name.to_lowercase().contains(&query))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Where a store made by [`Store::for_test`] sends its requests.
+/// These need to be kept alive even if they aren't checked, or sending to them fails.
+#[cfg(test)]
+pub(crate) struct TestChannels {
+    pub worker: std::sync::mpsc::Receiver<Request>,
+    pub prefetch: std::sync::mpsc::Receiver<Request>,
+    pub downloader: std::sync::mpsc::Receiver<DownloaderRequest>,
+}
+
+#[cfg(test)]
+impl Store {
+    /// Create a store which doesn't load any saved state or start any workers.
+    /// Requests are left in the returned channels, so tests can check them.
+    pub(crate) fn for_test(config: Config) -> (Self, TestChannels) {
+        let (worker_channel, worker) = std::sync::mpsc::channel();
+        let (prefetch_channel, prefetch) = std::sync::mpsc::channel();
+        let (downloader_channel, downloader) = std::sync::mpsc::channel();
+        let store = Self::with_channels(
+            Rc::new(config),
+            NEXT_EPOCH.fetch_add(1, Ordering::Relaxed),
+            false,
+            worker_channel,
+            prefetch_channel,
+            downloader_channel,
+            SavedState::default(),
+        );
+
+        (
+            store,
+            TestChannels {
+                worker,
+                prefetch,
+                downloader,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarise_new_content() {
+        assert_eq!(summarise_new_content(vec![("A", 0), ("B", 0)]), None);
+        assert_eq!(
+            summarise_new_content(vec![("A", 1)]).as_deref(),
+            Some("1 new item in A")
+        );
+        assert_eq!(
+            summarise_new_content(vec![("X", 1), ("Z", 0), ("Y", 3)]).as_deref(),
+            Some("3 new items in Y, 1 in X")
+        );
+    }
+
+    #[test]
+    fn test_old_epoch_ignored() {
+        let (old, _old_channels) = Store::for_test(Default::default());
+        let (mut store, _channels) = Store::for_test(Default::default());
+        assert_ne!(old.epoch, store.epoch);
+
+        let checked = |version: &str| Event::VersionChecked {
+            version: Some(version.to_string()),
+            tested: true,
+        };
+        store.event(old.epoch, checked("old"));
+        assert_eq!(store.learn_version(), None);
+
+        store.event(store.epoch, checked("new"));
+        assert_eq!(store.learn_version(), Some("new"));
+    }
+
+    #[test]
+    fn test_login_progress() {
+        let (mut store, _channels) = Store::for_test(Default::default());
+        let logging_in = |service| Event::LoggingIn { service };
+
+        store.event(store.epoch, logging_in("EASE"));
+        store.event(store.epoch, logging_in("the IDP"));
+        assert_eq!(store.login_progress(), Some("the IDP"));
+
+        store.event(
+            store.epoch,
+            Event::Error(AuthError::Timeout("the IDP").into()),
+        );
+        assert_eq!(store.login_progress(), None);
+
+        // logging in again later is shown like any other request
+        store.me = Some(
+            serde_json::from_str(r#"{"id": "_9_1", "userName": "s1234567", "givenName": "Ada"}"#)
+                .unwrap(),
+        );
+        store.event(store.epoch, logging_in("EASE"));
+        assert_eq!(store.login_progress(), None);
+    }
+
+    #[test]
+    fn test_scrub_personal() {
+        let (mut store, _channels) = Store::for_test(Default::default());
+        store.me = Some(User {
+            id: "_123_1".to_string(),
+            uuid: Some("abcdef".to_string()),
+            student_id: Some("s1234567".to_string()),
+            user_name: "s1234567".to_string(),
+            given_name: "Ada".to_string(),
+            family_name: Some("Lovelace".to_string()),
+            email_address: Some("ada@example.com".to_string()),
+        });
+
+        let scrubbed = store.scrub_personal(
+            "Ada Lovelace (s1234567, ada@example.com) _123_1 abcdef, signed Lovelace",
+        );
+        assert_eq!(
+            scrubbed,
+            "[redacted] ([redacted], [redacted]) [redacted] [redacted], signed [redacted]"
+        );
+    }
+
+    const PAGE: &str = r#"{"resource/x-bb-folder": {"isBbPage": true}}"#;
+    const FOLDER: &str = r#"{"resource/x-bb-folder": {"isBbPage": false}}"#;
+    const ASSESSMENT: &str = r#"{"resource/x-bb-asmt-test-link": {"test": {"gradingColumn": {
+        "id": "_5_1", "effectiveColumnName": "Quiz", "dueDate": "2024-01-01T10:00:00.000Z"
+    }}}}"#;
+    const FILE: &str = r#"{"resource/x-bb-file": {"file": {
+        "mimeType": "application/pdf", "fileName": "a.pdf", "permanentUrl": "/bbcswebdav/xid-1_1"
+    }}}"#;
+
+    /// Get a store with one course, whose top-level content has the given IDs and `contentDetail`s
+    fn store_with_content(items: &[(&str, &str)]) -> (Store, TestChannels) {
+        let (mut store, channels) = Store::for_test(Default::default());
+        store.courses = vec![serde_json::from_str(
+            r#"{"id": "_1_1", "uuid": "abc", "courseId": "INFR00001", "name": "Test Course"}"#,
+        )
+        .unwrap()];
+        let content = items.iter().map(|(id, detail)| {
+            Content::from_json(
+                &format!(r#"{{"id": "{id}", "parentId": "_root", "title": "Item {id}", "contentDetail": {detail}}}"#),
+                "_1_1",
+            )
+            .unwrap()
+        });
+        store.contents.insert_course_content(0, content.collect());
+
+        (store, channels)
+    }
+
+    /// Get a store with one course, whose top-level content is pages with the given IDs
+    fn store_with_pages(ids: &[&str]) -> (Store, TestChannels) {
+        store_with_content(&ids.iter().map(|id| (*id, PAGE)).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_page_text_requested_once() {
+        let (mut store, channels) = store_with_pages(&["_2_1", "_3_1"]);
+        store.prefetch_page_texts(0..2);
+        assert_eq!(channels.prefetch.try_iter().count(), 2);
+
+        // already being prefetched
+        store.request_page_text(0);
+        assert_eq!(channels.worker.try_iter().count(), 0);
+
+        // failed quietly, so opening it asks again, but only once
+        let action = store.event(store.epoch, Event::PrefetchFailed { content_idx: 0 });
+        assert!(matches!(action, Action::None));
+        store.request_page_text(0);
+        store.request_page_text(0);
+        assert!(matches!(
+            channels.worker.try_iter().collect::<Vec<_>>().as_slice(),
+            [Request::PageText {
+                content_idx: 0,
+                prefetch: false,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn test_open_linked_content() {
+        let (mut store, channels) = store_with_content(&[("_2_1", PAGE), ("_3_1", FOLDER)]);
+        assert!(matches!(
+            store.open_linked_content("_1_1", "_2_1"),
+            Some(Action::Show(Document::Content(0)))
+        ));
+        assert!(store.open_linked_content("_9_1", "_2_1").is_none());
+
+        // inside a folder that hasn't been loaded yet
+        store.open_linked_content("_1_1", "_4_1");
+        assert!(matches!(
+            channels.worker.try_recv(),
+            Ok(Request::FindContent { .. })
+        ));
+        let action = store.event(
+            store.epoch,
+            Event::ContentFound {
+                course_id: "_1_1".to_string(),
+                content_id: "_4_1".to_string(),
+                path: Some(vec!["_3_1".to_string(), "_4_1".to_string()]),
+            },
+        );
+        assert!(matches!(action, Action::None));
+        assert!(matches!(
+            channels.worker.try_recv(),
+            Ok(Request::ContentChildren { content_idx: 1, .. })
+        ));
+
+        let child = Content::from_json(
+            &format!(
+                r#"{{"id": "_4_1", "parentId": "_3_1", "title": "Inner", "contentDetail": {PAGE}}}"#
+            ),
+            "_1_1",
+        )
+        .unwrap();
+        let action = store.event(
+            store.epoch,
+            Event::ContentChildren {
+                content_idx: 1,
+                children: vec![child],
+            },
+        );
+        let Action::Show(Document::Content(idx)) = action else {
+            panic!("linked content wasn't shown");
+        };
+        assert_eq!(store.content(idx).id, "_4_1");
+    }
+
+    #[test]
+    fn test_refresh_loads_course_tree() {
+        let (mut store, channels) = store_with_content(&[("_2_1", FOLDER)]);
+        store.refresh_course_content(0);
+        assert!(store.contents.course_content(0).is_none());
+        assert!(matches!(
+            channels.worker.try_recv(),
+            Ok(Request::CourseTree {
+                course_idx: 0,
+                max_depth: REFRESH_DEPTH,
+                ..
+            })
+        ));
+
+        let node = |id: &str, parent: &str, detail: &str, children| {
+            ContentNode {
+            content: Content::from_json(
+                &format!(r#"{{"id": "{id}", "parentId": "{parent}", "title": "Item {id}", "contentDetail": {detail}}}"#),
+                "_1_1",
+            )
+            .unwrap(),
+            children,
+        }
+        };
+        let tree = vec![
+            node(
+                "_2_1",
+                "_root",
+                FOLDER,
+                Some(vec![node("_3_1", "_2_1", FOLDER, None)]),
+            ),
+            node("_4_1", "_root", PAGE, None),
+        ];
+        store.event(
+            store.epoch,
+            Event::CourseTree {
+                course_idx: 0,
+                tree,
+            },
+        );
+
+        let top = store.contents.course_content(0).unwrap();
+        assert_eq!(top.len(), 2);
+        let folder = store.find_content("_1_1", "_2_1").unwrap();
+        let children = store.content_children(folder).unwrap();
+        assert_eq!(store.content(children.start).id, "_3_1");
+
+        // deeper than we fetched, so it's loaded when opened
+        let inner = store.find_content("_1_1", "_3_1").unwrap();
+        assert!(store.content_children(inner).is_none());
+    }
+
+    #[test]
+    fn test_assessment_requested_once() {
+        let (mut store, channels) = store_with_content(&[("_2_1", ASSESSMENT)]);
+
+        // ie drawing several times before anything arrives
+        for _ in 0..3 {
+            store.request_attempts(0);
+            store.request_assessment_details(0);
+        }
+        assert_eq!(channels.worker.try_iter().count(), 2);
+
+        store.event(
+            store.epoch,
+            Event::Attempts {
+                content_idx: 0,
+                attempts: None,
+            },
+        );
+        assert!(store.attempts_pending.borrow().is_empty());
+        store.event(
+            store.epoch,
+            Event::AssessmentDetails {
+                content_idx: 0,
+                details: None,
+            },
+        );
+        assert!(store.assessment_details_pending.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_announcements_and_grades_requested_once() {
+        let (mut store, channels) = store_with_pages(&[]);
+        store.me = Some(
+            serde_json::from_str(r#"{"id": "_9_1", "userName": "s1234567", "givenName": "Ada"}"#)
+                .unwrap(),
+        );
+
+        // ie drawing several times before anything arrives
+        for _ in 0..3 {
+            store.request_announcements(0);
+            store.request_grades(0);
+        }
+        assert_eq!(channels.worker.try_iter().count(), 2);
+
+        store.event(
+            store.epoch,
+            Event::Announcements {
+                course_idx: 0,
+                announcements: None,
+            },
+        );
+        store.event(
+            store.epoch,
+            Event::Grades {
+                course_idx: 0,
+                grades: None,
+            },
+        );
+        assert!(store.announcements_pending.borrow().is_empty());
+        assert!(store.grades_pending.borrow().is_empty());
+
+        // refreshing the course gets them again
+        store.refresh_course_content(0);
+        assert!(store.announcements(0).is_none());
+        store.request_announcements(0);
+        store.request_grades(0);
+        assert_eq!(channels.worker.try_iter().count(), 3);
+    }
+
+    #[test]
+    fn test_failed_download_not_opened_later() {
+        let (mut store, channels) = store_with_content(&[("_2_1", FILE), ("_3_1", PAGE)]);
+
+        // only files are downloaded, so there's nothing to open
+        store.download_content(1, true);
+        assert!(store.open_when_downloaded.is_empty());
+
+        store.download_content(0, true);
+        assert!(store.open_when_downloaded.contains(&0));
+        store.event(
+            store.epoch,
+            Event::DownloadState(0, DownloadState::Errored("oops".to_string())),
+        );
+        assert!(store.open_when_downloaded.is_empty());
+
+        store.download_content(0, false);
+        assert!(store.open_when_downloaded.is_empty());
+        assert_eq!(channels.downloader.try_iter().count(), 2);
+    }
+
+    #[test]
+    fn test_search_content() {
+        let (mut store, _channels) = store_with_content(&[("_2_1", FOLDER), ("_3_1", PAGE)]);
+        store.courses.push(
+            serde_json::from_str(
+                r#"{"id": "_1_2", "uuid": "def", "courseId": "MATH00001", "name": "Item Theory"}"#,
+            )
+            .unwrap(),
+        );
+        let children = ["_4_1", "_5_1"].map(|id| {
+            Content::from_json(
+                &format!(r#"{{"id": "{id}", "parentId": "_2_1", "title": "Slides {id}"}}"#),
+                "_1_1",
+            )
+            .unwrap()
+        });
+        store.contents.insert_children(0, children.into());
+
+        // courses by code or name, then content by title
+        assert_eq!(
+            store.search_content("item"),
+            vec![
+                SearchResult::Course(1),
+                SearchResult::Content(0),
+                SearchResult::Content(1)
+            ]
+        );
+        assert_eq!(store.search_content("infr"), vec![SearchResult::Course(0)]);
+        assert_eq!(
+            store.search_content("SLIDES _5"),
+            vec![SearchResult::Content(3)]
+        );
+
+        // refreshed content isn't found any more
+        store.event(
+            store.epoch,
+            Event::ContentChildren {
+                content_idx: 0,
+                children: vec![],
+            },
+        );
+        assert!(store.search_content("slides").is_empty());
+        assert!(store.search_content(" ").is_empty());
+    }
+
+    #[test]
+    fn test_matching_names() {
+        let names = ["Week 1", "Lecture Slides", "week 2 slides", "Ölçüm"];
+
+        assert_eq!(matching_names(names.into_iter(), "week"), vec![0, 2]);
+        assert_eq!(matching_names(names.into_iter(), "SLIDES"), vec![1, 2]);
+        assert_eq!(matching_names(names.into_iter(), "ölç"), vec![3]);
+        assert!(matching_names(names.into_iter(), "exam").is_empty());
+        assert!(matching_names(names.into_iter(), "  ").is_empty());
+    }
+}
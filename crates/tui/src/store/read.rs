@@ -0,0 +1,39 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::auth_cache::{load_state, save_state};
+
+const FILE_NAME: &str = "learn-tui-read.json";
+
+/// Content items the user has read, as `(course_id, content_id)`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReadSet(BTreeSet<(String, String)>);
+
+impl ReadSet {
+    /// Load the set from disk
+    pub fn load() -> Result<Self> {
+        load_state(FILE_NAME)
+    }
+
+    /// Save the set to disk
+    pub fn save(&self) -> Result<()> {
+        save_state(FILE_NAME, self)
+    }
+
+    pub fn contains(&self, course_id: &str, content_id: &str) -> bool {
+        self.0
+            .contains(&(course_id.to_string(), content_id.to_string()))
+    }
+
+    /// Mark the given item as read or unread
+    pub fn set(&mut self, course_id: &str, content_id: &str, read: bool) {
+        let key = (course_id.to_string(), content_id.to_string());
+        if read {
+            self.0.insert(key);
+        } else {
+            self.0.remove(&key);
+        }
+    }
+}
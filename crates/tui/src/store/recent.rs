@@ -0,0 +1,52 @@
+use std::collections::VecDeque;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::auth_cache::{load_state, save_state};
+
+/// The maximum number of items we remember
+const MAX_RECENT: usize = 10;
+
+const FILE_NAME: &str = "learn-tui-recent.json";
+
+/// A recently viewed content item.
+/// This uses IDs rather than a [`super::ContentIdx`], so that it stays valid between sessions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecentItem {
+    pub course_id: String,
+    pub content_id: String,
+    pub title: String,
+}
+
+/// A bounded list of recently viewed content, most recent first.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RecentList(VecDeque<RecentItem>);
+
+impl RecentList {
+    /// Load the list from disk
+    pub fn load() -> Result<Self> {
+        load_state(FILE_NAME)
+    }
+
+    /// Save the list to disk
+    pub fn save(&self) -> Result<()> {
+        save_state(FILE_NAME, self)
+    }
+
+    /// Record that the given item was viewed, moving it to the front if it's already present.
+    pub fn push(&mut self, item: RecentItem) {
+        self.0
+            .retain(|i| i.course_id != item.course_id || i.content_id != item.content_id);
+        self.0.push_front(item);
+        self.0.truncate(MAX_RECENT);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RecentItem> {
+        self.0.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::auth_cache::{load_state, save_state};
+
+const FILE_NAME: &str = "learn-tui-session.json";
+
+/// A document that was being viewed, in a form that stays valid between sessions.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SavedDocument {
+    #[default]
+    Welcome,
+    Downloads,
+    Recent,
+
+    /// A course's announcements or grades
+    Announcements {
+        course_id: String,
+    },
+    Grades {
+        course_id: String,
+    },
+
+    /// A content item, with the IDs of each content item from the top of the course down to it.
+    Content {
+        course_id: String,
+        path: Vec<String>,
+    },
+}
+
+/// State of the previous session, which we restore on startup
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LastSession {
+    pub document: SavedDocument,
+
+    /// When the session started, so we can tell what's changed since
+    #[serde(default)]
+    pub started: Option<DateTime<Local>>,
+
+    /// Names of terms the user has collapsed in the navigation tree
+    #[serde(default)]
+    pub collapsed_terms: Vec<String>,
+
+    /// When the user last opened each course, by course ID
+    #[serde(default)]
+    pub course_visits: HashMap<String, DateTime<Local>>,
+}
+
+impl LastSession {
+    /// Load the last session from disk
+    pub fn load() -> Result<Self> {
+        load_state(FILE_NAME)
+    }
+
+    /// Save the session to disk
+    pub fn save(&self) -> Result<()> {
+        save_state(FILE_NAME, self)
+    }
+}
+
+/// Progress restoring a content item from a previous session.
+/// Each level of the path is loaded in turn, until we reach the item itself.
+#[derive(Debug)]
+pub(crate) struct PendingRestore {
+    pub course_idx: super::CourseIdx,
+
+    /// The content whose children we're waiting on, or `None` if waiting on the top level of the course.
+    pub waiting_on: Option<super::ContentIdx>,
+
+    /// The remaining content IDs to find
+    pub path: Vec<String>,
+}
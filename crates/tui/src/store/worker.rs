@@ -1,29 +1,60 @@
 use anyhow::Result;
-use edlearn_client::Client;
+use edlearn_client::{course::Course, users::User, Client};
 use log::debug;
-use std::sync::mpsc::{channel, Receiver, Sender};
+use std::{
+    sync::{
+        atomic::Ordering,
+        mpsc::{channel, Receiver, Sender},
+    },
+    thread,
+    time::{Duration, Instant},
+};
 
-use super::{Event, Request};
+use super::{Epoch, Event, Request};
 use crate::event::{Event as CrateEvent, EventBus};
 
+/// How deep we'll look for content when finding it by ID
+const MAX_CONTENT_DEPTH: usize = 32;
+
+/// How many times we try to get the current user and their courses, and how long we wait between attempts
+const ME_ATTEMPTS: usize = 3;
+const ME_RETRY_DELAY: Duration = Duration::from_secs(2);
+
 /// Performs requests it receives from the main thread, and sends the results back.
 pub struct Worker {
     client: Client,
+    offline: bool,
     msg_recv: Receiver<Request>,
     event_send: Sender<CrateEvent>,
+    epoch: Epoch,
 }
 
 impl Worker {
     /// Spawn the store worker on the given event bus, returning a channel to send commands down.
-    pub(crate) fn spawn_on(bus: &EventBus, client: Client) -> Sender<Request> {
+    /// If `offline` is set, it won't make any requests.
+    /// Events it sends are stamped with `epoch`.
+    pub(crate) fn spawn_on(
+        bus: &EventBus,
+        client: Client,
+        offline: bool,
+        epoch: Epoch,
+    ) -> Sender<Request> {
         let (cmd_send, cmd_recv) = channel();
 
         bus.spawn("store_worker", move |_, event_send| {
+            let progress_send = event_send.clone();
+            let client = client.with_login_progress(move |service| {
+                // if this fails, the main thread is gone and we'll exit soon anyway
+                let _ = progress_send.send(CrateEvent::Store(epoch, Event::LoggingIn { service }));
+            });
+
             // we don't need running because the receiver will raise an error and we'll exit
             Worker {
                 client,
+                offline,
                 msg_recv: cmd_recv,
                 event_send,
+                epoch,
             }
             .main()
         });
@@ -31,12 +62,38 @@ impl Worker {
         cmd_send
     }
 
+    /// Spawn a thread on the given event bus which asks the worker to check our session is still valid every `interval`.
+    pub(crate) fn spawn_health_check_on(
+        bus: &EventBus,
+        worker_channel: Sender<Request>,
+        interval: Duration,
+    ) {
+        bus.spawn("health_check", move |running, _| {
+            let mut last_check = Instant::now();
+            while running.load(Ordering::Relaxed) {
+                // sleep in small increments so we don't hold up exiting
+                thread::sleep(Duration::from_millis(250));
+                if last_check.elapsed() < interval {
+                    continue;
+                }
+
+                if worker_channel.send(Request::HealthCheck).is_err() {
+                    // worker has exited
+                    break;
+                }
+                last_check = Instant::now();
+            }
+        });
+    }
+
     fn main(self) {
         while let Ok(msg) = self.msg_recv.recv() {
             debug!("received message: {:?}", msg);
             if let Err(e) = match self.process_msg(msg) {
-                Ok(e) => self.event_send.send(CrateEvent::Store(e)),
-                Err(e) => self.event_send.send(CrateEvent::Store(Event::Error(e))),
+                Ok(e) => self.event_send.send(CrateEvent::Store(self.epoch, e)),
+                Err(e) => self
+                    .event_send
+                    .send(CrateEvent::Store(self.epoch, Event::Error(e))),
             } {
                 debug!("error sending event: {:?}", e);
                 break;
@@ -46,19 +103,98 @@ impl Worker {
         debug!("shutting down");
     }
 
+    /// Get the current user, and the courses they're a member of
+    fn me_and_courses(&self) -> Result<(User, Vec<Course>), edlearn_client::Error> {
+        let me = self.client.me()?;
+        let courses = self
+            .client
+            .user_memberships(&me.id)?
+            .into_iter()
+            .map(|m| m.course)
+            .collect();
+
+        Ok((me, courses))
+    }
+
+    /// Find the given content item in a course, returning the IDs leading to it.
+    /// This walks up from the item through its parents, until it reaches the course's root folder.
+    fn find_content(
+        &self,
+        course_id: &str,
+        content_id: &str,
+    ) -> Result<Option<Vec<String>>, edlearn_client::Error> {
+        let Some(root_id) = self
+            .client
+            .course_children(course_id)?
+            .into_iter()
+            .next()
+            .map(|c| c.parent_id)
+        else {
+            return Ok(None);
+        };
+
+        let Some(mut item) = self.client.content(course_id, content_id)? else {
+            return Ok(None);
+        };
+        let mut path = vec![item.id.clone()];
+        while item.parent_id != root_id {
+            // Guard against cycles, which shouldn't happen but would otherwise hang the worker
+            if path.len() > MAX_CONTENT_DEPTH {
+                return Ok(None);
+            }
+
+            let Some(parent) = self.client.content(course_id, &item.parent_id)? else {
+                return Ok(None);
+            };
+            item = parent;
+            path.insert(0, item.id.clone());
+        }
+
+        Ok(Some(path))
+    }
+
     fn process_msg(&self, msg: Request) -> Result<Event, edlearn_client::Error> {
+        if self.offline {
+            return Ok(Event::Unavailable);
+        }
+
         match msg {
             Request::Me => {
-                let me = self.client.me()?;
-                let courses = self
-                    .client
-                    .user_memberships(&me.id)?
-                    .into_iter()
-                    .map(|m| m.course)
-                    .collect::<Vec<_>>();
+                // Nothing works without these, so try a few times before giving up
+                let mut attempt = 1;
+                let (me, courses) = loop {
+                    match self.me_and_courses() {
+                        Ok(r) => break r,
+                        Err(e @ edlearn_client::Error::AuthError(_)) => return Err(e),
+                        Err(e) if attempt >= ME_ATTEMPTS => return Err(e),
+                        Err(e) => {
+                            debug!(
+                                "error getting user and courses (attempt {attempt}): {:?}",
+                                e
+                            );
+                            attempt += 1;
+                            thread::sleep(ME_RETRY_DELAY);
+                        }
+                    }
+                };
 
-                let terms = self.client.terms()?;
-                let favourite_ids = self.client.my_favourites()?;
+                // We can still show courses without these, so they aren't worth failing over
+                let terms = match self.client.terms() {
+                    Ok(t) => Some(t),
+                    Err(e @ edlearn_client::Error::AuthError(_)) => return Err(e),
+                    Err(e) => {
+                        debug!("error getting terms: {:?}", e);
+                        None
+                    }
+                };
+                let favourite_ids = match self.client.my_favourites() {
+                    Ok(f) => Some(f),
+                    Err(e @ edlearn_client::Error::AuthError(_)) => return Err(e),
+                    Err(e) => {
+                        debug!("error getting favourites: {:?}", e);
+                        None
+                    }
+                };
 
                 Ok(Event::Me {
                     me,
@@ -77,6 +213,14 @@ impl Worker {
                     content,
                 })
             }
+            Request::CourseTree {
+                course_idx,
+                course_id,
+                max_depth,
+            } => {
+                let tree = self.client.course_tree(&course_id, max_depth)?;
+                Ok(Event::CourseTree { course_idx, tree })
+            }
             Request::ContentChildren {
                 content_idx,
                 course_id,
@@ -92,10 +236,120 @@ impl Worker {
                 content_idx,
                 course_id,
                 content_id,
+                prefetch,
+            } => match self.client.page_text(&course_id, &content_id) {
+                Ok(text) => Ok(Event::PageText { content_idx, text }),
+                Err(e) if prefetch => {
+                    debug!("error prefetching page text: {e}");
+                    Ok(Event::PrefetchFailed { content_idx })
+                }
+                Err(e) => Err(e),
+            },
+            Request::Attempts {
+                content_idx,
+                course_id,
+                column_id,
+            } => {
+                // Not every assessment lets students see their attempts, so this isn't worth an error
+                let attempts = match self.client.attempts(&course_id, &column_id) {
+                    Ok(a) => Some(a),
+                    Err(e) => {
+                        debug!("error getting attempts: {:?}", e);
+                        None
+                    }
+                };
+                Ok(Event::Attempts {
+                    content_idx,
+                    attempts,
+                })
+            }
+            Request::AssessmentDetails {
+                content_idx,
+                course_id,
+                column_id,
+            } => {
+                // We can still show the basics without these
+                let details = match self.client.assessment_details(&course_id, &column_id) {
+                    Ok(d) => Some(d),
+                    Err(e @ edlearn_client::Error::AuthError(_)) => return Err(e),
+                    Err(e) => {
+                        debug!("error getting assessment details: {:?}", e);
+                        None
+                    }
+                };
+                Ok(Event::AssessmentDetails {
+                    content_idx,
+                    details,
+                })
+            }
+            Request::Announcements {
+                course_idx,
+                course_id,
+            } => {
+                let announcements = match self.client.course_announcements(&course_id) {
+                    Ok(a) => Some(a),
+                    Err(e @ edlearn_client::Error::AuthError(_)) => return Err(e),
+                    Err(e) => {
+                        debug!("error getting announcements: {:?}", e);
+                        None
+                    }
+                };
+                Ok(Event::Announcements {
+                    course_idx,
+                    announcements,
+                })
+            }
+            Request::Grades {
+                course_idx,
+                course_id,
+                user_id,
+            } => {
+                // Some courses don't let students see the gradebook at all
+                let grades = match self.client.grades(&course_id, &user_id) {
+                    Ok(g) => Some(g),
+                    Err(e @ edlearn_client::Error::AuthError(_)) => return Err(e),
+                    Err(e) => {
+                        debug!("error getting grades: {:?}", e);
+                        None
+                    }
+                };
+                Ok(Event::Grades { course_idx, grades })
+            }
+            Request::FindContent {
+                course_id,
+                content_id,
             } => {
-                let text = self.client.page_text(&course_id, &content_id)?;
-                Ok(Event::PageText { content_idx, text })
+                let path = self.find_content(&course_id, &content_id)?;
+                Ok(Event::ContentFound {
+                    course_id,
+                    content_id,
+                    path,
+                })
             }
+            Request::CheckVersion => match self.client.health() {
+                Ok(health) => Ok(Event::VersionChecked {
+                    tested: health.is_tested_version(),
+                    version: Some(health.version),
+                }),
+                // this is only informational, so don't bother the user if it fails
+                Err(e @ edlearn_client::Error::AuthError(_)) => Err(e),
+                Err(e) => {
+                    debug!("error checking version: {}", e);
+                    Ok(Event::VersionChecked {
+                        version: None,
+                        tested: true,
+                    })
+                }
+            },
+            Request::HealthCheck => match self.client.health() {
+                Ok(_) => Ok(Event::SessionChecked { ok: true }),
+                // this means re-authenticating failed, so let the UI re-prompt
+                Err(e @ edlearn_client::Error::AuthError(_)) => Err(e),
+                Err(e) => {
+                    debug!("health check failed: {}", e);
+                    Ok(Event::SessionChecked { ok: false })
+                }
+            },
         }
     }
 }
@@ -1,10 +1,174 @@
 use ratatui::{
     prelude::Text,
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
 };
 
-pub fn error_text(t: impl Into<Text<'static>>) -> Text<'static> {
+/// The colours used throughout the UI
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    /// Borders of the focused and unfocused panes
+    pub border_focused: Color,
+    pub border_unfocused: Color,
+
+    /// Errors and other bad news, ie overdue assessments
+    pub error: Color,
+
+    /// Things which might need attention, ie unsubmitted assessments
+    pub warning: Color,
+
+    /// Things which went well, ie finished downloads
+    pub success: Color,
+
+    /// Course tools and unread markers in the navigation tree
+    pub accent: Color,
+
+    /// Downloads in progress
+    pub progress: Color,
+
+    /// Headers in the navigation tree
+    pub header: Color,
+
+    /// Badges for new content
+    pub badge: Color,
+
+    /// Hints and queued downloads
+    pub hint: Color,
+
+    /// Courses which have ended, and empty progress bars
+    pub inactive: Color,
+
+    /// Titles in the viewer, and keys in the welcome message
+    pub title: Color,
+
+    /// Matches for the current search
+    pub search_match: Style,
+
+    /// The selected item in the navigation tree
+    pub tree_highlight: Style,
+
+    /// Colours used for page content
+    pub page: bbml::Theme,
+}
+
+impl Theme {
+    /// For terminals with a dark background
+    pub const DARK: Theme = Theme {
+        border_focused: Color::Blue,
+        border_unfocused: Color::DarkGray,
+        error: Color::Red,
+        warning: Color::Yellow,
+        success: Color::Green,
+        accent: Color::Cyan,
+        progress: Color::Blue,
+        header: Color::Yellow,
+        badge: Color::Yellow,
+        hint: Color::Gray,
+        inactive: Color::DarkGray,
+        title: Color::Blue,
+        search_match: Style::new().fg(Color::Black).bg(Color::Yellow),
+        tree_highlight: Style::new(),
+        page: bbml::Theme::DARK,
+    };
+
+    /// For terminals with a light background
+    pub const LIGHT: Theme = Theme {
+        border_focused: Color::Blue,
+        border_unfocused: Color::Gray,
+        error: Color::Red,
+        warning: Color::Magenta,
+        success: Color::Green,
+        accent: Color::Blue,
+        progress: Color::Blue,
+        header: Color::Magenta,
+        badge: Color::Magenta,
+        hint: Color::DarkGray,
+        inactive: Color::Gray,
+        title: Color::Blue,
+        search_match: Style::new().fg(Color::Black).bg(Color::Cyan),
+        tree_highlight: Style::new(),
+        page: bbml::Theme::LIGHT,
+    };
+
+    /// Bright colours, and a highlighted selection
+    pub const HIGH_CONTRAST: Theme = Theme {
+        border_focused: Color::White,
+        border_unfocused: Color::Gray,
+        error: Color::LightRed,
+        warning: Color::LightYellow,
+        success: Color::LightGreen,
+        accent: Color::LightCyan,
+        progress: Color::LightBlue,
+        header: Color::LightYellow,
+        badge: Color::LightYellow,
+        hint: Color::White,
+        inactive: Color::Gray,
+        title: Color::LightBlue,
+        search_match: Style::new().fg(Color::Black).bg(Color::LightYellow),
+        tree_highlight: Style::new().add_modifier(Modifier::REVERSED),
+        page: bbml::Theme::HIGH_CONTRAST,
+    };
+}
+
+pub fn error_text(theme: &Theme, t: impl Into<Text<'static>>) -> Text<'static> {
     let mut t = t.into();
-    t.patch_style(Style::default().fg(Color::Red));
+    t.patch_style(Style::default().fg(theme.error));
     t
 }
+
+/// The style of a pane's border, depending on whether it's focused
+pub fn pane_border(theme: &Theme, focused: bool) -> Style {
+    if focused {
+        Style::default().fg(theme.border_focused)
+    } else {
+        Style::default().fg(theme.border_unfocused)
+    }
+}
+
+/// Highlight every occurrence of `query` in the given text, ignoring (ASCII) case.
+/// Occurrences split across multiple spans aren't highlighted.
+pub fn highlight_matches(theme: &Theme, text: &Text<'static>, query: &str) -> Text<'static> {
+    if query.is_empty() {
+        return text.clone();
+    }
+    let query = query.to_ascii_lowercase();
+    let highlight = theme.search_match;
+
+    let lines = text
+        .lines
+        .iter()
+        .map(|line| {
+            let mut spans = vec![];
+            for span in line.spans.iter() {
+                // this has the same byte offsets as the original
+                let lower = span.content.to_ascii_lowercase();
+                let mut last = 0;
+                for (start, _) in lower.match_indices(&query) {
+                    let end = start + query.len();
+                    if start > last {
+                        spans.push(Span::styled(
+                            span.content[last..start].to_string(),
+                            span.style,
+                        ));
+                    }
+                    spans.push(Span::styled(
+                        span.content[start..end].to_string(),
+                        span.style.patch(highlight),
+                    ));
+                    last = end;
+                }
+                if last == 0 {
+                    spans.push(span.clone());
+                } else if last < span.content.len() {
+                    spans.push(Span::styled(span.content[last..].to_string(), span.style));
+                }
+            }
+            Line {
+                spans,
+                alignment: line.alignment,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Text::from(lines)
+}
@@ -1,17 +1,28 @@
 use crate::Screen;
 use anyhow::Result;
-use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
+use crossterm::event::{
+    DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+};
 use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
 use ratatui::backend::Backend;
 use ratatui::Terminal;
 
 use std::io;
 use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether we captured the mouse in [`init`], so [`reset`] knows to release it
+static MOUSE_CAPTURED: AtomicBool = AtomicBool::new(false);
 
 /// Initialize the terminal interface.
-pub fn init<B: Backend>(terminal: &mut Terminal<B>) -> Result<()> {
+/// If `capture_mouse` is false, the terminal keeps handling the mouse itself, so text can be selected and copied.
+pub fn init<B: Backend>(terminal: &mut Terminal<B>, capture_mouse: bool) -> Result<()> {
     terminal::enable_raw_mode()?;
-    crossterm::execute!(io::stderr(), EnterAlternateScreen, EnableMouseCapture)?;
+    crossterm::execute!(io::stderr(), EnterAlternateScreen, EnableBracketedPaste)?;
+    if capture_mouse {
+        crossterm::execute!(io::stderr(), EnableMouseCapture)?;
+        MOUSE_CAPTURED.store(true, Ordering::Relaxed);
+    }
 
     // Define a custom panic hook to reset the terminal properties.
     // This way, you won't have your terminal messed up if an unexpected error happens.
@@ -35,7 +46,10 @@ pub fn draw<B: Backend>(terminal: &mut Terminal<B>, app: &mut dyn Screen) -> Res
 /// Resets the terminal interface.
 pub fn reset() -> Result<()> {
     terminal::disable_raw_mode()?;
-    crossterm::execute!(io::stderr(), LeaveAlternateScreen, DisableMouseCapture)?;
+    crossterm::execute!(io::stderr(), LeaveAlternateScreen, DisableBracketedPaste)?;
+    if MOUSE_CAPTURED.swap(false, Ordering::Relaxed) {
+        crossterm::execute!(io::stderr(), DisableMouseCapture)?;
+    }
     Ok(())
 }
 